@@ -0,0 +1,376 @@
+use super::xml::Element;
+use super::{decode, Error, Result};
+use crate::win::reg::DataType;
+use std::path::Path;
+
+/// `decimal` 要素の `maxValue` の既定値
+const DEFAULT_MAX: u32 = 9999;
+
+/// ポリシーを適用する対象 (`class` 属性)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PolicyClass {
+    /// HKLM
+    Machine,
+    /// HKCU
+    User,
+    Both,
+}
+
+impl PolicyClass {
+    fn parse(value: &str) -> Result<Self> {
+        match value {
+            "Machine" => Ok(Self::Machine),
+            "User" => Ok(Self::User),
+            "Both" => Ok(Self::Both),
+            _ => Err(Error::Invalid(format!("class ({value})"))),
+        }
+    }
+}
+
+/// レジストリに書き込む値 (`<decimal>` や `<delete>` など)
+#[derive(Debug, Clone, PartialEq)]
+pub enum PolicyValue {
+    Decimal(u32),
+    LongDecimal(u64),
+    String(String),
+    /// 値を削除する
+    Delete,
+}
+
+impl PolicyValue {
+    /// `<enabledValue>` などの中の値
+    fn parse(container: &Element) -> Result<Self> {
+        let e = container
+            .children
+            .first()
+            .ok_or_else(|| Error::Missing(format!("value of <{}>", container.name)))?;
+        let ret = match e.name.as_str() {
+            "decimal" => Self::Decimal(parse_number(e.required("value")?)?),
+            "longDecimal" => Self::LongDecimal(parse_number(e.required("value")?)?),
+            "string" => Self::String(e.text.clone()),
+            "delete" => Self::Delete,
+            name => return Err(Error::Invalid(format!("value type ({name})"))),
+        };
+        Ok(ret)
+    }
+
+    /// 値の型 (`Delete` は `None`)
+    pub fn data_type(&self) -> Option<DataType> {
+        match self {
+            Self::Decimal(_) => Some(DataType::DWord),
+            Self::LongDecimal(_) => Some(DataType::QWord),
+            Self::String(_) => Some(DataType::String),
+            Self::Delete => None,
+        }
+    }
+
+    /// 候補の値 (`Delete` は `None`)
+    pub fn text(&self) -> Option<String> {
+        match self {
+            Self::Decimal(v) => Some(v.to_string()),
+            Self::LongDecimal(v) => Some(v.to_string()),
+            Self::String(v) => Some(v.clone()),
+            Self::Delete => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ElementKind {
+    /// 表示名 (`$(string.ID)`) と値の組
+    Enum(Vec<(String, PolicyValue)>),
+    Decimal {
+        min: u32,
+        max: u32,
+        /// REG_SZ として書き込む
+        store_as_text: bool,
+    },
+    Boolean {
+        on: PolicyValue,
+        off: PolicyValue,
+    },
+    /// 取り込まない種類 (`text` や `list` など)
+    Other(String),
+}
+
+/// ポリシーの `<elements>` の中の 1 つの値
+#[derive(Debug, Clone, PartialEq)]
+pub struct PolicyElement {
+    pub id: String,
+    /// 省略した場合はポリシーのキー
+    pub key: Option<String>,
+    pub value_name: String,
+    pub kind: ElementKind,
+}
+
+impl PolicyElement {
+    fn parse(e: &Element) -> Result<Self> {
+        let kind = match e.name.as_str() {
+            "enum" => {
+                let mut items = Vec::new();
+                for item in e.children_named("item") {
+                    warn_lists(item, e.attr("id").unwrap_or_default(), &["valueList"]);
+                    let value = item
+                        .child("value")
+                        .ok_or_else(|| Error::Missing("value of <item>".into()))?;
+                    items.push((
+                        item.required("displayName")?.to_string(),
+                        PolicyValue::parse(value)?,
+                    ));
+                }
+                ElementKind::Enum(items)
+            }
+            "decimal" => ElementKind::Decimal {
+                min: e
+                    .attr("minValue")
+                    .map(parse_number)
+                    .transpose()?
+                    .unwrap_or(0),
+                max: e
+                    .attr("maxValue")
+                    .map(parse_number)
+                    .transpose()?
+                    .unwrap_or(DEFAULT_MAX),
+                store_as_text: e.attr("storeAsText") == Some("true"),
+            },
+            "boolean" => {
+                warn_lists(
+                    e,
+                    e.attr("id").unwrap_or_default(),
+                    &["trueList", "falseList"],
+                );
+                let value = |name: &str, default: u32| match e.child(name) {
+                    Some(x) => PolicyValue::parse(x),
+                    None => Ok(PolicyValue::Decimal(default)),
+                };
+                ElementKind::Boolean {
+                    on: value("trueValue", 1)?,
+                    off: value("falseValue", 0)?,
+                }
+            }
+            name => ElementKind::Other(name.to_string()),
+        };
+        Ok(Self {
+            id: e.required("id")?.to_string(),
+            key: e.attr("key").map(str::to_string),
+            value_name: e.attr("valueName").unwrap_or_default().to_string(),
+            kind,
+        })
+    }
+}
+
+/// ADMX の `<policy>`
+#[derive(Debug, Clone, PartialEq)]
+pub struct PolicyDefinition {
+    pub name: String,
+    pub class: PolicyClass,
+    /// `$(string.ID)` の形の参照
+    pub display_name: String,
+    pub explain_text: Option<String>,
+    /// `$(presentation.ID)` の形の参照
+    pub presentation: Option<String>,
+    pub category: Option<String>,
+    pub key: String,
+    pub value_name: Option<String>,
+    pub enabled_value: Option<PolicyValue>,
+    pub disabled_value: Option<PolicyValue>,
+    pub elements: Vec<PolicyElement>,
+}
+
+impl PolicyDefinition {
+    fn parse(e: &Element) -> Result<Self> {
+        warn_lists(
+            e,
+            e.attr("name").unwrap_or_default(),
+            &["enabledList", "disabledList"],
+        );
+        let value = |name: &str| e.child(name).map(PolicyValue::parse).transpose();
+        let elements = match e.child("elements") {
+            Some(x) => x
+                .children
+                .iter()
+                .map(PolicyElement::parse)
+                .collect::<Result<_>>()?,
+            None => Vec::new(),
+        };
+        Ok(Self {
+            name: e.required("name")?.to_string(),
+            class: PolicyClass::parse(e.required("class")?)?,
+            display_name: e.required("displayName")?.to_string(),
+            explain_text: e.attr("explainText").map(str::to_string),
+            presentation: e.attr("presentation").map(str::to_string),
+            category: e
+                .child("parentCategory")
+                .and_then(|x| x.attr("ref"))
+                .map(str::to_string),
+            key: e.required("key")?.to_string(),
+            value_name: e.attr("valueName").map(str::to_string),
+            enabled_value: value("enabledValue")?,
+            disabled_value: value("disabledValue")?,
+            elements,
+        })
+    }
+}
+
+/// ADMX ファイル (ポリシーの定義)
+#[derive(Debug, Clone, Default)]
+pub struct PolicyFile {
+    pub policies: Vec<PolicyDefinition>,
+    /// 分類の名前と表示名 (`$(string.ID)`)
+    pub categories: Vec<(String, String)>,
+}
+
+impl PolicyFile {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let bytes = std::fs::read(path)?;
+        Self::parse(&decode(&bytes))
+    }
+
+    pub fn parse(text: &str) -> Result<Self> {
+        let root = Element::parse(text)?;
+        let mut ret = Self::default();
+        if let Some(categories) = root.child("categories") {
+            for c in categories.children_named("category") {
+                ret.categories.push((
+                    c.required("name")?.to_string(),
+                    c.required("displayName")?.to_string(),
+                ));
+            }
+        }
+        if let Some(policies) = root.child("policies") {
+            for p in policies.children_named("policy") {
+                ret.policies.push(PolicyDefinition::parse(p)?);
+            }
+        }
+        Ok(ret)
+    }
+
+    pub fn find(&self, name: &str) -> Option<&PolicyDefinition> {
+        self.policies.iter().find(|x| x.name == name)
+    }
+
+    /// 分類の表示名の参照 (他のファイルの分類は `None`)
+    pub fn category(&self, name: &str) -> Option<&str> {
+        self.categories
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+/// 複数の値をまとめて書き込む定義 (`enabledList` や `valueList` など) は取り込まないため、ログに残す
+fn warn_lists(e: &Element, owner: &str, names: &[&str]) {
+    for name in names.iter().filter(|x| e.child(x).is_some()) {
+        tracing::warn!(owner, "<{name}> is not imported");
+    }
+}
+
+fn parse_number<T: std::str::FromStr>(value: &str) -> Result<T> {
+    value
+        .trim()
+        .parse()
+        .map_err(|_| Error::Invalid(format!("number ({value})")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> PolicyFile {
+        PolicyFile::parse(include_str!("testdata/sample.admx")).unwrap()
+    }
+
+    #[test]
+    fn parses_enabled_and_disabled_values() {
+        let file = sample();
+        let policy = file.find("AllowNewsAndInterests").unwrap();
+        assert_eq!(policy.class, PolicyClass::Machine);
+        assert_eq!(policy.key, r"Software\Policies\Microsoft\Dsh");
+        assert_eq!(policy.value_name.as_deref(), Some("AllowNewsAndInterests"));
+        assert_eq!(policy.enabled_value, Some(PolicyValue::Decimal(0)));
+        assert_eq!(policy.disabled_value, Some(PolicyValue::Decimal(1)));
+        assert_eq!(policy.category.as_deref(), Some("Widgets"));
+        assert_eq!(file.category("Widgets"), Some("$(string.Widgets)"));
+        assert_eq!(file.category("windows:Other"), None);
+    }
+
+    #[test]
+    fn parses_elements() {
+        let file = sample();
+        let policy = file.find("SearchOptions").unwrap();
+        assert_eq!(policy.class, PolicyClass::Both);
+        assert_eq!(policy.value_name, None);
+
+        let kinds = policy
+            .elements
+            .iter()
+            .map(|x| (x.id.as_str(), &x.kind))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            [
+                (
+                    "Mode",
+                    &ElementKind::Enum(vec![
+                        ("$(string.Mode_Off)".into(), PolicyValue::Decimal(0)),
+                        ("$(string.Mode_Web)".into(), PolicyValue::Decimal(1)),
+                    ])
+                ),
+                (
+                    "Count",
+                    &ElementKind::Decimal {
+                        min: 1,
+                        max: 30,
+                        store_as_text: false
+                    }
+                ),
+                (
+                    "Limit",
+                    &ElementKind::Decimal {
+                        min: 0,
+                        max: DEFAULT_MAX,
+                        store_as_text: true
+                    }
+                ),
+                (
+                    "Suggest",
+                    &ElementKind::Boolean {
+                        on: PolicyValue::String("yes".into()),
+                        off: PolicyValue::Delete
+                    }
+                ),
+                ("Url", &ElementKind::Other("text".into())),
+            ]
+        );
+        assert_eq!(
+            policy.elements[2].key.as_deref(),
+            Some(r"Software\Policies\Test\Limit")
+        );
+    }
+
+    #[test]
+    fn skips_value_lists() {
+        let file = sample();
+        let policy = file.find("ListOnly").unwrap();
+        assert_eq!(policy.value_name, None);
+        assert_eq!(policy.enabled_value, None);
+        assert!(policy.elements.is_empty());
+    }
+
+    #[test]
+    fn rejects_invalid_definitions() {
+        let text = include_str!("testdata/sample.admx");
+        assert!(matches!(
+            PolicyFile::parse(&text.replace(r#"class="User""#, r#"class="Any""#)),
+            Err(Error::Invalid(_))
+        ));
+        assert!(matches!(
+            PolicyFile::parse(&text.replace(r#"<decimal value="1" />"#, "<decimal />")),
+            Err(Error::Missing(_))
+        ));
+        assert!(matches!(
+            PolicyFile::parse("<a><b></a>"),
+            Err(Error::Xml(_))
+        ));
+    }
+}
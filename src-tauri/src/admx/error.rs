@@ -0,0 +1,47 @@
+use std::io;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    Xml(quick_xml::Error),
+    /// 必要な要素や属性が無い
+    Missing(String),
+    /// 属性の値を解釈できない
+    Invalid(String),
+    /// ADMX に無いポリシー
+    UnknownPolicy(String),
+}
+
+impl std::error::Error for Error {}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(e) => e.fmt(f),
+            Self::Xml(e) => e.fmt(f),
+            Self::Missing(v) => write!(f, "Missing {v}"),
+            Self::Invalid(v) => write!(f, "Invalid {v}"),
+            Self::UnknownPolicy(v) => write!(f, "Unknown policy ({v})"),
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(value: io::Error) -> Self {
+        Error::Io(value)
+    }
+}
+
+impl From<quick_xml::Error> for Error {
+    fn from(value: quick_xml::Error) -> Self {
+        Error::Xml(value)
+    }
+}
+
+impl From<quick_xml::events::attributes::AttrError> for Error {
+    fn from(value: quick_xml::events::attributes::AttrError) -> Self {
+        Error::Xml(value.into())
+    }
+}
@@ -0,0 +1,362 @@
+use super::definition::{ElementKind, PolicyClass, PolicyDefinition, PolicyElement, PolicyValue};
+use super::{Error, PolicyFile, Result, StringTable};
+use crate::command::{OptionCandidate, RegistryEditOption, WindowsDefault};
+use crate::domain::ValueDomain;
+use crate::win::reg::{DataType, RegDef, View};
+
+/// ADMX 内に分類の定義が無い場合の分類
+const DEFAULT_CATEGORY: &str = "ポリシー";
+const NOT_CONFIGURED: &str = "未構成";
+
+/// `names` のポリシー (空の場合はすべて) を設定項目にする
+/// ID は `first_id` から順に振る
+pub fn import(
+    file: &PolicyFile,
+    strings: &StringTable,
+    names: &[&str],
+    first_id: u64,
+) -> Result<Vec<RegistryEditOption>> {
+    let policies = if names.is_empty() {
+        file.policies.iter().collect()
+    } else {
+        names
+            .iter()
+            .map(|name| {
+                file.find(name)
+                    .ok_or_else(|| Error::UnknownPolicy(name.to_string()))
+            })
+            .collect::<Result<Vec<_>>>()?
+    };
+
+    let mut importer = Importer {
+        file,
+        strings,
+        next_id: first_id,
+        buffer: Vec::new(),
+    };
+    for policy in policies {
+        match policy.class {
+            PolicyClass::Machine | PolicyClass::User => importer.policy(policy, policy.class, ""),
+            PolicyClass::Both => {
+                importer.policy(policy, PolicyClass::Machine, " (コンピューター)");
+                importer.policy(policy, PolicyClass::User, " (ユーザー)");
+            }
+        }
+    }
+    Ok(importer.buffer)
+}
+
+struct Importer<'a> {
+    file: &'a PolicyFile,
+    strings: &'a StringTable,
+    next_id: u64,
+    buffer: Vec<RegistryEditOption>,
+}
+
+impl Importer<'_> {
+    /// `class` は `Machine` か `User`
+    fn policy(&mut self, policy: &PolicyDefinition, class: PolicyClass, suffix: &str) {
+        let label = self
+            .strings
+            .resolve(&policy.display_name)
+            .unwrap_or(&policy.name);
+        let label = format!("{label}{suffix}");
+        let category = policy
+            .category
+            .as_deref()
+            .and_then(|x| self.file.category(x))
+            .and_then(|x| self.strings.resolve(x))
+            .unwrap_or(DEFAULT_CATEGORY)
+            .to_string();
+
+        if let Some(value_name) = &policy.value_name {
+            let enabled = policy
+                .enabled_value
+                .clone()
+                .unwrap_or(PolicyValue::Decimal(1));
+            let disabled = policy.disabled_value.clone().unwrap_or(PolicyValue::Delete);
+            let data_type = enabled
+                .data_type()
+                .or(disabled.data_type())
+                .unwrap_or(DataType::DWord);
+            let values = with_not_configured(vec![
+                candidate(&enabled, "有効"),
+                candidate(&disabled, "無効"),
+            ]);
+            let def = reg_def(class, &policy.key, value_name, data_type);
+            self.push(
+                label.clone(),
+                &category,
+                policy,
+                def,
+                values,
+                Default::default(),
+            );
+        } else if policy.elements.is_empty() {
+            tracing::debug!(policy = %policy.name, "no value to import");
+        }
+
+        for element in &policy.elements {
+            let element_label = policy
+                .presentation
+                .as_deref()
+                .and_then(|x| self.strings.label(x, &element.id))
+                .unwrap_or(&element.id);
+            let label = format!("{label} - {element_label}");
+            let Some((data_type, values, domain)) = self.element(element) else {
+                tracing::debug!(policy = %policy.name, element = %element.id, "skip element");
+                continue;
+            };
+            let key = element.key.as_deref().unwrap_or(&policy.key);
+            let def = reg_def(class, key, &element.value_name, data_type);
+            self.push(label, &category, policy, def, values, domain);
+        }
+    }
+
+    fn element(
+        &self,
+        element: &PolicyElement,
+    ) -> Option<(DataType, Vec<OptionCandidate>, ValueDomain)> {
+        match &element.kind {
+            ElementKind::Enum(items) => {
+                let data_type = items.iter().find_map(|(_, v)| v.data_type())?;
+                let values = items
+                    .iter()
+                    .map(|(name, value)| {
+                        let desc = self.strings.resolve(name).unwrap_or(name);
+                        candidate(value, desc)
+                    })
+                    .collect();
+                Some((
+                    data_type,
+                    with_not_configured(values),
+                    ValueDomain::Candidates,
+                ))
+            }
+            ElementKind::Decimal {
+                min,
+                max,
+                store_as_text,
+            } => {
+                let (data_type, domain) = if *store_as_text {
+                    (DataType::String, ValueDomain::pattern("[0-9]+"))
+                } else {
+                    (
+                        DataType::DWord,
+                        ValueDomain::range(*min as u64, *max as u64),
+                    )
+                };
+                Some((data_type, with_not_configured(Vec::new()), domain))
+            }
+            ElementKind::Boolean { on, off } => {
+                let data_type = on.data_type().or(off.data_type())?;
+                let values =
+                    with_not_configured(vec![candidate(on, "オン"), candidate(off, "オフ")]);
+                Some((data_type, values, ValueDomain::Candidates))
+            }
+            ElementKind::Other(_) => None,
+        }
+    }
+
+    fn push(
+        &mut self,
+        label: String,
+        category: &str,
+        policy: &PolicyDefinition,
+        def: RegDef,
+        values: Vec<OptionCandidate>,
+        domain: ValueDomain,
+    ) {
+        self.buffer.push(RegistryEditOption {
+            id: self.next_id,
+            label,
+            category: category.to_string(),
+            tags: vec![policy.name.clone()],
+            def,
+            values,
+            domain,
+            bits: None,
+            windows_default: Some(WindowsDefault::Absent),
+            requires: Vec::new(),
+            conflicts_with: Vec::new(),
+            policies: Vec::new(),
+        });
+        self.next_id += 1;
+    }
+}
+
+fn candidate(value: &PolicyValue, description: &str) -> OptionCandidate {
+    match value.text() {
+        Some(v) => OptionCandidate::new(v, description),
+        None => OptionCandidate::absent(description),
+    }
+}
+
+/// 未構成 (値が無い状態) の候補を加える
+/// 値を削除する候補は未構成と区別できないため、その候補の説明に含める
+fn with_not_configured(mut values: Vec<OptionCandidate>) -> Vec<OptionCandidate> {
+    match values.iter_mut().find(|x| x.absent) {
+        Some(x) => x.description = format!("{} / {NOT_CONFIGURED}", x.description),
+        None => values.push(OptionCandidate::absent(NOT_CONFIGURED)),
+    }
+    values
+}
+
+fn reg_def(class: PolicyClass, key: &str, value_name: &str, data_type: DataType) -> RegDef {
+    let def = match class {
+        PolicyClass::User => RegDef::user(key, value_name, data_type),
+        _ => RegDef::hklm(key, value_name, data_type),
+    };
+    def.in_view(View::Bit64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> (PolicyFile, StringTable) {
+        (
+            PolicyFile::parse(include_str!("testdata/sample.admx")).unwrap(),
+            StringTable::parse(include_str!("testdata/sample.adml")).unwrap(),
+        )
+    }
+
+    fn values(opt: &RegistryEditOption) -> Vec<(Option<&str>, &str)> {
+        opt.values
+            .iter()
+            .map(|x| {
+                let value = (!x.absent).then_some(x.value.as_str());
+                (value, x.description.as_str())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn imports_enabled_and_disabled_values() {
+        let (file, strings) = sample();
+        let options = import(&file, &strings, &["AllowNewsAndInterests"], 100).unwrap();
+        assert_eq!(options.len(), 1);
+
+        let opt = &options[0];
+        assert_eq!(opt.id, 100);
+        assert_eq!(opt.label, "ウィジェットを許可する");
+        assert_eq!(opt.category, "ウィジェット");
+        assert_eq!(opt.tags, ["AllowNewsAndInterests"]);
+        assert_eq!(
+            opt.def.to_string(),
+            r"HKLM\Software\Policies\Microsoft\Dsh\AllowNewsAndInterests"
+        );
+        assert_eq!(opt.def.data_type, DataType::DWord);
+        assert_eq!(
+            values(opt),
+            [
+                (Some("0"), "有効"),
+                (Some("1"), "無効"),
+                (None, NOT_CONFIGURED)
+            ]
+        );
+        assert_eq!(opt.windows_default, Some(WindowsDefault::Absent));
+    }
+
+    #[test]
+    fn imports_elements_for_both_classes() {
+        let (file, strings) = sample();
+        let options = import(&file, &strings, &["SearchOptions"], 1).unwrap();
+        let labels = options
+            .iter()
+            .map(|x| (x.id, x.label.as_str(), x.def.to_string()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            labels,
+            [
+                (
+                    1,
+                    "検索 & 候補 (コンピューター) - モード",
+                    r"HKLM\Software\Policies\Test\Search\Mode".to_string()
+                ),
+                (
+                    2,
+                    "検索 & 候補 (コンピューター) - 回数",
+                    r"HKLM\Software\Policies\Test\Search\Count".to_string()
+                ),
+                (
+                    3,
+                    "検索 & 候補 (コンピューター) - Limit",
+                    r"HKLM\Software\Policies\Test\Limit\Limit".to_string()
+                ),
+                (
+                    4,
+                    "検索 & 候補 (コンピューター) - 候補を表示する",
+                    r"HKLM\Software\Policies\Test\Search\Suggest".to_string()
+                ),
+                (
+                    5,
+                    "検索 & 候補 (ユーザー) - モード",
+                    r"HKCU\Software\Policies\Test\Search\Mode".to_string()
+                ),
+                (
+                    6,
+                    "検索 & 候補 (ユーザー) - 回数",
+                    r"HKCU\Software\Policies\Test\Search\Count".to_string()
+                ),
+                (
+                    7,
+                    "検索 & 候補 (ユーザー) - Limit",
+                    r"HKCU\Software\Policies\Test\Limit\Limit".to_string()
+                ),
+                (
+                    8,
+                    "検索 & 候補 (ユーザー) - 候補を表示する",
+                    r"HKCU\Software\Policies\Test\Search\Suggest".to_string()
+                ),
+            ]
+        );
+        assert!(options.iter().all(|x| x.category == DEFAULT_CATEGORY));
+
+        // enum
+        assert_eq!(
+            values(&options[0]),
+            [
+                (Some("0"), "オフ"),
+                (Some("1"), "Web も検索する"),
+                (None, NOT_CONFIGURED)
+            ]
+        );
+        assert!(matches!(options[0].domain, ValueDomain::Candidates));
+        // decimal
+        assert_eq!(options[1].def.data_type, DataType::DWord);
+        assert!(matches!(
+            options[1].domain,
+            ValueDomain::Range { min: 1, max: 30 }
+        ));
+        assert_eq!(values(&options[1]), [(None, NOT_CONFIGURED)]);
+        assert_eq!(options[2].def.data_type, DataType::String);
+        assert!(matches!(options[2].domain, ValueDomain::Pattern(_)));
+        // boolean (値の削除は未構成と区別できない)
+        assert_eq!(options[3].def.data_type, DataType::String);
+        assert_eq!(
+            values(&options[3]),
+            [(Some("yes"), "オン"), (None, "オフ / 未構成")]
+        );
+    }
+
+    #[test]
+    fn skips_policies_without_values() {
+        let (file, strings) = sample();
+        let options = import(&file, &strings, &[], 1).unwrap();
+        assert_eq!(options.len(), 9);
+        assert!(options.iter().all(|x| x.tags != ["ListOnly"]));
+        assert!(import(&file, &strings, &["ListOnly"], 1)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn rejects_unknown_policies() {
+        let (file, strings) = sample();
+        assert!(matches!(
+            import(&file, &strings, &["AllowNewsAndInterests", "Nope"], 1),
+            Err(Error::UnknownPolicy(name)) if name == "Nope"
+        ));
+    }
+}
@@ -0,0 +1,30 @@
+//! 管理用テンプレート (ADMX/ADML) のポリシーを設定項目として取り込む
+//!
+//! ADMX はポリシーのレジストリ上の場所と値を、ADML は言語ごとの表示名を持つ
+
+mod definition;
+mod error;
+mod import;
+mod strings;
+mod xml;
+
+pub use definition::{
+    ElementKind, PolicyClass, PolicyDefinition, PolicyElement, PolicyFile, PolicyValue,
+};
+pub use error::{Error, Result};
+pub use import::import;
+pub use strings::StringTable;
+
+/// ADMX/ADML は通常 UTF-8 だが、UTF-16LE (BOM 付き) のものもある
+fn decode(bytes: &[u8]) -> String {
+    if let Some(body) = bytes.strip_prefix(&[0xFF, 0xFE]) {
+        let utf16: Vec<u16> = body
+            .chunks_exact(2)
+            .map(|x| u16::from_le_bytes([x[0], x[1]]))
+            .collect();
+        String::from_utf16_lossy(&utf16)
+    } else {
+        let body = bytes.strip_prefix(&[0xEF, 0xBB, 0xBF]).unwrap_or(bytes);
+        String::from_utf8_lossy(body).into_owned()
+    }
+}
@@ -0,0 +1,71 @@
+use super::xml::Element;
+use super::{decode, Result};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// ADML ファイル (ポリシーの表示名や説明などの言語ごとの文字列)
+#[derive(Debug, Clone, Default)]
+pub struct StringTable {
+    strings: HashMap<String, String>,
+    /// プレゼンテーションの ID ごとの、要素の ID とラベル
+    presentations: HashMap<String, Vec<(String, String)>>,
+}
+
+impl StringTable {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let bytes = std::fs::read(path)?;
+        Self::parse(&decode(&bytes))
+    }
+
+    pub fn parse(text: &str) -> Result<Self> {
+        let root = Element::parse(text)?;
+        let mut ret = Self::default();
+        let Some(resources) = root.child("resources") else {
+            return Ok(ret);
+        };
+
+        if let Some(table) = resources.child("stringTable") {
+            for s in table.children_named("string") {
+                ret.strings
+                    .insert(s.required("id")?.to_string(), s.text.clone());
+            }
+        }
+        if let Some(table) = resources.child("presentationTable") {
+            for p in table.children_named("presentation") {
+                // textBox などはラベルを子要素の <label> に持つ
+                let labels = p
+                    .children
+                    .iter()
+                    .filter_map(|x| {
+                        let text = x.child("label").map_or(x.text.as_str(), |l| &l.text);
+                        Some((x.attr("refId")?.to_string(), text.to_string()))
+                    })
+                    .collect();
+                ret.presentations
+                    .insert(p.required("id")?.to_string(), labels);
+            }
+        }
+        Ok(ret)
+    }
+
+    /// `$(string.ID)` の文字列 (見つからない場合は `None`)
+    pub fn resolve(&self, reference: &str) -> Option<&str> {
+        let id = reference
+            .strip_prefix("$(string.")
+            .and_then(|x| x.strip_suffix(')'))?;
+        self.strings.get(id).map(String::as_str)
+    }
+
+    /// `$(presentation.ID)` の中の要素のラベル (末尾の `:` は除く)
+    pub fn label(&self, presentation: &str, ref_id: &str) -> Option<&str> {
+        let id = presentation
+            .strip_prefix("$(presentation.")
+            .and_then(|x| x.strip_suffix(')'))?;
+        self.presentations
+            .get(id)?
+            .iter()
+            .find(|(r, _)| r == ref_id)
+            .map(|(_, v)| v.trim().trim_end_matches([':', '：']).trim())
+            .filter(|x| !x.is_empty())
+    }
+}
@@ -0,0 +1,96 @@
+//! ADMX/ADML を読むための簡単な要素の木 (名前空間の接頭辞は無視する)
+
+use super::{Error, Result};
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+
+#[derive(Debug, Clone, Default)]
+pub struct Element {
+    pub name: String,
+    pub attrs: Vec<(String, String)>,
+    pub children: Vec<Element>,
+    pub text: String,
+}
+
+impl Element {
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut reader = Reader::from_str(text);
+        reader.config_mut().trim_text(true);
+
+        // 先頭は文書全体を入れる仮の要素
+        let mut stack = vec![Element::default()];
+        loop {
+            match reader.read_event()? {
+                Event::Start(e) => stack.push(start(&e)?),
+                Event::Empty(e) => {
+                    let element = start(&e)?;
+                    push_child(&mut stack, element);
+                }
+                Event::End(_) => {
+                    let element = stack.pop().ok_or_else(|| Error::Invalid("XML".into()))?;
+                    push_child(&mut stack, element);
+                }
+                Event::Text(e) => {
+                    if let Some(x) = stack.last_mut() {
+                        x.text.push_str(&e.unescape()?);
+                    }
+                }
+                Event::CData(e) => {
+                    if let Some(x) = stack.last_mut() {
+                        x.text.push_str(&String::from_utf8_lossy(&e.into_inner()));
+                    }
+                }
+                Event::Eof => break,
+                _ => (),
+            }
+        }
+
+        let mut document = stack.pop().filter(|_| stack.is_empty());
+        document
+            .as_mut()
+            .and_then(|x| x.children.pop())
+            .ok_or_else(|| Error::Missing("root element".into()))
+    }
+
+    pub fn attr(&self, name: &str) -> Option<&str> {
+        self.attrs
+            .iter()
+            .find(|(k, _)| k == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// 必須の属性
+    pub fn required(&self, name: &str) -> Result<&str> {
+        self.attr(name)
+            .ok_or_else(|| Error::Missing(format!("{name} attribute of <{}>", self.name)))
+    }
+
+    pub fn child(&self, name: &str) -> Option<&Element> {
+        self.children.iter().find(|x| x.name == name)
+    }
+
+    pub fn children_named<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Element> {
+        self.children.iter().filter(move |x| x.name == name)
+    }
+}
+
+fn start(e: &BytesStart) -> Result<Element> {
+    let mut attrs = Vec::new();
+    for attr in e.attributes() {
+        let attr = attr?;
+        let key = String::from_utf8_lossy(attr.key.local_name().as_ref()).into_owned();
+        attrs.push((key, attr.unescape_value()?.into_owned()));
+    }
+    Ok(Element {
+        name: String::from_utf8_lossy(e.local_name().as_ref()).into_owned(),
+        attrs,
+        children: Vec::new(),
+        text: String::new(),
+    })
+}
+
+fn push_child(stack: &mut [Element], element: Element) {
+    if let Some(parent) = stack.last_mut() {
+        parent.children.push(element);
+    }
+}
@@ -0,0 +1,307 @@
+use super::{Baseline, Finding, Report, Status};
+use crate::command::RegistryEditOption;
+use crate::plan::CurrentState;
+use crate::policy;
+use crate::tweak::{self, Tweak};
+use crate::win::reg::Value;
+
+/// 期待値と現在の状態を比較して監査結果を作成する
+/// レジストリ以外の項目 (`tweaks`) は、`state` がその状態を表す場合だけ判定する
+pub fn audit(
+    options: &[RegistryEditOption],
+    tweaks: &[Box<dyn Tweak>],
+    baseline: &Baseline,
+    state: &impl CurrentState,
+    source: impl Into<String>,
+) -> Report {
+    let findings = baseline
+        .expected
+        .iter()
+        .map(|(id, expected)| {
+            let mut finding = Finding {
+                id: *id,
+                label: String::new(),
+                target: None,
+                expected: expected.clone(),
+                actual: None,
+                status: Status::NotApplicable,
+                message: None,
+            };
+
+            // ベースラインが古くなっている場合に気付けるよう、判定できない項目はエラーにする
+            let Some(opt) = options.iter().find(|x| x.id == *id) else {
+                match tweak::find(tweaks, *id).filter(|x| x.registry().is_none()) {
+                    Some(tweak) => audit_tweak(&mut finding, tweak, state.covers_tweaks()),
+                    None => {
+                        finding.status = Status::Error;
+                        finding.message = Some("Unknown option".into());
+                    }
+                }
+                return finding;
+            };
+            finding.label = opt.title();
+            finding.target = Some(opt.def.to_string());
+
+            if !state.covers(&opt.def) {
+                finding.message = Some("Not covered by the source".into());
+                return finding;
+            }
+
+            let expected = match expected
+                .as_deref()
+                .map(|x| opt.domain.parse(opt.value_type(), x))
+                .transpose()
+            {
+                Ok(v) => v,
+                Err(e) => {
+                    finding.status = Status::Error;
+                    finding.message = Some(format!("Invalid expected value: {e}"));
+                    return finding;
+                }
+            };
+
+            // ポリシーで上書きされている場合は実際に有効な値で判定する
+            let actual = match policy::resolve(opt, state) {
+                Ok(r) => {
+                    if r.masked() {
+                        finding.message = Some(format!("Overridden by {}", r.layer));
+                    }
+                    r.effective
+                }
+                Err(e) => {
+                    finding.status = Status::Error;
+                    finding.message = Some(e.to_string());
+                    return finding;
+                }
+            };
+            finding.actual = actual.as_ref().map(Value::to_string);
+
+            finding.status = match (expected, actual) {
+                (e, a) if e == a => Status::Compliant,
+                (Some(_), None) => Status::Missing,
+                _ => Status::Drifted,
+            };
+            finding
+        })
+        .collect();
+
+    Report {
+        name: baseline.name.clone(),
+        source: source.into(),
+        findings,
+    }
+}
+
+fn audit_tweak(finding: &mut Finding, tweak: &dyn Tweak, live: bool) {
+    finding.label = tweak.title();
+    finding.target = Some(tweak.describe());
+    if !live {
+        finding.message = Some("Not covered by the source".into());
+        return;
+    }
+    if let Err(e) = tweak.info().check(finding.expected.as_deref()) {
+        finding.status = Status::Error;
+        finding.message = Some(format!("Invalid expected value: {e}"));
+        return;
+    }
+
+    let current = tweak.read_state();
+    if let Some(e) = current.error {
+        finding.status = Status::Error;
+        finding.message = Some(e);
+        return;
+    }
+    finding.status = match (finding.expected.as_deref(), current.value.as_deref()) {
+        (None, None) => Status::Compliant,
+        (Some(_), None) => Status::Missing,
+        (Some(e), Some(_)) if current.candidate.as_deref() == Some(e) => Status::Compliant,
+        _ => Status::Drifted,
+    };
+    finding.actual = current.value;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command::OptionCandidate;
+    use crate::plan::Selections;
+    use crate::regfile::{RegFile, RegFileState};
+    use crate::tweak::TweakInfo;
+    use crate::watch::ValueChanged;
+    use crate::win::reg::{DataType, RegDef, Result};
+
+    const EXPORT: &str = r#"Windows Registry Editor Version 5.00
+
+[HKEY_CURRENT_USER\Software\Win11Tweaks]
+"A"=dword:00000001
+"B"=dword:00000000
+"#;
+
+    fn option(id: u64, def: RegDef) -> RegistryEditOption {
+        RegistryEditOption {
+            id,
+            label: format!("項目{id}"),
+            category: String::new(),
+            tags: Vec::new(),
+            def,
+            values: vec![
+                OptionCandidate::new("0", "オフ"),
+                OptionCandidate::new("1", "オン"),
+            ],
+            domain: Default::default(),
+            bits: None,
+            windows_default: None,
+            requires: Vec::new(),
+            conflicts_with: Vec::new(),
+            policies: Vec::new(),
+        }
+    }
+
+    fn options() -> Vec<RegistryEditOption> {
+        let key = r"Software\Win11Tweaks";
+        vec![
+            option(1, RegDef::hkcu(key, "A", DataType::DWord)),
+            option(2, RegDef::hkcu(key, "B", DataType::DWord)),
+            option(3, RegDef::hkcu(key, "C", DataType::DWord)),
+            option(4, RegDef::hklm(key, "D", DataType::DWord)),
+        ]
+    }
+
+    /// 読み取り結果が決まっているレジストリ以外の項目
+    struct FakeTweak {
+        info: TweakInfo,
+        state: ValueChanged,
+    }
+
+    impl Tweak for FakeTweak {
+        fn info(&self) -> &TweakInfo {
+            &self.info
+        }
+
+        fn read_state(&self) -> ValueChanged {
+            self.state.clone()
+        }
+
+        fn apply(&self, _: Option<&str>) -> tweak::Result<()> {
+            unreachable!()
+        }
+
+        fn describe(&self) -> String {
+            format!("Fake: {}", self.info.id)
+        }
+    }
+
+    fn tweaks() -> Vec<Box<dyn Tweak>> {
+        let tweak = |id, state| -> Box<dyn Tweak> {
+            let info = TweakInfo::new(
+                id,
+                "",
+                "",
+                vec![
+                    OptionCandidate::new("0", "オフ"),
+                    OptionCandidate::new("1", "オン"),
+                ],
+            );
+            Box::new(FakeTweak { info, state })
+        };
+        vec![
+            tweak(
+                10,
+                ValueChanged {
+                    value: Some("1".into()),
+                    candidate: Some("1".into()),
+                    ..ValueChanged::new(10)
+                },
+            ),
+            tweak(11, ValueChanged::error(11, "Access denied")),
+        ]
+    }
+
+    /// レジストリ以外の項目も現在の状態として扱う
+    struct Live(RegFileState);
+
+    impl CurrentState for Live {
+        fn covers(&self, def: &RegDef) -> bool {
+            self.0.covers(def)
+        }
+
+        fn covers_tweaks(&self) -> bool {
+            true
+        }
+
+        fn key_exists(&self, def: &RegDef) -> Result<bool> {
+            self.0.key_exists(def)
+        }
+
+        fn value(&self, def: &RegDef) -> Result<Option<Value>> {
+            self.0.value(def)
+        }
+    }
+
+    fn state() -> RegFileState {
+        RegFileState::new(RegFile::parse(EXPORT).unwrap())
+    }
+
+    fn run(expected: &[(u64, Option<&str>)], state: &impl CurrentState) -> Vec<(u64, Status)> {
+        let baseline = Baseline {
+            name: "test".into(),
+            expected: expected
+                .iter()
+                .map(|(id, v)| (*id, v.map(Into::into)))
+                .collect::<Selections>(),
+        };
+        audit(&options(), &tweaks(), &baseline, state, "export.reg")
+            .findings
+            .iter()
+            .map(|x| (x.id, x.status))
+            .collect()
+    }
+
+    #[test]
+    fn maps_registry_values_to_status() {
+        assert_eq!(
+            run(
+                &[
+                    (1, Some("1")),
+                    (2, Some("1")),
+                    (3, Some("1")),
+                    (4, Some("1")),
+                    (99, Some("1")),
+                ],
+                &state()
+            ),
+            [
+                (1, Status::Compliant),
+                (2, Status::Drifted),
+                (3, Status::Missing),
+                (4, Status::NotApplicable),
+                (99, Status::Error),
+            ]
+        );
+        assert_eq!(
+            run(&[(1, None), (2, Some("x")), (3, None)], &state()),
+            [
+                (1, Status::Drifted),
+                (2, Status::Error),
+                (3, Status::Compliant),
+            ]
+        );
+    }
+
+    #[test]
+    fn tweaks_are_audited_only_against_live_state() {
+        let expected = [(10, Some("1")), (11, Some("1"))];
+        assert_eq!(
+            run(&expected, &state()),
+            [(10, Status::NotApplicable), (11, Status::NotApplicable)]
+        );
+        assert_eq!(
+            run(&expected, &Live(state())),
+            [(10, Status::Compliant), (11, Status::Error)]
+        );
+        assert_eq!(
+            run(&[(10, Some("0")), (11, Some("2"))], &Live(state())),
+            [(10, Status::Drifted), (11, Status::Error)]
+        );
+    }
+}
@@ -0,0 +1,19 @@
+use super::Result;
+use crate::plan::Selections;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// 期待される設定値の一覧 (`null` は値が存在しないことを期待する)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Baseline {
+    #[serde(default)]
+    pub name: String,
+    pub expected: Selections,
+}
+
+impl Baseline {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let text = std::fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&text)?)
+    }
+}
@@ -0,0 +1,9 @@
+mod auditor;
+mod baseline;
+mod error;
+mod report;
+
+pub use auditor::audit;
+pub use baseline::Baseline;
+pub use error::{Error, Result};
+pub use report::{Finding, Format, Report, Status};
@@ -0,0 +1,322 @@
+use super::{Error, Result};
+use serde::Serialize;
+use std::fmt::Write;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Status {
+    Compliant,
+    Drifted,
+    Missing,
+    NotApplicable,
+    /// 判定できない (ベースラインの ID が存在しない、読み取りに失敗したなど)
+    Error,
+}
+
+impl Status {
+    pub fn str(&self) -> &'static str {
+        match self {
+            Self::Compliant => "compliant",
+            Self::Drifted => "drifted",
+            Self::Missing => "missing",
+            Self::NotApplicable => "not-applicable",
+            Self::Error => "error",
+        }
+    }
+
+    pub fn is_failure(&self) -> bool {
+        matches!(self, Self::Drifted | Self::Missing | Self::Error)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Finding {
+    pub id: u64,
+    pub label: String,
+    pub target: Option<String>,
+    /// `None` は値が存在しないことを表す
+    pub expected: Option<String>,
+    pub actual: Option<String>,
+    pub status: Status,
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Json,
+    Markdown,
+    JUnit,
+}
+
+impl std::str::FromStr for Format {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "json" => Ok(Self::Json),
+            "md" | "markdown" => Ok(Self::Markdown),
+            "junit" | "xml" => Ok(Self::JUnit),
+            _ => Err(Error::UnknownFormat(s.into())),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Report {
+    pub name: String,
+    pub source: String,
+    pub findings: Vec<Finding>,
+}
+
+impl Report {
+    pub fn count(&self, status: Status) -> usize {
+        self.findings.iter().filter(|x| x.status == status).count()
+    }
+
+    pub fn is_compliant(&self) -> bool {
+        !self.findings.iter().any(|x| x.status.is_failure())
+    }
+
+    pub fn render(&self, format: Format) -> Result<String> {
+        let ret = match format {
+            Format::Json => serde_json::to_string_pretty(self)?,
+            Format::Markdown => self.to_markdown(),
+            Format::JUnit => self.to_junit(),
+        };
+
+        Ok(ret)
+    }
+
+    fn to_markdown(&self) -> String {
+        let mut ret = String::new();
+        let _ = writeln!(ret, "# Win11 Tweaks Audit: {}", self.name);
+        let _ = writeln!(ret);
+        let _ = writeln!(ret, "Source: {}", self.source);
+        let _ = writeln!(ret);
+        for status in [
+            Status::Compliant,
+            Status::Drifted,
+            Status::Missing,
+            Status::NotApplicable,
+            Status::Error,
+        ] {
+            let _ = writeln!(ret, "- {}: {}", status.str(), self.count(status));
+        }
+        let _ = writeln!(ret);
+        let _ = writeln!(ret, "| ID | Label | Target | Expected | Actual | Status |");
+        let _ = writeln!(ret, "|---:|---|---|---|---|---|");
+        for x in self.findings.iter() {
+            let mut status = x.status.str().to_string();
+            if let Some(m) = x.message.as_ref() {
+                let _ = write!(status, " ({m})");
+            }
+            let _ = writeln!(
+                ret,
+                "| {} | {} | {} | {} | {} | {} |",
+                x.id,
+                md_escape(&x.label),
+                md_escape(x.target.as_deref().unwrap_or("")),
+                md_escape(&display_value(&x.expected)),
+                md_escape(&display_value(&x.actual)),
+                md_escape(&status),
+            );
+        }
+
+        ret
+    }
+
+    fn to_junit(&self) -> String {
+        let tests = self.findings.len();
+        let failures = self.count(Status::Drifted) + self.count(Status::Missing);
+        let errors = self.count(Status::Error);
+        let skipped = self.count(Status::NotApplicable);
+
+        let mut ret = String::new();
+        let _ = writeln!(ret, r#"<?xml version="1.0" encoding="UTF-8"?>"#);
+        let _ = writeln!(
+            ret,
+            r#"<testsuites name="win11-tweaks audit" tests="{tests}" failures="{failures}" errors="{errors}" skipped="{skipped}">"#
+        );
+        let _ = writeln!(
+            ret,
+            r#"  <testsuite name="{}" tests="{tests}" failures="{failures}" errors="{errors}" skipped="{skipped}">"#,
+            xml_escape(&self.name)
+        );
+        let _ = writeln!(
+            ret,
+            r#"    <properties><property name="source" value="{}" /></properties>"#,
+            xml_escape(&self.source)
+        );
+        for x in self.findings.iter() {
+            let name = format!("[{}] {}", x.id, x.label);
+            let _ = write!(
+                ret,
+                r#"    <testcase classname="win11-tweaks.audit" name="{}""#,
+                xml_escape(&name)
+            );
+            let detail = format!(
+                "target: {}, expected: {}, actual: {}{}",
+                x.target.as_deref().unwrap_or(""),
+                display_value(&x.expected),
+                display_value(&x.actual),
+                x.message
+                    .as_ref()
+                    .map(|m| format!(", {m}"))
+                    .unwrap_or_default()
+            );
+            match x.status {
+                Status::Compliant => {
+                    let _ = writeln!(ret, " />");
+                }
+                Status::NotApplicable => {
+                    let _ = writeln!(ret, ">");
+                    let _ = writeln!(
+                        ret,
+                        r#"      <skipped message="{}" />"#,
+                        xml_escape(&detail)
+                    );
+                    let _ = writeln!(ret, "    </testcase>");
+                }
+                Status::Drifted | Status::Missing => {
+                    let _ = writeln!(ret, ">");
+                    let _ = writeln!(
+                        ret,
+                        r#"      <failure type="{}" message="{}">{}</failure>"#,
+                        x.status.str(),
+                        x.status.str(),
+                        xml_escape(&detail)
+                    );
+                    let _ = writeln!(ret, "    </testcase>");
+                }
+                Status::Error => {
+                    let _ = writeln!(ret, ">");
+                    let _ = writeln!(
+                        ret,
+                        r#"      <error type="error" message="{}">{}</error>"#,
+                        xml_escape(x.message.as_deref().unwrap_or("error")),
+                        xml_escape(&detail)
+                    );
+                    let _ = writeln!(ret, "    </testcase>");
+                }
+            }
+        }
+        let _ = writeln!(ret, "  </testsuite>");
+        let _ = writeln!(ret, "</testsuites>");
+
+        ret
+    }
+}
+
+fn display_value(v: &Option<String>) -> String {
+    match v {
+        Some(v) => v.clone(),
+        None => "(absent)".into(),
+    }
+}
+
+fn md_escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('|', "\\|")
+}
+
+fn xml_escape(s: &str) -> String {
+    let mut ret = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => ret.push_str("&amp;"),
+            '<' => ret.push_str("&lt;"),
+            '>' => ret.push_str("&gt;"),
+            '"' => ret.push_str("&quot;"),
+            '\'' => ret.push_str("&apos;"),
+            c => ret.push(c),
+        }
+    }
+
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn finding(id: u64, status: Status, actual: Option<&str>, message: Option<&str>) -> Finding {
+        Finding {
+            id,
+            label: format!("項目 <{id}>"),
+            target: Some(r"HKCU\Software\Win11Tweaks\Value".into()),
+            expected: Some("1".into()),
+            actual: actual.map(Into::into),
+            status,
+            message: message.map(Into::into),
+        }
+    }
+
+    fn report() -> Report {
+        Report {
+            name: "R&D | baseline".into(),
+            source: "export.reg".into(),
+            findings: vec![
+                finding(1, Status::Compliant, Some("1"), None),
+                finding(2, Status::Drifted, Some("0"), None),
+                finding(3, Status::Missing, None, None),
+                finding(
+                    4,
+                    Status::NotApplicable,
+                    None,
+                    Some("Not covered by the source"),
+                ),
+                finding(5, Status::Error, None, Some("Unknown option")),
+            ],
+        }
+    }
+
+    #[test]
+    fn failures_are_drifted_missing_and_errors() {
+        let mut report = report();
+        assert!(!report.is_compliant());
+        report.findings.retain(|x| !x.status.is_failure());
+        assert!(report.is_compliant());
+        assert_eq!(report.count(Status::NotApplicable), 1);
+    }
+
+    #[test]
+    fn renders_junit() {
+        let xml = report().render(Format::JUnit).unwrap();
+        assert!(xml.contains(
+            r#"<testsuite name="R&amp;D | baseline" tests="5" failures="2" errors="1" skipped="1">"#
+        ));
+        assert!(xml
+            .contains(r#"<testcase classname="win11-tweaks.audit" name="[1] 項目 &lt;1&gt;" />"#));
+        assert!(xml.contains(
+            r#"<failure type="drifted" message="drifted">target: HKCU\Software\Win11Tweaks\Value, expected: 1, actual: 0</failure>"#
+        ));
+        assert!(xml.contains(r#"<failure type="missing" message="missing">"#));
+        assert!(xml.contains(r#"actual: (absent)</failure>"#));
+        assert!(xml.contains(r#"<skipped message="target: HKCU\Software\Win11Tweaks\Value, expected: 1, actual: (absent), Not covered by the source" />"#));
+        assert!(xml.contains(r#"<error type="error" message="Unknown option">"#));
+        assert_eq!(xml.matches("<testcase ").count(), 5);
+        assert_eq!(xml.matches("</testcase>").count(), 4);
+    }
+
+    #[test]
+    fn renders_markdown() {
+        let md = report().render(Format::Markdown).unwrap();
+        assert!(md.starts_with("# Win11 Tweaks Audit: R&D | baseline\n"));
+        for line in [
+            "- compliant: 1",
+            "- drifted: 1",
+            "- missing: 1",
+            "- not-applicable: 1",
+            "- error: 1",
+            r"| 1 | 項目 <1> | HKCU\\Software\\Win11Tweaks\\Value | 1 | 1 | compliant |",
+            r"| 3 | 項目 <3> | HKCU\\Software\\Win11Tweaks\\Value | 1 | (absent) | missing |",
+            r"| 5 | 項目 <5> | HKCU\\Software\\Win11Tweaks\\Value | 1 | (absent) | error (Unknown option) |",
+        ] {
+            assert!(md.lines().any(|x| x == line), "{line}");
+        }
+
+        let mut report = report();
+        report.findings[0].label = "a|b".into();
+        let md = report.render(Format::Markdown).unwrap();
+        assert!(md.contains(r"| 1 | a\|b |"));
+    }
+}
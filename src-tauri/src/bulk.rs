@@ -0,0 +1,253 @@
+use crate::command::RegistryEditOption;
+use crate::plan::{self, CurrentState, PlanAction, Planner, Selections};
+use crate::tweak::{self, Tweak};
+use crate::watch::{self, ValueChanged};
+use crate::win::reg::{Error, OpenKey, RegDef, Registry, Result, Root, Value, View, WriteOutcome};
+use serde::Serialize;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+/// 大文字小文字を区別しないキーの識別子 (ビューが違えば別のキー)
+type KeyId = (Root, String, View);
+
+/// 一度開いたキーを使い回して現在の状態を読み取る
+#[derive(Default)]
+pub struct KeyCache {
+    /// キーが存在しない場合は `None`
+    keys: RefCell<HashMap<KeyId, Option<Rc<OpenKey>>>>,
+}
+
+impl KeyCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn get(&self, def: &RegDef) -> Result<Option<Rc<OpenKey>>> {
+        let id = (def.root, def.sub_key.to_lowercase(), def.view);
+        if let Some(key) = self.keys.borrow().get(&id) {
+            return Ok(key.clone());
+        }
+
+        let key = match OpenKey::open(def.root(), &def.sub_key, def.view) {
+            Ok(key) => Some(Rc::new(key)),
+            Err(Error::SubkeyNotFound(_)) => None,
+            Err(e) => return Err(e),
+        };
+        self.keys.borrow_mut().insert(id, key.clone());
+        Ok(key)
+    }
+}
+
+impl CurrentState for KeyCache {
+    fn covers_tweaks(&self) -> bool {
+        true
+    }
+
+    fn key_exists(&self, def: &RegDef) -> Result<bool> {
+        Ok(self.get(def)?.is_some())
+    }
+
+    fn value(&self, def: &RegDef) -> Result<Option<Value>> {
+        match self.get(def)? {
+            Some(key) => key.find_value(&def.value_name, def.data_type),
+            None => Ok(None),
+        }
+    }
+}
+
+/// 項目ごとの書き込み結果
+#[derive(Debug, Clone, Serialize)]
+pub struct ApplyResult {
+    pub id: u64,
+    pub error: Option<String>,
+    /// 書き込みはしたが確認が必要な点 (前提を満たしていない、ポリシーで上書きされているなど)
+    pub warnings: Vec<String>,
+}
+
+impl ApplyResult {
+    fn new(id: u64, error: Option<String>) -> Self {
+        Self {
+            id,
+            error,
+            warnings: Vec::new(),
+        }
+    }
+}
+
+/// 指定された項目の現在の状態をまとめて読み取る (レジストリの項目は同じキーを一度だけ開く)
+pub fn get_values(tweaks: &[Box<dyn Tweak>], ids: &[u64]) -> Vec<ValueChanged> {
+    let cache = KeyCache::new();
+    ids.iter()
+        .map(|id| match tweak::find(tweaks, *id) {
+            Some(tweak) => match tweak.registry() {
+                Some(opt) => watch::status(opt, &cache),
+                None => tweak.read_state(),
+            },
+            None => ValueChanged::error(*id, format!("Unknown option (ID={id})")),
+        })
+        .collect()
+}
+
+/// 選択された値を `Planner` と同じ検証と順序で書き込む (同じキーは一度だけ開く)
+/// 1 つの項目が失敗しても残りの項目は書き込む
+pub fn apply_values(
+    options: &[RegistryEditOption],
+    tweaks: &[Box<dyn Tweak>],
+    selections: &Selections,
+) -> Vec<ApplyResult> {
+    let mut ret = Vec::with_capacity(selections.len());
+
+    // 値の検証に失敗した項目は計画から外す
+    let mut valid = Selections::new();
+    for (id, value) in selections.iter() {
+        let Some(tweak) = tweak::find(tweaks, *id) else {
+            ret.push(ApplyResult::new(
+                *id,
+                Some(format!("Unknown option (ID={id})")),
+            ));
+            continue;
+        };
+        let checked = match tweak.registry() {
+            Some(opt) => match value {
+                Some(v) => opt.parse_value(v).map(|_| ()),
+                None => opt.validate_absent(),
+            }
+            .map_err(|e| e.to_string()),
+            None => tweak
+                .info()
+                .check(value.as_deref())
+                .map(|_| ())
+                .map_err(|e| e.to_string()),
+        };
+        match checked {
+            Ok(()) => {
+                valid.insert(*id, value.clone());
+            }
+            Err(e) => ret.push(ApplyResult::new(*id, Some(e))),
+        }
+    }
+
+    // 他の項目と衝突する項目は外して計画し直す
+    let planner = Planner::new(options).with_tweaks(tweaks);
+    let cache = KeyCache::new();
+    let plan = loop {
+        match planner.plan(&valid, &cache) {
+            Ok(plan) => break plan,
+            Err(e @ plan::Error::Conflict(id, _)) => {
+                valid.remove(&id);
+                ret.push(ApplyResult::new(id, Some(e.to_string())));
+            }
+            Err(e) => {
+                let e = e.to_string();
+                ret.extend(
+                    valid
+                        .keys()
+                        .map(|id| ApplyResult::new(*id, Some(e.clone()))),
+                );
+                return ret;
+            }
+        }
+    };
+    // 読み取り用に開いたキーは書き込む前に閉じる
+    drop(cache);
+
+    // 計画の順 (前提となる項目が先) に書き込む。同じ項目の手順は前の手順が失敗したら行わない
+    let mut keys = KeyWriter::default();
+    let mut results: Vec<ApplyResult> = Vec::with_capacity(valid.len());
+    for entry in plan.entries.iter() {
+        let index = match results.iter().position(|x| x.id == entry.id) {
+            Some(i) => i,
+            None => {
+                results.push(ApplyResult::new(entry.id, None));
+                results.len() - 1
+            }
+        };
+        if results[index].error.is_some() {
+            continue;
+        }
+        let result = match &entry.target {
+            Some(def) => keys.apply(entry.id, def, &entry.action),
+            None => match (&entry.action, tweak::find(tweaks, entry.id)) {
+                (PlanAction::ApplyTweak { new, .. }, Some(tweak)) => {
+                    tweak.apply(new.as_deref()).map_err(|e| e.to_string())
+                }
+                _ => Ok(()),
+            },
+        };
+        results[index].error = result.err();
+    }
+    for warning in plan.warnings {
+        if let Some(x) = results.iter_mut().find(|x| x.id == warning.id) {
+            x.warnings.push(warning.message);
+        }
+    }
+
+    ret.extend(results);
+    ret
+}
+
+/// 書き込み用に開いたキーを使い回す
+#[derive(Default)]
+struct KeyWriter {
+    keys: HashMap<KeyId, OpenKey>,
+}
+
+impl KeyWriter {
+    /// 書き込み用に開く (`create` が `false` でキーが存在しない場合は `None`)
+    fn open(&mut self, def: &RegDef, create: bool) -> Result<Option<&OpenKey>> {
+        let id = (def.root, def.sub_key.to_lowercase(), def.view);
+        if !self.keys.contains_key(&id) {
+            let key = match create {
+                true => OpenKey::create(def.root(), &def.sub_key, def.view),
+                false => match OpenKey::open_writable(def.root(), &def.sub_key, def.view) {
+                    Err(Error::SubkeyNotFound(_)) => return Ok(None),
+                    ret => ret,
+                },
+            }?;
+            self.keys.insert(id.clone(), key);
+        }
+        Ok(self.keys.get(&id))
+    }
+
+    /// 計画の手順を 1 つ書き込む (値は読み直して確認する)
+    fn apply(
+        &mut self,
+        id: u64,
+        def: &RegDef,
+        action: &PlanAction,
+    ) -> std::result::Result<(), String> {
+        let outcome = match action {
+            PlanAction::CreateKey => self.open(def, true).map(|_| None),
+            PlanAction::SetValue { new, .. } => self.write(def, new).map(Some),
+            PlanAction::DeleteValue { .. } => self.delete(def).map(|_| None),
+            PlanAction::ApplyTweak { .. } | PlanAction::NoOp => Ok(None),
+        };
+        match outcome.map_err(|e| e.to_string())? {
+            Some(WriteOutcome::AppliedButVirtualized) => {
+                Err(tweak::Error::Virtualized(id).to_string())
+            }
+            Some(WriteOutcome::Reverted) => Err(tweak::Error::Reverted(id).to_string()),
+            Some(WriteOutcome::Applied) | None => Ok(()),
+        }
+    }
+
+    fn write(&mut self, def: &RegDef, value: &Value) -> Result<WriteOutcome> {
+        let registry = Registry::from_def(def);
+        let mirror = registry.virtual_store_value(value.data_type())?;
+        if let Some(key) = self.open(def, true)? {
+            key.write_value(&def.value_name, value)?;
+        }
+        registry.verify(value, mirror.as_ref())
+    }
+
+    fn delete(&mut self, def: &RegDef) -> Result<()> {
+        match self.open(def, false)? {
+            Some(key) => match key.delete_value(&def.value_name) {
+                Err(Error::ValueNameNotFound(_)) => Ok(()),
+                ret => ret,
+            },
+            None => Ok(()),
+        }
+    }
+}
@@ -1,9 +1,13 @@
+use crate::domain::{ValidationError, ValueDomain};
+use crate::policy::Policy;
 use crate::win;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct OptionCandidate {
     pub value: String,
     pub description: String,
+    /// 値そのものが存在しない状態を表す候補
+    pub absent: bool,
 }
 
 impl OptionCandidate {
@@ -11,14 +15,257 @@ impl OptionCandidate {
         Self {
             value: value.into(),
             description: description.into(),
+            absent: false,
         }
     }
+
+    pub fn absent(description: impl Into<String>) -> Self {
+        Self {
+            value: String::new(),
+            description: description.into(),
+            absent: true,
+        }
+    }
+}
+
+/// Windows の初期状態
+#[derive(Debug, Clone, PartialEq)]
+pub enum WindowsDefault {
+    Value(String),
+    /// 初期状態では値が存在しない
+    Absent,
+}
+
+/// 他の項目の状態についての条件 (`values` のいずれかに一致すれば成り立つ)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Condition {
+    pub id: u64,
+    /// `None` は値が存在しない状態
+    pub values: Vec<Option<String>>,
 }
 
-#[derive(Debug)]
+impl Condition {
+    pub fn new(id: u64, values: &[Option<&str>]) -> Self {
+        Self {
+            id,
+            values: values.iter().map(|x| x.map(|x| x.to_string())).collect(),
+        }
+    }
+}
+
+/// この項目が `when` のいずれかの値 (空の場合はどの値でも) のときに `target` を評価する関係
+#[derive(Debug, Clone, PartialEq)]
+pub struct Relation {
+    pub when: Vec<Option<String>>,
+    pub target: Condition,
+}
+
+#[derive(Debug, Clone)]
 pub struct RegistryEditOption {
     pub id: u64,
     pub label: String,
+    /// 画面上でまとめて表示する分類 (「エクスプローラ」など)
+    pub category: String,
+    /// 検索用の別名 (英語やローマ字表記など)
+    pub tags: Vec<String>,
     pub def: win::reg::RegDef,
     pub values: Vec<OptionCandidate>,
+    pub domain: ValueDomain,
+    /// 値の中の 1 つのフラグだけを扱う項目 (候補は 0/1)
+    pub bits: Option<win::reg::BitField>,
+    /// `None` は初期状態が分からない項目
+    pub windows_default: Option<WindowsDefault>,
+    /// この項目が意味を持つために必要な他の項目の状態
+    pub requires: Vec<Relation>,
+    /// この項目と同時に成り立ってはいけない他の項目の状態
+    pub conflicts_with: Vec<Relation>,
+    /// この項目を上書きするポリシー
+    pub policies: Vec<Policy>,
+}
+
+impl RegistryEditOption {
+    pub fn set_domain(&mut self, domain: ValueDomain) -> &mut Self {
+        self.domain = domain;
+        self
+    }
+
+    pub fn set_bit_field(&mut self, field: win::reg::BitField) -> &mut Self {
+        self.bits = Some(field);
+        self
+    }
+
+    pub fn set_tags(&mut self, tags: &[&str]) -> &mut Self {
+        self.tags.extend(tags.iter().map(|x| x.to_string()));
+        self
+    }
+
+    pub fn requires(&mut self, when: &[Option<&str>], target: Condition) -> &mut Self {
+        self.requires.push(Relation {
+            when: Condition::new(self.id, when).values,
+            target,
+        });
+        self
+    }
+
+    pub fn conflicts_with(&mut self, when: &[Option<&str>], target: Condition) -> &mut Self {
+        self.conflicts_with.push(Relation {
+            when: Condition::new(self.id, when).values,
+            target,
+        });
+        self
+    }
+
+    pub fn add_policy(&mut self, policy: Policy) -> &mut Self {
+        self.policies.push(policy);
+        self
+    }
+
+    /// 値 (`None` は値が存在しない) が関係の `when` に当てはまるか
+    pub fn applies(&self, relation: &Relation, value: Option<&win::reg::Value>) -> bool {
+        relation.when.is_empty() || self.matches_any(&relation.when, value)
+    }
+
+    /// 値 (`None` は値が存在しない) が条件の候補のいずれかに一致するか
+    pub fn satisfies(&self, condition: &Condition, value: Option<&win::reg::Value>) -> bool {
+        self.matches_any(&condition.values, value)
+    }
+
+    fn matches_any(&self, values: &[Option<String>], value: Option<&win::reg::Value>) -> bool {
+        values.iter().any(|x| match (x, value) {
+            (Some(x), Some(v)) => self
+                .domain
+                .parse(self.value_type(), x)
+                .is_ok_and(|x| x == *v),
+            (None, None) => true,
+            _ => false,
+        })
+    }
+
+    /// ユーザーごとのキー (項目とポリシー) を対象のユーザーのキーに置き換える
+    pub fn for_target(&self, target: &win::reg::Target) -> Self {
+        let mut ret = self.clone();
+        ret.def = self.def.for_target(target);
+        for policy in ret.policies.iter_mut() {
+            policy.def = policy.def.for_target(target);
+        }
+        ret
+    }
+
+    /// 分類を含めた表示名 (計画やレポートなど、分類ごとにまとまっていない場所で使う)
+    pub fn title(&self) -> String {
+        if self.category.is_empty() {
+            self.label.clone()
+        } else {
+            format!("{} - {}", self.category, self.label)
+        }
+    }
+
+    pub fn set_windows_default(&mut self, default: WindowsDefault) -> &mut Self {
+        self.windows_default = Some(default);
+        self
+    }
+
+    /// 初期状態に戻すときの選択 (`None` の要素は値の削除)
+    pub fn default_selection(&self) -> Option<Option<String>> {
+        match self.windows_default.as_ref()? {
+            WindowsDefault::Value(v) => Some(Some(v.clone())),
+            WindowsDefault::Absent => Some(None),
+        }
+    }
+
+    /// この項目が扱う値の型 (ビットフィールドはフラグのオン/オフを REG_DWORD の 0/1 で表す)
+    pub fn value_type(&self) -> win::reg::DataType {
+        match self.bits {
+            Some(_) => win::reg::DataType::DWord,
+            None => self.def.data_type,
+        }
+    }
+
+    /// レジストリ上の値から、この項目が扱う値を取り出す
+    pub fn project(
+        &self,
+        raw: Option<win::reg::Value>,
+    ) -> win::reg::Result<Option<win::reg::Value>> {
+        match (self.bits, raw) {
+            (Some(field), Some(v)) => Ok(Some(win::reg::Value::DWord(field.get(&v)? as u32))),
+            (_, raw) => Ok(raw),
+        }
+    }
+
+    /// この項目の値をレジストリ上の値に反映した結果を返す
+    /// ビットフィールドは他のビットを保ったまま書き換える
+    pub fn merge(
+        &self,
+        raw: Option<&win::reg::Value>,
+        value: &win::reg::Value,
+    ) -> win::reg::Result<win::reg::Value> {
+        use win::reg::{DataType, Error, Value};
+
+        let Some(field) = self.bits else {
+            return Ok(value.clone());
+        };
+
+        let base = match (raw, self.def.data_type) {
+            (Some(v), _) => v.clone(),
+            (None, DataType::DWord) => Value::DWord(0),
+            (None, DataType::QWord) => Value::QWord(0),
+            (None, _) => return Err(Error::ValueNameNotFound(self.def.value_name.clone())),
+        };
+        field.set(&base, *value != Value::DWord(0))
+    }
+
+    /// 入力を値に変換し、この項目で受け付けられるか検証する
+    pub fn parse_value(&self, input: &str) -> Result<win::reg::Value, ValidationError> {
+        let ret = self.domain.parse(self.value_type(), input)?;
+        self.validate(&ret)?;
+        Ok(ret)
+    }
+
+    pub fn validate(&self, value: &win::reg::Value) -> Result<(), ValidationError> {
+        if value.data_type() != self.value_type() {
+            return Err(ValidationError::TypeMismatch(value.data_type().str()));
+        }
+
+        if let ValueDomain::Candidates = self.domain {
+            if self.detect(Some(value)).is_none() && !self.is_windows_default(value) {
+                return Err(ValidationError::NotACandidate(value.to_string()));
+            }
+        }
+        self.domain.validate(value)
+    }
+
+    /// 値の削除を受け付けるか (初期状態が値なしの項目は常に受け付ける)
+    pub fn validate_absent(&self) -> Result<(), ValidationError> {
+        if self.values.iter().any(|x| x.absent)
+            || self.windows_default == Some(WindowsDefault::Absent)
+        {
+            Ok(())
+        } else {
+            Err(ValidationError::AbsentNotAllowed)
+        }
+    }
+
+    fn is_windows_default(&self, value: &win::reg::Value) -> bool {
+        match &self.windows_default {
+            Some(WindowsDefault::Value(v)) => self
+                .domain
+                .parse(self.value_type(), v)
+                .is_ok_and(|x| x == *value),
+            _ => false,
+        }
+    }
+
+    /// 現在の値 (`None` は値が存在しない) に一致する候補を探す
+    pub fn detect(&self, current: Option<&win::reg::Value>) -> Option<&OptionCandidate> {
+        self.values.iter().find(|x| match current {
+            Some(v) => {
+                !x.absent
+                    && self
+                        .domain
+                        .parse(self.value_type(), &x.value)
+                        .is_ok_and(|c| c == *v)
+            }
+            None => x.absent,
+        })
+    }
 }
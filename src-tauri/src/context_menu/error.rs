@@ -0,0 +1,50 @@
+use crate::win;
+use std::io;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    Json(serde_json::Error),
+    Registry(win::reg::Error),
+    /// 一覧に無い項目
+    UnknownEntry(String),
+    /// この項目には使えない無効化の方法 (CLSID の無い動詞をブロックするなど)
+    Unsupported(String),
+    /// 一覧の読み取りが中断された
+    Cancelled,
+}
+
+impl std::error::Error for Error {}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(e) => e.fmt(f),
+            Self::Json(e) => e.fmt(f),
+            Self::Registry(e) => e.fmt(f),
+            Self::UnknownEntry(v) => write!(f, "Unknown context menu entry ({v})"),
+            Self::Unsupported(v) => write!(f, "Unsupported operation ({v})"),
+            Self::Cancelled => write!(f, "Cancelled"),
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(value: io::Error) -> Self {
+        Error::Io(value)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(value: serde_json::Error) -> Self {
+        Error::Json(value)
+    }
+}
+
+impl From<win::reg::Error> for Error {
+    fn from(value: win::reg::Error) -> Self {
+        Error::Registry(value)
+    }
+}
@@ -0,0 +1,269 @@
+use super::{DisableMethod, EntryKind, Error, MenuEntry, Result, Scope};
+use crate::win::reg::{self, DataType, RegDef, Registry, Value};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// 書き換えた 1 つの値と、書き換える前の値 (`None` は値が存在しなかった)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MenuChange {
+    /// 項目の ID (`MenuEntry` の `id`)
+    pub entry: String,
+    pub def: RegDef,
+    pub original: Option<Value>,
+}
+
+impl MenuChange {
+    fn same_value(&self, other: &Self) -> bool {
+        let (a, b) = (&self.def, &other.def);
+        a.root == b.root
+            && a.view == b.view
+            && a.sub_key.eq_ignore_ascii_case(&b.sub_key)
+            && a.value_name.eq_ignore_ascii_case(&b.value_name)
+    }
+}
+
+/// 項目を有効または無効にし、書き換えた値を `journal` に記録する
+/// 無効にする方法を省略した場合は項目の既定の方法を使う。有効にする場合は無効にしているすべての方法を取り除く
+/// 途中で失敗した場合も、それまでに書き換えた値は記録されている
+pub fn set_enabled(
+    entry: &MenuEntry,
+    enabled: bool,
+    method: Option<DisableMethod>,
+    journal: &mut Journal,
+) -> Result<()> {
+    if enabled {
+        for method in entry.disabled_by.iter() {
+            for def in targets(entry, *method, true)? {
+                let r = Registry::from_def(&def);
+                let Some(original) = r.find_any_value()? else {
+                    continue;
+                };
+                r.delete_value()?;
+                journal.record([MenuChange {
+                    entry: entry.id.clone(),
+                    def,
+                    original: Some(original),
+                }]);
+            }
+        }
+    } else {
+        let method = method.unwrap_or(entry.default_method());
+        if entry.disabled_by.contains(&method) {
+            return Ok(());
+        }
+        for def in targets(entry, method, false)? {
+            let r = Registry::from_def(&def);
+            let original = r.find_any_value()?;
+            r.write_value(&Value::String(String::new()))?;
+            journal.record([MenuChange {
+                entry: entry.id.clone(),
+                def,
+                original,
+            }]);
+        }
+    }
+    Ok(())
+}
+
+/// 方法ごとに書き換える値
+/// ブロックは項目と同じ場所の一覧に加え、取り除くときはユーザーとコンピューターの両方から取り除く
+fn targets(entry: &MenuEntry, method: DisableMethod, enable: bool) -> Result<Vec<RegDef>> {
+    match (method.value_name(), entry.kind) {
+        (Some(name), EntryKind::Verb) => Ok(vec![entry.scope.classes(
+            &entry.key(),
+            name,
+            DataType::String,
+        )]),
+        (Some(name), EntryKind::Handler) => Err(Error::Unsupported(format!(
+            "{name} cannot be used for shell extensions"
+        ))),
+        (None, _) => {
+            let clsid = entry.clsid.as_deref().ok_or_else(|| {
+                Error::Unsupported(format!("{} has no CLSID to block", entry.name))
+            })?;
+            let scopes = match enable {
+                true => Scope::ALL.to_vec(),
+                false => vec![entry.scope],
+            };
+            Ok(scopes.iter().map(|x| x.blocked(clsid)).collect())
+        }
+    }
+}
+
+/// 書き換える前の値に戻す
+pub fn revert(change: &MenuChange) -> Result<()> {
+    let r = Registry::from_def(&change.def);
+    match &change.original {
+        Some(v) => r.write_value(v)?,
+        // 値やキーが既に無い場合は戻す必要がない
+        None => match r.delete_value() {
+            Err(reg::Error::ValueNameNotFound(_)) | Err(reg::Error::SubkeyNotFound(_)) => (),
+            ret => ret?,
+        },
+    }
+    Ok(())
+}
+
+/// このツールで書き換えた値の記録 (すべての変更を元に戻せるよう、最初に書き換える前の値を残す)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Journal {
+    pub changes: Vec<MenuChange>,
+}
+
+impl Journal {
+    /// ファイルが無い場合は空の記録
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        match std::fs::read_to_string(path) {
+            Ok(text) => Ok(serde_json::from_str(&text)?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// 既に記録している値は、最初の変更前の値を残す
+    pub fn record(&mut self, changes: impl IntoIterator<Item = MenuChange>) {
+        for change in changes {
+            if !self.changes.iter().any(|x| x.same_value(&change)) {
+                self.changes.push(change);
+            }
+        }
+    }
+
+    /// 項目 (`None` はすべての項目) を最初に書き換える前の状態に戻し、記録から取り除く
+    /// 後から書き換えた値から順に戻す
+    pub fn revert(&mut self, entry: Option<&str>) -> Result<()> {
+        self.revert_with(entry, revert)
+    }
+
+    fn revert_with(
+        &mut self,
+        entry: Option<&str>,
+        mut revert: impl FnMut(&MenuChange) -> Result<()>,
+    ) -> Result<()> {
+        while let Some(i) = self
+            .changes
+            .iter()
+            .rposition(|x| entry.is_none_or(|id| x.entry == id))
+        {
+            revert(&self.changes[i])?;
+            self.changes.remove(i);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context_menu::Location;
+    use crate::win::reg::Root;
+
+    fn entry(kind: EntryKind, clsid: Option<&str>) -> MenuEntry {
+        MenuEntry {
+            id: "entry".into(),
+            location: Location::Directory,
+            kind,
+            scope: Scope::User,
+            name: "Open".into(),
+            text: None,
+            clsid: clsid.map(Into::into),
+            friendly_name: None,
+            dll: None,
+            disabled_by: Vec::new(),
+        }
+    }
+
+    fn change(entry: &str, value_name: &str, original: u32) -> MenuChange {
+        MenuChange {
+            entry: entry.into(),
+            def: RegDef::hkcu(r"Software\Win11Tweaks", value_name, DataType::DWord),
+            original: Some(Value::DWord(original)),
+        }
+    }
+
+    #[test]
+    fn targets_verb_values() {
+        let verb = entry(EntryKind::Verb, None);
+        let defs = targets(&verb, DisableMethod::LegacyDisable, false).unwrap();
+        assert_eq!(defs.len(), 1);
+        assert_eq!(defs[0].sub_key, r"Software\Classes\Directory\shell\Open");
+        assert_eq!(defs[0].value_name, "LegacyDisable");
+        assert!(matches!(
+            targets(&verb, DisableMethod::Blocked, false),
+            Err(Error::Unsupported(_))
+        ));
+    }
+
+    #[test]
+    fn targets_blocked_clsid() {
+        const CLSID: &str = "{09A47860-11B0-4DA5-AFA5-26D86198A780}";
+        let handler = entry(EntryKind::Handler, Some(CLSID));
+        assert!(matches!(
+            targets(&handler, DisableMethod::LegacyDisable, false),
+            Err(Error::Unsupported(_))
+        ));
+
+        // 無効にするときは項目と同じ場所だけ、有効にするときは両方から取り除く
+        let roots = |enable| {
+            targets(&handler, DisableMethod::Blocked, enable)
+                .unwrap()
+                .iter()
+                .map(|x| {
+                    assert_eq!(x.value_name, CLSID);
+                    x.root
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(roots(false), [Root::User]);
+        assert_eq!(roots(true), [Root::User, Root::LocalMachine]);
+
+        assert!(matches!(
+            targets(
+                &entry(EntryKind::Handler, None),
+                DisableMethod::Blocked,
+                false
+            ),
+            Err(Error::Unsupported(_))
+        ));
+    }
+
+    #[test]
+    fn record_keeps_first_original() {
+        let mut journal = Journal::default();
+        journal.record([change("a", "Value", 1)]);
+        journal.record([change("a", "VALUE", 2), change("b", "Other", 3)]);
+        assert_eq!(journal.changes.len(), 2);
+        assert_eq!(journal.changes[0].original, Some(Value::DWord(1)));
+        assert_eq!(journal.changes[1].entry, "b");
+    }
+
+    #[test]
+    fn revert_in_reverse_order() {
+        let mut journal = Journal::default();
+        journal.record([
+            change("a", "First", 1),
+            change("b", "Second", 2),
+            change("a", "Third", 3),
+        ]);
+
+        let mut reverted = Vec::new();
+        journal
+            .revert_with(Some("a"), |x| {
+                reverted.push(x.def.value_name.clone());
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(reverted, ["Third", "First"]);
+        assert_eq!(journal.changes.len(), 1);
+
+        // 失敗した変更は記録に残す
+        let ret = journal.revert_with(None, |_| Err(Error::UnknownEntry("b".into())));
+        assert!(ret.is_err());
+        assert_eq!(journal.changes.len(), 1);
+    }
+}
@@ -0,0 +1,192 @@
+//! エクスプローラの右クリックメニューに登録されている動詞とシェル拡張
+//! HKCR はユーザー (HKCU\Software\Classes) とコンピューター (HKLM\SOFTWARE\Classes) を
+//! 合わせたものなので、両方を別々に列挙する
+
+mod error;
+mod journal;
+mod scan;
+
+pub use error::{Error, Result};
+pub use journal::{revert, set_enabled, Journal, MenuChange};
+pub use scan::{resolve_clsid, scan, scan_with, ClassInfo};
+
+use crate::win::reg::{DataType, RegDef, View};
+use serde::{Deserialize, Serialize};
+
+/// シェル拡張をブロックする一覧 (値の名前が CLSID)
+const BLOCKED_KEY: &str = r"Software\Microsoft\Windows\CurrentVersion\Shell Extensions\Blocked";
+
+/// メニューを表示する対象
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Location {
+    /// すべてのファイル (`*`)
+    AllFiles,
+    Directory,
+    /// フォルダーの背景
+    Background,
+}
+
+impl Location {
+    pub const ALL: [Self; 3] = [Self::AllFiles, Self::Directory, Self::Background];
+
+    /// HKCR 以下のクラス名
+    pub fn class(&self) -> &'static str {
+        match self {
+            Self::AllFiles => "*",
+            Self::Directory => "Directory",
+            Self::Background => r"Directory\Background",
+        }
+    }
+}
+
+/// 登録されている場所 (同じ名前はユーザーの方が優先される)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Scope {
+    User,
+    Machine,
+}
+
+impl Scope {
+    pub const ALL: [Self; 2] = [Self::User, Self::Machine];
+
+    /// `Classes` 以下のキーの値
+    /// エクスプローラは 64 ビットのため、32 ビット版のビルドからも 64 ビットのビューを参照する
+    pub fn classes(&self, sub_key: &str, value_name: &str, data_type: DataType) -> RegDef {
+        let ret = match self {
+            Self::User => RegDef::user(
+                format!(r"Software\Classes\{sub_key}"),
+                value_name,
+                data_type,
+            ),
+            Self::Machine => RegDef::hklm(
+                format!(r"SOFTWARE\Classes\{sub_key}"),
+                value_name,
+                data_type,
+            ),
+        };
+        ret.in_view(View::Bit64)
+    }
+
+    /// `Shell Extensions\Blocked` の CLSID の値
+    pub fn blocked(&self, clsid: &str) -> RegDef {
+        let ret = match self {
+            Self::User => RegDef::user(BLOCKED_KEY, clsid, DataType::String),
+            Self::Machine => RegDef::hklm(BLOCKED_KEY, clsid, DataType::String),
+        };
+        ret.in_view(View::Bit64)
+    }
+
+    pub fn str(&self) -> &'static str {
+        match self {
+            Self::User => "user",
+            Self::Machine => "machine",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum EntryKind {
+    /// `shell\<動詞>`
+    Verb,
+    /// `shellex\ContextMenuHandlers\<名前>`
+    Handler,
+}
+
+impl EntryKind {
+    /// クラスのキーからの相対パス
+    fn parent(&self) -> &'static str {
+        match self {
+            Self::Verb => "shell",
+            Self::Handler => r"shellex\ContextMenuHandlers",
+        }
+    }
+}
+
+/// 項目を無効にする方法
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DisableMethod {
+    /// 動詞のキーに `LegacyDisable` を置く
+    LegacyDisable,
+    /// 動詞のキーに `ProgrammaticAccessOnly` を置く (メニューには出ないがプログラムからは呼べる)
+    ProgrammaticAccessOnly,
+    /// `Shell Extensions\Blocked` に CLSID を加える (CLSID を持つ項目のみ)
+    Blocked,
+}
+
+impl DisableMethod {
+    /// 動詞のキーに置く値の名前 (`Blocked` は `None`)
+    fn value_name(&self) -> Option<&'static str> {
+        match self {
+            Self::LegacyDisable => Some("LegacyDisable"),
+            Self::ProgrammaticAccessOnly => Some("ProgrammaticAccessOnly"),
+            Self::Blocked => None,
+        }
+    }
+}
+
+/// 右クリックメニューの 1 つの項目
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MenuEntry {
+    /// 画面や記録で項目を指す ID
+    pub id: String,
+    pub location: Location,
+    pub kind: EntryKind,
+    pub scope: Scope,
+    /// キーの名前 (動詞やハンドラーの名前)
+    pub name: String,
+    /// メニューに表示される文字列 (`MUIVerb` か既定値。`@shell32.dll,-1234` のような参照はそのまま)
+    pub text: Option<String>,
+    /// シェル拡張の CLSID (動詞の場合は `ExplorerCommandHandler`)
+    pub clsid: Option<String>,
+    /// CLSID の既定値
+    pub friendly_name: Option<String>,
+    /// CLSID の `InprocServer32` (環境変数は展開しない)
+    pub dll: Option<String>,
+    /// 無効にしている方法 (空の場合は有効)
+    pub disabled_by: Vec<DisableMethod>,
+}
+
+impl MenuEntry {
+    pub fn new(location: Location, kind: EntryKind, scope: Scope, name: String) -> Self {
+        let mut ret = Self {
+            id: String::new(),
+            location,
+            kind,
+            scope,
+            name,
+            text: None,
+            clsid: None,
+            friendly_name: None,
+            dll: None,
+            disabled_by: Vec::new(),
+        };
+        ret.id = format!("{}:{}", scope.str(), ret.key());
+        ret
+    }
+
+    /// `Classes` からの項目のキーのパス
+    pub fn key(&self) -> String {
+        format!(
+            r"{}\{}\{}",
+            self.location.class(),
+            self.kind.parent(),
+            self.name
+        )
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.disabled_by.is_empty()
+    }
+
+    /// 無効にするときの既定の方法 (動詞は `LegacyDisable`、シェル拡張はブロック)
+    pub fn default_method(&self) -> DisableMethod {
+        match self.kind {
+            EntryKind::Verb => DisableMethod::LegacyDisable,
+            EntryKind::Handler => DisableMethod::Blocked,
+        }
+    }
+}
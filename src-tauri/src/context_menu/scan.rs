@@ -0,0 +1,151 @@
+use super::Error;
+use super::{DisableMethod, EntryKind, Location, MenuEntry, Result, Scope};
+use crate::task::{CancelToken, Progress};
+use crate::win::reg::{DataType, RegDef, Registry, Value};
+
+/// CLSID から分かる名前と DLL
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ClassInfo {
+    pub friendly_name: Option<String>,
+    pub dll: Option<String>,
+}
+
+/// 右クリックメニューの項目をすべて列挙する
+pub fn scan() -> Result<Vec<MenuEntry>> {
+    scan_with(&CancelToken::new(), |_| ())
+}
+
+/// 親のキーごとに進み具合を通知しながら列挙する
+pub fn scan_with(
+    cancel: &CancelToken,
+    mut progress: impl FnMut(&Progress),
+) -> Result<Vec<MenuEntry>> {
+    let mut parents = Vec::new();
+    for location in Location::ALL {
+        for scope in Scope::ALL {
+            for kind in [EntryKind::Verb, EntryKind::Handler] {
+                parents.push((location, scope, kind));
+            }
+        }
+    }
+
+    let mut ret = Vec::new();
+    let total = parents.len();
+    for (i, (location, scope, kind)) in parents.into_iter().enumerate() {
+        if cancel.is_cancelled() {
+            return Err(Error::Cancelled);
+        }
+        let parent = format!(r"{}\{}", location.class(), kind.parent());
+        let def = scope.classes(&parent, "", DataType::String);
+        progress(&Progress {
+            step: i + 1,
+            total,
+            key: def.to_string(),
+        });
+        for name in Registry::from_def(&def).sub_keys()? {
+            ret.push(read_entry(location, kind, scope, name)?);
+        }
+    }
+    Ok(ret)
+}
+
+fn read_entry(
+    location: Location,
+    kind: EntryKind,
+    scope: Scope,
+    name: String,
+) -> Result<MenuEntry> {
+    let mut entry = MenuEntry::new(location, kind, scope, name);
+    let key = entry.key();
+    let read = |value_name: &str| read_string(&scope.classes(&key, value_name, DataType::String));
+
+    match kind {
+        EntryKind::Verb => {
+            entry.text = read("MUIVerb")?.or(read("")?);
+            entry.clsid = read("ExplorerCommandHandler")?;
+            for method in [
+                DisableMethod::LegacyDisable,
+                DisableMethod::ProgrammaticAccessOnly,
+            ] {
+                let value_name = method.value_name().unwrap_or_default();
+                if exists(&scope.classes(&key, value_name, DataType::String))? {
+                    entry.disabled_by.push(method);
+                }
+            }
+        }
+        // 既定値が CLSID でない場合は、キーの名前が CLSID になっている
+        EntryKind::Handler => {
+            entry.clsid = read("")?
+                .filter(|x| is_clsid(x))
+                .or_else(|| is_clsid(&entry.name).then(|| entry.name.clone()));
+        }
+    }
+
+    if let Some(clsid) = &entry.clsid {
+        let info = resolve_clsid(clsid)?;
+        entry.friendly_name = info.friendly_name;
+        entry.dll = info.dll;
+        for scope in Scope::ALL {
+            if exists(&scope.blocked(clsid))? {
+                entry.disabled_by.push(DisableMethod::Blocked);
+                break;
+            }
+        }
+    }
+    Ok(entry)
+}
+
+/// CLSID の名前と DLL を調べる (ユーザーの登録を優先する)
+pub fn resolve_clsid(clsid: &str) -> Result<ClassInfo> {
+    let mut ret = ClassInfo::default();
+    for scope in Scope::ALL {
+        let key = format!(r"CLSID\{clsid}");
+        if ret.friendly_name.is_none() {
+            ret.friendly_name = read_string(&scope.classes(&key, "", DataType::String))?;
+        }
+        if ret.dll.is_none() {
+            let server = format!(r"{key}\InprocServer32");
+            ret.dll = read_string(&scope.classes(&server, "", DataType::String))?;
+        }
+    }
+    Ok(ret)
+}
+
+/// `{xxxxxxxx-xxxx-xxxx-xxxx-xxxxxxxxxxxx}` の形か
+fn is_clsid(text: &str) -> bool {
+    let Some(inner) = text.strip_prefix('{').and_then(|x| x.strip_suffix('}')) else {
+        return false;
+    };
+    let groups = inner.split('-').map(str::len).collect::<Vec<_>>();
+    groups == [8, 4, 4, 4, 12] && inner.chars().all(|x| x == '-' || x.is_ascii_hexdigit())
+}
+
+/// 文字列の値 (空文字列や文字列以外の型は `None`)
+fn read_string(def: &RegDef) -> Result<Option<String>> {
+    let ret = match Registry::from_def(def).find_any_value()? {
+        Some(Value::String(v)) | Some(Value::ExpandString(v)) if !v.is_empty() => Some(v),
+        _ => None,
+    };
+    Ok(ret)
+}
+
+fn exists(def: &RegDef) -> Result<bool> {
+    Ok(Registry::from_def(def).find_any_value()?.is_some())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn is_clsid_checks_groups_and_digits() {
+        assert!(is_clsid("{09A47860-11B0-4DA5-AFA5-26D86198A780}"));
+        assert!(is_clsid("{09a47860-11b0-4da5-afa5-26d86198a780}"));
+        assert!(!is_clsid("09A47860-11B0-4DA5-AFA5-26D86198A780"));
+        assert!(!is_clsid("{09A47860-11B0-4DA5-AFA526D86198A780}"));
+        assert!(!is_clsid("{09A4786-011B0-4DA5-AFA5-26D86198A780}"));
+        assert!(!is_clsid("{G9A47860-11B0-4DA5-AFA5-26D86198A780}"));
+        assert!(!is_clsid("{}"));
+        assert!(!is_clsid("Open"));
+    }
+}
@@ -0,0 +1,34 @@
+#[derive(Debug, Clone, PartialEq)]
+pub enum ValidationError {
+    Parse { input: String, reason: String },
+    NotACandidate(String),
+    AbsentNotAllowed,
+    OutOfRange { value: u64, min: u64, max: u64 },
+    PatternMismatch { value: String, pattern: String },
+    UnknownFlag(String),
+    UnsupportedBits { value: u64, unknown: u64 },
+    TypeMismatch(&'static str),
+}
+
+impl std::error::Error for ValidationError {}
+
+impl std::fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Parse { input, reason } => write!(f, "Invalid value \"{input}\" ({reason})"),
+            Self::NotACandidate(v) => write!(f, "\"{v}\" is not one of the candidates"),
+            Self::AbsentNotAllowed => write!(f, "This value cannot be deleted"),
+            Self::OutOfRange { value, min, max } => {
+                write!(f, "{value} is out of range ({min}..={max})")
+            }
+            Self::PatternMismatch { value, pattern } => {
+                write!(f, "\"{value}\" does not match the pattern ({pattern})")
+            }
+            Self::UnknownFlag(v) => write!(f, "Unknown flag ({v})"),
+            Self::UnsupportedBits { value, unknown } => {
+                write!(f, "0x{value:x} contains unsupported bits (0x{unknown:x})")
+            }
+            Self::TypeMismatch(t) => write!(f, "Value domain does not apply to {t}"),
+        }
+    }
+}
@@ -0,0 +1,247 @@
+mod error;
+
+pub use error::ValidationError;
+
+use crate::win::reg::{DataType, Value};
+use regex::Regex;
+
+/// ビットマスクの各ビットの名前
+#[derive(Debug, Clone)]
+pub struct Flag {
+    pub name: String,
+    pub mask: u64,
+    pub description: String,
+}
+
+impl Flag {
+    pub fn new(name: impl Into<String>, mask: u64, description: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            mask,
+            description: description.into(),
+        }
+    }
+}
+
+/// 設定項目が受け付ける値の範囲
+#[derive(Debug, Clone, Default)]
+pub enum ValueDomain {
+    /// 候補のいずれか
+    #[default]
+    Candidates,
+    /// 数値の範囲 (両端を含む)
+    Range {
+        min: u64,
+        max: u64,
+    },
+    /// 文字列全体が一致する正規表現
+    Pattern(Regex),
+    Bitmask(Vec<Flag>),
+}
+
+impl ValueDomain {
+    pub fn range(min: u64, max: u64) -> Self {
+        Self::Range { min, max }
+    }
+
+    /// カタログ定義の誤りなので、正規表現が不正な場合は panic する
+    pub fn pattern(pattern: &str) -> Self {
+        Self::Pattern(Regex::new(&format!("^(?:{pattern})$")).expect("Invalid pattern"))
+    }
+
+    pub fn bitmask(flags: Vec<Flag>) -> Self {
+        Self::Bitmask(flags)
+    }
+
+    /// 入力文字列を値に変換する
+    /// 数値は 10 進と `0x` 付きの 16 進、ビットマスクは `|` や `,` で区切ったフラグ名も受け付ける
+    pub fn parse(&self, data_type: DataType, input: &str) -> Result<Value, ValidationError> {
+        if let Self::Bitmask(flags) = self {
+            let mut bits = 0u64;
+            for token in input.split(['|', ',', '+']).map(str::trim) {
+                if token.is_empty() {
+                    continue;
+                }
+                bits |= match flags.iter().find(|x| x.name.eq_ignore_ascii_case(token)) {
+                    Some(flag) => flag.mask,
+                    None => match Value::parse(DataType::QWord, token) {
+                        Ok(Value::QWord(v)) => v,
+                        _ => return Err(ValidationError::UnknownFlag(token.into())),
+                    },
+                };
+            }
+
+            return match data_type {
+                DataType::DWord => {
+                    u32::try_from(bits)
+                        .map(Value::DWord)
+                        .map_err(|_| ValidationError::Parse {
+                            input: input.into(),
+                            reason: "does not fit in REG_DWORD".into(),
+                        })
+                }
+                DataType::QWord => Ok(Value::QWord(bits)),
+                t => Err(ValidationError::TypeMismatch(t.str())),
+            };
+        }
+
+        Value::parse(data_type, input).map_err(|e| ValidationError::Parse {
+            input: input.into(),
+            reason: e.to_string(),
+        })
+    }
+
+    /// 候補以外の範囲を検証する (`Candidates` は呼び出し側で候補と比較する)
+    pub fn validate(&self, value: &Value) -> Result<(), ValidationError> {
+        match self {
+            Self::Candidates => Ok(()),
+            Self::Range { min, max } => {
+                let v = number(value)?;
+                if v < *min || v > *max {
+                    return Err(ValidationError::OutOfRange {
+                        value: v,
+                        min: *min,
+                        max: *max,
+                    });
+                }
+                Ok(())
+            }
+            Self::Pattern(re) => {
+                let items = match value {
+                    Value::String(v) | Value::ExpandString(v) => vec![v],
+                    Value::MultiString(v) => v.iter().collect(),
+                    v => return Err(ValidationError::TypeMismatch(v.data_type().str())),
+                };
+                match items.into_iter().find(|x| !re.is_match(x)) {
+                    Some(v) => Err(ValidationError::PatternMismatch {
+                        value: v.clone(),
+                        pattern: re.as_str().into(),
+                    }),
+                    None => Ok(()),
+                }
+            }
+            Self::Bitmask(flags) => {
+                let v = number(value)?;
+                let known = flags.iter().fold(0u64, |acc, x| acc | x.mask);
+                if v & !known != 0 {
+                    return Err(ValidationError::UnsupportedBits {
+                        value: v,
+                        unknown: v & !known,
+                    });
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+fn number(value: &Value) -> Result<u64, ValidationError> {
+    match value {
+        Value::DWord(v) => Ok(*v as u64),
+        Value::QWord(v) => Ok(*v),
+        v => Err(ValidationError::TypeMismatch(v.data_type().str())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn flags() -> ValueDomain {
+        ValueDomain::bitmask(vec![
+            Flag::new("Hidden", 0x1, "隠す"),
+            Flag::new("System", 0x4, "システム"),
+            Flag::new("High", 0x1_0000_0000, "上位"),
+        ])
+    }
+
+    #[test]
+    fn parses_decimal_and_hex() {
+        let domain = ValueDomain::range(0, 0x20);
+        assert_eq!(domain.parse(DataType::DWord, "16"), Ok(Value::DWord(16)));
+        assert_eq!(domain.parse(DataType::DWord, "0x10"), Ok(Value::DWord(16)));
+        assert_eq!(
+            domain.parse(DataType::QWord, "0x100000000"),
+            Ok(Value::QWord(0x1_0000_0000))
+        );
+        assert!(matches!(
+            domain.parse(DataType::DWord, "0x100000000"),
+            Err(ValidationError::Parse { .. })
+        ));
+        assert!(matches!(
+            domain.parse(DataType::DWord, "ten"),
+            Err(ValidationError::Parse { .. })
+        ));
+    }
+
+    #[test]
+    fn parses_flag_names() {
+        let domain = flags();
+        assert_eq!(
+            domain.parse(DataType::DWord, "hidden | SYSTEM"),
+            Ok(Value::DWord(0x5))
+        );
+        assert_eq!(
+            domain.parse(DataType::DWord, "Hidden,0x4"),
+            Ok(Value::DWord(0x5))
+        );
+        assert_eq!(domain.parse(DataType::DWord, ""), Ok(Value::DWord(0)));
+        assert_eq!(
+            domain.parse(DataType::QWord, "High+Hidden"),
+            Ok(Value::QWord(0x1_0000_0001))
+        );
+        assert!(matches!(
+            domain.parse(DataType::DWord, "High"),
+            Err(ValidationError::Parse { .. })
+        ));
+        assert_eq!(
+            domain.parse(DataType::DWord, "Hidden|Archive"),
+            Err(ValidationError::UnknownFlag("Archive".into()))
+        );
+        assert_eq!(
+            domain.parse(DataType::String, "Hidden"),
+            Err(ValidationError::TypeMismatch(DataType::String.str()))
+        );
+    }
+
+    #[test]
+    fn validates_range_bounds() {
+        let domain = ValueDomain::range(1, 3);
+        assert_eq!(domain.validate(&Value::DWord(1)), Ok(()));
+        assert_eq!(domain.validate(&Value::QWord(3)), Ok(()));
+        for v in [0, 4] {
+            assert_eq!(
+                domain.validate(&Value::DWord(v)),
+                Err(ValidationError::OutOfRange {
+                    value: v as u64,
+                    min: 1,
+                    max: 3
+                })
+            );
+        }
+        assert!(matches!(
+            domain.validate(&Value::String("2".into())),
+            Err(ValidationError::TypeMismatch(_))
+        ));
+    }
+
+    #[test]
+    fn rejects_values_outside_domain() {
+        assert_eq!(
+            flags().validate(&Value::DWord(0x7)),
+            Err(ValidationError::UnsupportedBits {
+                value: 0x7,
+                unknown: 0x2
+            })
+        );
+        assert_eq!(flags().validate(&Value::DWord(0x5)), Ok(()));
+
+        let domain = ValueDomain::pattern(r"[a-z]+");
+        assert_eq!(domain.validate(&Value::String("abc".into())), Ok(()));
+        assert!(matches!(
+            domain.validate(&Value::MultiString(vec!["abc".into(), "abc1".into()])),
+            Err(ValidationError::PatternMismatch { value, .. }) if value == "abc1"
+        ));
+        assert_eq!(ValueDomain::Candidates.validate(&Value::DWord(9)), Ok(()));
+    }
+}
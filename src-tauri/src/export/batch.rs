@@ -0,0 +1,139 @@
+use super::{Error, Result, Step};
+use crate::win::reg::{Value, View};
+use std::fmt::Write;
+
+pub(super) fn render(name: &str, steps: &[Step]) -> Result<String> {
+    let mut ret = String::new();
+    let _ = writeln!(ret, "@echo off");
+    // 日本語のコメントや値を含む UTF-8 のファイルを、既定のコード ページ (932 など) で読まないようにする
+    let _ = writeln!(ret, "chcp 65001 >nul");
+    let _ = writeln!(ret, "rem Win11 Tweaks: {}", comment(name));
+    let _ = writeln!(ret, "setlocal");
+
+    for step in steps.iter() {
+        let def = &step.opt.def;
+        let key = quote(&format!("{}\\{}", def.root, def.sub_key));
+        let name = if def.value_name.is_empty() {
+            "/ve".to_string()
+        } else {
+            format!("/v {}", quote(&def.value_name))
+        };
+
+        let _ = writeln!(ret);
+        let _ = writeln!(ret, "rem {}", comment(&step.comment));
+        // 両方のビューを指定した場合はビューごとに書き込む
+        for view in def.view.targets().iter().map(view_flag) {
+            match step.value.as_ref() {
+                // reg.exe では読み取ってから一部のビットだけ書き換えることができない
+                Some(v) if step.opt.bits.is_some() => {
+                    return Err(Error::Unrepresentable(step.opt.id, v.to_string()));
+                }
+                Some(v) => {
+                    let data = data(v)
+                        .ok_or_else(|| Error::Unrepresentable(step.opt.id, v.to_string()))?;
+                    let _ = writeln!(
+                        ret,
+                        "reg add {key} {name} /t {} /d {data} /f{view} >nul || exit /b 1",
+                        v.data_type()
+                    );
+                }
+                None => {
+                    let _ = writeln!(ret, "reg delete {key} {name} /f{view} >nul 2>&1");
+                }
+            }
+        }
+    }
+
+    let _ = writeln!(ret);
+    let _ = writeln!(ret, "exit /b 0");
+    Ok(ret)
+}
+
+/// reg.exe のビューの指定 (cmd.exe のビット数に合わせる場合は空)
+fn view_flag(view: &View) -> &'static str {
+    match view {
+        View::Native | View::Both => "",
+        View::Bit64 => " /reg:64",
+        View::Bit32 => " /reg:32",
+    }
+}
+
+/// `reg add` の `/d` に渡す値。コマンドラインで表現できない場合は `None`
+fn data(value: &Value) -> Option<String> {
+    let ret = match value {
+        Value::DWord(v) => v.to_string(),
+        Value::QWord(v) => v.to_string(),
+        Value::String(v) | Value::ExpandString(v) => {
+            if v.contains(['\r', '\n']) {
+                return None;
+            }
+            quote(v)
+        }
+        // 要素の区切りは reg.exe の既定の `\0`
+        Value::MultiString(v) => {
+            if v.iter()
+                .any(|x| x.contains(['\r', '\n']) || x.contains("\\0"))
+            {
+                return None;
+            }
+            quote(&v.join("\\0"))
+        }
+        Value::Binary(v) if v.is_empty() => "\"\"".into(),
+        Value::Binary(v) => v.iter().map(|x| format!("{x:02x}")).collect(),
+    };
+
+    Some(ret)
+}
+
+/// reg.exe の引数として二重引用符で囲み、さらに cmd.exe 向けにエスケープする
+fn quote(s: &str) -> String {
+    // reg.exe (MSVC の規則): `"` の前と末尾のバックスラッシュは二重化する
+    let mut arg = String::from("\"");
+    let mut backslashes = 0;
+    for c in s.chars() {
+        match c {
+            '\\' => backslashes += 1,
+            '"' => {
+                arg.push_str(&"\\".repeat(backslashes * 2 + 1));
+                arg.push('"');
+                backslashes = 0;
+            }
+            c => {
+                arg.push_str(&"\\".repeat(backslashes));
+                arg.push(c);
+                backslashes = 0;
+            }
+        }
+    }
+    arg.push_str(&"\\".repeat(backslashes * 2));
+    arg.push('"');
+
+    // cmd.exe: `%` は常に二重化し、`\"` によって引用符の外になる特殊文字は `^` を付ける
+    let mut ret = String::with_capacity(arg.len());
+    let mut quoted = false;
+    for c in arg.chars() {
+        match c {
+            '"' => {
+                quoted = !quoted;
+                ret.push(c);
+            }
+            '%' => ret.push_str("%%"),
+            '^' | '&' | '|' | '<' | '>' | '(' | ')' if !quoted => {
+                ret.push('^');
+                ret.push(c);
+            }
+            c => ret.push(c),
+        }
+    }
+
+    ret
+}
+
+fn comment(s: &str) -> String {
+    s.lines()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .replace('%', "%%")
+        .trim_end_matches('^')
+        .to_string()
+}
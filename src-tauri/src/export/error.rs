@@ -0,0 +1,44 @@
+use crate::domain::ValidationError;
+use crate::win;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Debug)]
+pub enum Error {
+    UnknownOption(u64),
+    UnknownFormat(String),
+    /// コマンドラインでは表現できない値 (改行を含む文字列など)
+    Unrepresentable(u64, String),
+    /// レジストリ以外の項目 (サービスやファイルなど) はスクリプトに出力できない
+    NotRegistry(u64),
+    Validation(u64, ValidationError),
+    Registry(win::reg::Error),
+}
+
+impl std::error::Error for Error {}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnknownOption(id) => write!(f, "Unknown option (ID={id})"),
+            Self::UnknownFormat(v) => write!(f, "Unknown script format ({v})"),
+            Self::Unrepresentable(id, v) => {
+                write!(f, "Value cannot be written to the script (ID={id}, {v})")
+            }
+            Self::NotRegistry(id) => {
+                write!(
+                    f,
+                    "Only registry options can be written to the script (ID={id})"
+                )
+            }
+            Self::Validation(id, e) => write!(f, "{e} (ID={id})"),
+            Self::Registry(e) => e.fmt(f),
+        }
+    }
+}
+
+impl From<win::reg::Error> for Error {
+    fn from(value: win::reg::Error) -> Self {
+        Error::Registry(value)
+    }
+}
@@ -0,0 +1,182 @@
+mod batch;
+mod error;
+mod powershell;
+
+pub use error::{Error, Result};
+
+use crate::command::RegistryEditOption;
+use crate::profile::Profile;
+use crate::tweak::{self, Tweak};
+use crate::win::reg::Value;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    PowerShell,
+    Batch,
+}
+
+impl std::str::FromStr for Format {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "ps1" | "powershell" => Ok(Self::PowerShell),
+            "cmd" | "bat" | "batch" => Ok(Self::Batch),
+            _ => Err(Error::UnknownFormat(s.into())),
+        }
+    }
+}
+
+/// スクリプトに書き出す 1 項目分の操作
+struct Step<'a> {
+    opt: &'a RegistryEditOption,
+    /// `None` は値の削除
+    value: Option<Value>,
+    comment: String,
+}
+
+/// プロファイルの内容を何度実行しても同じ結果になるスクリプトとして書き出す
+/// レジストリ以外の項目 (`tweaks`) が含まれている場合は `Error::NotRegistry` を返す
+pub fn export(
+    options: &[RegistryEditOption],
+    tweaks: &[Box<dyn Tweak>],
+    profile: &Profile,
+    format: Format,
+) -> Result<String> {
+    let mut steps = Vec::with_capacity(profile.values.len());
+    for (id, value) in profile.values.iter() {
+        let Some(opt) = options.iter().find(|x| x.id == *id) else {
+            return match tweak::find(tweaks, *id) {
+                Some(_) => Err(Error::NotRegistry(*id)),
+                None => Err(Error::UnknownOption(*id)),
+            };
+        };
+        let value = match value {
+            Some(v) => Some(opt.parse_value(v).map_err(|e| Error::Validation(*id, e))?),
+            None => {
+                opt.validate_absent()
+                    .map_err(|e| Error::Validation(*id, e))?;
+                None
+            }
+        };
+        let description = opt
+            .detect(value.as_ref())
+            .map(|x| x.description.as_str())
+            .unwrap_or("");
+        let comment = match value.as_ref() {
+            Some(v) => format!("[{}] {}: {} {}", opt.id, opt.title(), v, description),
+            None => format!("[{}] {}: (削除) {}", opt.id, opt.title(), description),
+        };
+        steps.push(Step {
+            opt,
+            value,
+            comment: comment.lines().collect::<Vec<_>>().join(" ").trim().into(),
+        });
+    }
+
+    match format {
+        Format::PowerShell => powershell::render(&profile.name, &steps),
+        Format::Batch => batch::render(&profile.name, &steps),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command::OptionCandidate;
+    use crate::win::reg::{DataType, RegDef, View};
+
+    fn option(id: u64, def: RegDef, value: &str) -> RegistryEditOption {
+        RegistryEditOption {
+            id,
+            label: def.value_name.clone(),
+            category: "テスト".into(),
+            tags: Vec::new(),
+            def,
+            values: vec![
+                OptionCandidate::new(value, "候補"),
+                OptionCandidate::absent("削除"),
+            ],
+            domain: Default::default(),
+            bits: None,
+            windows_default: None,
+            requires: Vec::new(),
+            conflicts_with: Vec::new(),
+            policies: Vec::new(),
+        }
+    }
+
+    /// 値の型ごとに 1 項目ずつと、値の削除、ワイルドカード文字を含むキー、32 ビットのビュー
+    fn fixture() -> (Vec<RegistryEditOption>, Profile) {
+        const KEY: &str = r"Software\Win11Tweaks\Test";
+        let values = [
+            (RegDef::hkcu(KEY, "DWord", DataType::DWord), "4294967295"),
+            (RegDef::hkcu(KEY, "QWord", DataType::QWord), "0x100000000"),
+            (
+                RegDef::hkcu(KEY, "String", DataType::String),
+                r#"it's 100% "quoted" & (escaped)\"#,
+            ),
+            (
+                RegDef::hkcu(KEY, "ExpandString", DataType::ExpandString),
+                r"%USERPROFILE%\bin",
+            ),
+            (
+                RegDef::hkcu(KEY, "MultiString", DataType::MultiString),
+                "first\nsecond item",
+            ),
+            (RegDef::hkcu(KEY, "Binary", DataType::Binary), "01,a0,ff"),
+            (RegDef::hkcu(KEY, "Deleted", DataType::DWord), "1"),
+            (
+                RegDef::hkcu(r"Software\Classes\*\shell\[test]", "", DataType::String),
+                "open",
+            ),
+            (
+                RegDef::hklm(r"SOFTWARE\Win11Tweaks", "Wow64", DataType::DWord)
+                    .in_view(View::Bit32),
+                "1",
+            ),
+        ];
+
+        let mut options = Vec::new();
+        let mut profile = Profile {
+            name: "golden".into(),
+            values: Default::default(),
+        };
+        for (i, (def, value)) in values.into_iter().enumerate() {
+            let id = i as u64 + 1;
+            let selected = match def.value_name.as_str() {
+                "Deleted" => None,
+                _ => Some(value.to_string()),
+            };
+            options.push(option(id, def, value));
+            profile.values.insert(id, selected);
+        }
+        (options, profile)
+    }
+
+    #[test]
+    fn powershell_matches_golden_file() {
+        let (options, profile) = fixture();
+        let text = export(&options, &[], &profile, Format::PowerShell).unwrap();
+        assert_eq!(text, include_str!("testdata/golden.ps1"));
+    }
+
+    #[test]
+    fn batch_matches_golden_file() {
+        let (options, profile) = fixture();
+        let text = export(&options, &[], &profile, Format::Batch).unwrap();
+        assert_eq!(text, include_str!("testdata/golden.cmd"));
+    }
+
+    #[test]
+    fn batch_rejects_multiline_strings() {
+        let (mut options, profile) = fixture();
+        options[2].values[0] = OptionCandidate::new("a\nb", "候補");
+        let mut profile = profile;
+        profile.values.insert(3, Some("a\nb".into()));
+        assert!(matches!(
+            export(&options, &[], &profile, Format::Batch),
+            Err(Error::Unrepresentable(3, _))
+        ));
+    }
+}
@@ -0,0 +1,158 @@
+use super::{Error, Result, Step};
+use crate::win::reg::{physical_sub_key, BitField, DataType, Root, Value};
+use std::fmt::Write;
+
+pub(super) fn render(name: &str, steps: &[Step]) -> Result<String> {
+    // Windows PowerShell 5.1 は BOM の無いファイルを既定のコード ページとして読む
+    let mut ret = String::from('\u{feff}');
+    let _ = writeln!(ret, "# Win11 Tweaks: {}", single_line(name));
+    let _ = writeln!(ret, "$ErrorActionPreference = 'Stop'");
+
+    for step in steps.iter() {
+        let def = &step.opt.def;
+        let name = if def.value_name.is_empty() {
+            quote("(default)")
+        } else {
+            quote(&def.value_name)
+        };
+
+        let _ = writeln!(ret);
+        let _ = writeln!(ret, "# {}", step.comment);
+        // 64 ビットの PowerShell で実行する前提で、32 ビットのビューは WOW6432Node のパスに書き込む
+        for view in def.view.targets() {
+            let sub_key = physical_sub_key(def.root, &def.sub_key, *view, true);
+            let path = quote(&format!("Registry::{}\\{sub_key}", def.root.long_name()));
+            match (step.value.as_ref(), step.opt.bits) {
+                (Some(v), Some(field)) => {
+                    let on = *v != Value::DWord(0);
+                    let _ = writeln!(ret, "{}", create_key(&path, def.root, &sub_key));
+                    let line = bits(step.opt.id, &path, &name, def.data_type, field, on)?;
+                    let _ = writeln!(ret, "{line}");
+                }
+                (Some(v), None) => {
+                    let _ = writeln!(ret, "{}", create_key(&path, def.root, &sub_key));
+                    let _ = writeln!(
+                        ret,
+                        "Set-ItemProperty -LiteralPath {path} -Name {name} -Value {} -Type {}",
+                        literal(v),
+                        kind(v)
+                    );
+                }
+                (None, _) => {
+                    let _ = writeln!(
+                        ret,
+                        "Remove-ItemProperty -LiteralPath {path} -Name {name} -ErrorAction SilentlyContinue"
+                    );
+                }
+            }
+        }
+    }
+
+    Ok(ret)
+}
+
+/// キーが無ければ作成する
+/// New-Item はパスの `*` や `[` をワイルドカードとして解釈するため、.NET の API で作成する
+fn create_key(path: &str, root: Root, sub_key: &str) -> String {
+    let hive = match root {
+        Root::CurrentUser | Root::User => "CurrentUser",
+        Root::Users => "Users",
+        Root::LocalMachine => "LocalMachine",
+    };
+    format!(
+        "if (-not (Test-Path -LiteralPath {path})) {{ \
+         ([Microsoft.Win32.RegistryKey]::OpenBaseKey('{hive}', 'Registry64')).CreateSubKey({}).Dispose() }}",
+        quote(sub_key)
+    )
+}
+
+/// 現在の値を読み取り、フラグのビットだけを書き換えて書き戻す
+fn bits(
+    id: u64,
+    path: &str,
+    name: &str,
+    data_type: DataType,
+    field: BitField,
+    on: bool,
+) -> Result<String> {
+    if data_type == DataType::Binary {
+        let (offset, mask) = field.locate();
+        let byte = match on {
+            true => format!("-bor 0x{mask:02x}"),
+            false => format!("-band 0x{:02x}", !mask),
+        };
+        return Ok(format!(
+            "$v = [byte[]](Get-ItemPropertyValue -LiteralPath {path} -Name {name}); \
+             $v[{offset}] = $v[{offset}] {byte}; \
+             Set-ItemProperty -LiteralPath {path} -Name {name} -Value $v -Type Binary"
+        ));
+    }
+
+    // 値が無い場合は 0 から始める
+    let zero = match data_type {
+        DataType::QWord => Value::QWord(0),
+        _ => Value::DWord(0),
+    };
+    let mask = match field.set(&zero, true)? {
+        Value::QWord(v) => format!("{}L", v as i64),
+        Value::DWord(v) => (v as i32).to_string(),
+        v => return Err(Error::Unrepresentable(id, v.to_string())),
+    };
+    let op = match on {
+        true => format!("-bor {mask}"),
+        false => format!("-band (-bnot {mask})"),
+    };
+    Ok(format!(
+        "$v = 0; try {{ $v = Get-ItemPropertyValue -LiteralPath {path} -Name {name} }} catch {{ }}; \
+         Set-ItemProperty -LiteralPath {path} -Name {name} -Value ($v {op}) -Type {}",
+        kind(&zero)
+    ))
+}
+
+/// Set-ItemProperty の `-Type` (RegistryValueKind の名前)
+fn kind(value: &Value) -> &'static str {
+    match value {
+        Value::Binary(_) => "Binary",
+        Value::DWord(_) => "DWord",
+        Value::QWord(_) => "QWord",
+        Value::String(_) => "String",
+        Value::MultiString(_) => "MultiString",
+        Value::ExpandString(_) => "ExpandString",
+    }
+}
+
+fn literal(value: &Value) -> String {
+    match value {
+        // 0x80000000 以上は符号付きで渡さないと範囲外エラーになる
+        Value::DWord(v) => (*v as i32).to_string(),
+        Value::QWord(v) => (*v as i64).to_string(),
+        Value::String(v) | Value::ExpandString(v) => quote(v),
+        Value::MultiString(v) => {
+            let items = v.iter().map(|x| quote(x)).collect::<Vec<_>>();
+            format!("([string[]]@({}))", items.join(", "))
+        }
+        Value::Binary(v) => {
+            let items = v.iter().map(|x| format!("0x{x:02x}")).collect::<Vec<_>>();
+            format!("([byte[]]@({}))", items.join(", "))
+        }
+    }
+}
+
+/// 単一引用符の文字列にする (変数展開や `%VAR%` の解釈は行われない)
+/// PowerShell は全角の引用符も単一引用符として扱うため同様に重ねる
+fn quote(s: &str) -> String {
+    let mut ret = String::with_capacity(s.len() + 2);
+    ret.push('\'');
+    for c in s.chars() {
+        if matches!(c, '\'' | '\u{2018}' | '\u{2019}' | '\u{201A}' | '\u{201B}') {
+            ret.push(c);
+        }
+        ret.push(c);
+    }
+    ret.push('\'');
+    ret
+}
+
+fn single_line(s: &str) -> String {
+    s.lines().collect::<Vec<_>>().join(" ")
+}
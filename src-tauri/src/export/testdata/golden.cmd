@@ -0,0 +1,33 @@
+@echo off
+chcp 65001 >nul
+rem Win11 Tweaks: golden
+setlocal
+
+rem [1] テスト - DWord: 4294967295 候補
+reg add "HKCU\Software\Win11Tweaks\Test" /v "DWord" /t REG_DWORD /d 4294967295 /f >nul || exit /b 1
+
+rem [2] テスト - QWord: 4294967296 候補
+reg add "HKCU\Software\Win11Tweaks\Test" /v "QWord" /t REG_QWORD /d 4294967296 /f >nul || exit /b 1
+
+rem [3] テスト - String: it's 100%% "quoted" & (escaped)\ 候補
+reg add "HKCU\Software\Win11Tweaks\Test" /v "String" /t REG_SZ /d "it's 100%% \"quoted\" & (escaped)\\" /f >nul || exit /b 1
+
+rem [4] テスト - ExpandString: %%USERPROFILE%%\bin 候補
+reg add "HKCU\Software\Win11Tweaks\Test" /v "ExpandString" /t REG_EXPAND_SZ /d "%%USERPROFILE%%\bin" /f >nul || exit /b 1
+
+rem [5] テスト - MultiString: first second item 候補
+reg add "HKCU\Software\Win11Tweaks\Test" /v "MultiString" /t REG_MULTI_SZ /d "first\0second item" /f >nul || exit /b 1
+
+rem [6] テスト - Binary: 01,a0,ff 候補
+reg add "HKCU\Software\Win11Tweaks\Test" /v "Binary" /t REG_BINARY /d 01a0ff /f >nul || exit /b 1
+
+rem [7] テスト - Deleted: (削除) 削除
+reg delete "HKCU\Software\Win11Tweaks\Test" /v "Deleted" /f >nul 2>&1
+
+rem [8] テスト - : open 候補
+reg add "HKCU\Software\Classes\*\shell\[test]" /ve /t REG_SZ /d "open" /f >nul || exit /b 1
+
+rem [9] テスト - Wow64: 1 候補
+reg add "HKLM\SOFTWARE\Win11Tweaks" /v "Wow64" /t REG_DWORD /d 1 /f /reg:32 >nul || exit /b 1
+
+exit /b 0
@@ -0,0 +1,37 @@
+﻿# Win11 Tweaks: golden
+$ErrorActionPreference = 'Stop'
+
+# [1] テスト - DWord: 4294967295 候補
+if (-not (Test-Path -LiteralPath 'Registry::HKEY_CURRENT_USER\Software\Win11Tweaks\Test')) { ([Microsoft.Win32.RegistryKey]::OpenBaseKey('CurrentUser', 'Registry64')).CreateSubKey('Software\Win11Tweaks\Test').Dispose() }
+Set-ItemProperty -LiteralPath 'Registry::HKEY_CURRENT_USER\Software\Win11Tweaks\Test' -Name 'DWord' -Value -1 -Type DWord
+
+# [2] テスト - QWord: 4294967296 候補
+if (-not (Test-Path -LiteralPath 'Registry::HKEY_CURRENT_USER\Software\Win11Tweaks\Test')) { ([Microsoft.Win32.RegistryKey]::OpenBaseKey('CurrentUser', 'Registry64')).CreateSubKey('Software\Win11Tweaks\Test').Dispose() }
+Set-ItemProperty -LiteralPath 'Registry::HKEY_CURRENT_USER\Software\Win11Tweaks\Test' -Name 'QWord' -Value 4294967296 -Type QWord
+
+# [3] テスト - String: it's 100% "quoted" & (escaped)\ 候補
+if (-not (Test-Path -LiteralPath 'Registry::HKEY_CURRENT_USER\Software\Win11Tweaks\Test')) { ([Microsoft.Win32.RegistryKey]::OpenBaseKey('CurrentUser', 'Registry64')).CreateSubKey('Software\Win11Tweaks\Test').Dispose() }
+Set-ItemProperty -LiteralPath 'Registry::HKEY_CURRENT_USER\Software\Win11Tweaks\Test' -Name 'String' -Value 'it''s 100% "quoted" & (escaped)\' -Type String
+
+# [4] テスト - ExpandString: %USERPROFILE%\bin 候補
+if (-not (Test-Path -LiteralPath 'Registry::HKEY_CURRENT_USER\Software\Win11Tweaks\Test')) { ([Microsoft.Win32.RegistryKey]::OpenBaseKey('CurrentUser', 'Registry64')).CreateSubKey('Software\Win11Tweaks\Test').Dispose() }
+Set-ItemProperty -LiteralPath 'Registry::HKEY_CURRENT_USER\Software\Win11Tweaks\Test' -Name 'ExpandString' -Value '%USERPROFILE%\bin' -Type ExpandString
+
+# [5] テスト - MultiString: first second item 候補
+if (-not (Test-Path -LiteralPath 'Registry::HKEY_CURRENT_USER\Software\Win11Tweaks\Test')) { ([Microsoft.Win32.RegistryKey]::OpenBaseKey('CurrentUser', 'Registry64')).CreateSubKey('Software\Win11Tweaks\Test').Dispose() }
+Set-ItemProperty -LiteralPath 'Registry::HKEY_CURRENT_USER\Software\Win11Tweaks\Test' -Name 'MultiString' -Value ([string[]]@('first', 'second item')) -Type MultiString
+
+# [6] テスト - Binary: 01,a0,ff 候補
+if (-not (Test-Path -LiteralPath 'Registry::HKEY_CURRENT_USER\Software\Win11Tweaks\Test')) { ([Microsoft.Win32.RegistryKey]::OpenBaseKey('CurrentUser', 'Registry64')).CreateSubKey('Software\Win11Tweaks\Test').Dispose() }
+Set-ItemProperty -LiteralPath 'Registry::HKEY_CURRENT_USER\Software\Win11Tweaks\Test' -Name 'Binary' -Value ([byte[]]@(0x01, 0xa0, 0xff)) -Type Binary
+
+# [7] テスト - Deleted: (削除) 削除
+Remove-ItemProperty -LiteralPath 'Registry::HKEY_CURRENT_USER\Software\Win11Tweaks\Test' -Name 'Deleted' -ErrorAction SilentlyContinue
+
+# [8] テスト - : open 候補
+if (-not (Test-Path -LiteralPath 'Registry::HKEY_CURRENT_USER\Software\Classes\*\shell\[test]')) { ([Microsoft.Win32.RegistryKey]::OpenBaseKey('CurrentUser', 'Registry64')).CreateSubKey('Software\Classes\*\shell\[test]').Dispose() }
+Set-ItemProperty -LiteralPath 'Registry::HKEY_CURRENT_USER\Software\Classes\*\shell\[test]' -Name '(default)' -Value 'open' -Type String
+
+# [9] テスト - Wow64: 1 候補
+if (-not (Test-Path -LiteralPath 'Registry::HKEY_LOCAL_MACHINE\SOFTWARE\WOW6432Node\Win11Tweaks')) { ([Microsoft.Win32.RegistryKey]::OpenBaseKey('LocalMachine', 'Registry64')).CreateSubKey('SOFTWARE\WOW6432Node\Win11Tweaks').Dispose() }
+Set-ItemProperty -LiteralPath 'Registry::HKEY_LOCAL_MACHINE\SOFTWARE\WOW6432Node\Win11Tweaks' -Name 'Wow64' -Value 1 -Type DWord
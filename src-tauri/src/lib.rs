@@ -1,5 +1,21 @@
+pub mod admx;
+pub mod audit;
+pub mod bulk;
 pub mod command;
+pub mod context_menu;
+pub mod domain;
+pub mod export;
+pub mod logging;
+pub mod plan;
+pub mod policy;
+pub mod profile;
 mod reg_edit_options;
+pub mod regfile;
+pub mod search;
+pub mod startup;
+pub mod task;
+pub mod tweak;
+pub mod watch;
 pub mod win;
 
 pub use reg_edit_options::default_edit_options;
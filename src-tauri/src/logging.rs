@@ -0,0 +1,31 @@
+use std::path::Path;
+use tracing::level_filters::LevelFilter;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+
+/// 残しておくログファイルの数 (1 日 1 ファイル)
+const MAX_LOG_FILES: usize = 14;
+
+/// `dir` に日ごとに切り替わるログファイルを作成し、以降のログの出力先にする
+/// デバッグビルドでは標準出力にも出力する
+pub fn init(dir: &Path) -> anyhow::Result<()> {
+    let appender = RollingFileAppender::builder()
+        .rotation(Rotation::DAILY)
+        .filename_prefix("win11-tweaks")
+        .filename_suffix("log")
+        .max_log_files(MAX_LOG_FILES)
+        .build(dir)?;
+
+    let file = tracing_subscriber::fmt::layer()
+        .with_writer(appender)
+        .with_ansi(false);
+    let stdout = cfg!(debug_assertions).then(tracing_subscriber::fmt::layer);
+    tracing_subscriber::registry()
+        .with(LevelFilter::DEBUG)
+        .with(file)
+        .with(stdout)
+        .try_init()?;
+
+    Ok(())
+}
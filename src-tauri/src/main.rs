@@ -1,40 +1,161 @@
 // Prevents additional console window on Windows in release, DO NOT REMOVE!!
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+use serde::Serialize;
 use std::collections::HashMap;
-use std::sync::LazyLock;
-use win11_tweaks_lib::command::RegistryEditOption;
+use std::path::PathBuf;
+use std::sync::{LazyLock, Mutex};
+use tauri::{AppHandle, Emitter, Manager, State};
+use win11_tweaks_lib::admx::{self, PolicyFile, StringTable};
+use win11_tweaks_lib::audit::{self, Baseline};
+use win11_tweaks_lib::bulk;
+use win11_tweaks_lib::command::{OptionCandidate, RegistryEditOption};
+use win11_tweaks_lib::context_menu::{self, Journal, MenuEntry};
+use win11_tweaks_lib::domain::ValueDomain;
+use win11_tweaks_lib::export;
+use win11_tweaks_lib::logging;
+use win11_tweaks_lib::plan::{
+    self, LiveRegistry, Plan, Planner, RegistryOnly, ResetScope, Selections,
+};
+use win11_tweaks_lib::policy::Layer;
+use win11_tweaks_lib::profile::Profile;
+use win11_tweaks_lib::regfile::{RegFile, RegFileState};
+use win11_tweaks_lib::search;
+use win11_tweaks_lib::startup::{self, StartupEntry};
+use win11_tweaks_lib::task::{CancelToken, Progress};
+use win11_tweaks_lib::tweak::{self, Tweak};
+use win11_tweaks_lib::watch::{EventSink, ValueChanged, Watcher};
 use win11_tweaks_lib::win;
 
-static EDIT_OPT_LIST: LazyLock<Vec<RegistryEditOption>> =
-    LazyLock::new(win11_tweaks_lib::default_edit_options);
+/// 画面とコマンドラインで扱うすべての設定項目 (レジストリ以外の項目の ID はレジストリの項目の後に続ける)
+static TWEAKS: LazyLock<Vec<Box<dyn Tweak>>> =
+    LazyLock::new(|| tweak::with_registry(win11_tweaks_lib::default_edit_options()));
 
-static EDIT_OPT_MAP: LazyLock<HashMap<u64, &RegistryEditOption>> = LazyLock::new(|| {
-    EDIT_OPT_LIST.iter().fold(HashMap::new(), |mut acc, x| {
-        acc.insert(x.id, x);
-        acc
-    })
+/// レジストリの項目の定義 (前提や競合の判定、監査、スクリプト出力、監視で使う)
+static EDIT_OPT_LIST: LazyLock<Vec<RegistryEditOption>> = LazyLock::new(|| {
+    TWEAKS
+        .iter()
+        .filter_map(|x| x.registry())
+        .cloned()
+        .collect()
 });
 
+fn planner() -> Planner<'static> {
+    Planner::new(&EDIT_OPT_LIST).with_tweaks(&TWEAKS)
+}
+
+/// 画面側のログを同じ出力先に記録する
 #[tauri::command]
 fn log(text: &str) {
-    println!("{text}");
+    tracing::info!(target: "frontend", "{text}");
 }
 
-fn get_component_html(cmd: &RegistryEditOption) -> String {
-    let items = cmd
-        .values
+/// 自由入力欄に表示する入力可能な値の説明
+fn domain_hint(domain: &ValueDomain) -> String {
+    match domain {
+        ValueDomain::Candidates => String::new(),
+        ValueDomain::Range { min, max } => format!("{min} 〜 {max}"),
+        ValueDomain::Pattern(re) => format!("正規表現: {}", re.as_str()),
+        ValueDomain::Bitmask(flags) => {
+            let names = flags.iter().map(|x| x.name.as_str()).collect::<Vec<_>>();
+            format!("フラグ: {}", names.join(" | "))
+        }
+    }
+}
+
+fn candidates_html(values: &[OptionCandidate]) -> String {
+    values
         .iter()
         .map(|x| {
-            format!(
-                "<option value=\"{}\">{}: {}</option>",
-                x.value, x.value, x.description
-            )
+            if x.absent {
+                format!(
+                    "<option value=\"\" data-absent=\"1\">(削除): {}</option>",
+                    x.description
+                )
+            } else {
+                format!(
+                    "<option value=\"{}\">{}: {}</option>",
+                    x.value, x.value, x.description
+                )
+            }
         })
         .collect::<Vec<String>>()
-        .join("");
+        .join("")
+}
+
+/// 候補から選ぶ入力欄 (先頭の「変更しない」を選んでいる間は計画に含めない)
+fn select_html(values: &[OptionCandidate]) -> String {
     format!(
-        r#"<div class="group" data-cmdid="{}">
+        r#"<select class="combobox"><option value="" data-keep="1" selected>(変更しない)</option>{}</select>"#,
+        candidates_html(values)
+    )
+}
+
+fn get_component_html(cmd: &RegistryEditOption) -> String {
+    let items = candidates_html(&cmd.values);
+    // フラグ 1 つ分の項目はチェックボックス、候補以外の値も受け付ける項目は自由入力にする
+    let input = match cmd.domain {
+        _ if cmd.bits.is_some() => {
+            let on = cmd
+                .values
+                .iter()
+                .find(|x| x.value == "1")
+                .map(|x| x.description.as_str())
+                .unwrap_or("有効");
+            format!(
+                r#"<label class="toggle"><input type="checkbox" class="combobox" />{on}</label>"#
+            )
+        }
+        ValueDomain::Candidates => select_html(&cmd.values),
+        _ => format!(
+            r#"<input type="text" class="combobox" list="candidates-{}" placeholder="{}" /><datalist id="candidates-{}">{items}</datalist>"#,
+            cmd.id,
+            domain_hint(&cmd.domain),
+            cmd.id
+        ),
+    };
+    // 初期状態が分かっている項目だけ戻すボタンを表示する
+    let reset = match cmd.windows_default {
+        Some(_) => r#"<button class="button button-reset">初期状態に戻す</button>"#,
+        None => "",
+    };
+    group_html(
+        cmd.id,
+        &cmd.category,
+        &cmd.label,
+        &cmd.def.to_string(),
+        &input,
+        reset,
+    )
+}
+
+/// レジストリ以外の設定項目は候補から選ぶだけにする
+fn get_tweak_html(tweak: &dyn Tweak) -> String {
+    let input = select_html(tweak.candidates());
+    let reset = match tweak.default_selection() {
+        Some(_) => r#"<button class="button button-reset">初期状態に戻す</button>"#,
+        None => "",
+    };
+    group_html(
+        tweak.id(),
+        tweak.category(),
+        tweak.label(),
+        &tweak.describe(),
+        &input,
+        reset,
+    )
+}
+
+fn group_html(
+    id: u64,
+    category: &str,
+    label: &str,
+    target: &str,
+    input: &str,
+    reset: &str,
+) -> String {
+    format!(
+        r#"<div class="group" data-cmdid="{}" data-category="{}">
   <div class="group-header">{}</div>
   <div class="group-body">
     <div class="input-row">
@@ -42,61 +163,353 @@ fn get_component_html(cmd: &RegistryEditOption) -> String {
       <button class="button button-check">チェック</button>
     </div>
     <div class="input-row">
-      <select class="combobox">{}</select>
+      {}
       <button class="button button-exec">値設定</button>
+      {}
     </div>
   </div>
 </div>"#,
-        cmd.id, cmd.label, cmd.def, items
+        id, category, label, target, input, reset
     )
 }
 
 #[tauri::command]
 fn get_default_components() -> Vec<String> {
-    EDIT_OPT_LIST
+    TWEAKS
         .iter()
-        .map(get_component_html)
+        .map(|x| match x.registry() {
+            Some(opt) => get_component_html(opt),
+            None => get_tweak_html(x.as_ref()),
+        })
         .collect::<Vec<String>>()
 }
 
 #[tauri::command]
+#[tracing::instrument]
 fn get_registry_value(cmd_id: u64) {
-    use win::reg::Registry;
-    println!("get_registry_value: Command ID={cmd_id}");
-    if let Some(cmd) = EDIT_OPT_MAP.get(&cmd_id) {
-        let r = Registry::new(cmd.def.root(), &cmd.def.sub_key, &cmd.def.value_name);
-        match r.get_value(cmd.def.data_type) {
-            Ok(v) => win::message_box(format!("現在の値: {v}"), "Win11 Tweaks"),
-            Err(e) => win::message_box(format!("{e}"), "Win11 Tweaks"),
-        }
-    } else {
+    let Some(tweak) = tweak::find(&TWEAKS, cmd_id) else {
+        win::message_box("コマンドが見つかりませんでした", "Win11 Tweaks");
+        return;
+    };
+    let state = tweak.read_state();
+    let value = match (state.value, state.description) {
+        (Some(v), Some(d)) => format!("{v} ({d})"),
+        (Some(v), None) => v,
+        (None, _) => "(なし)".to_string(),
+    };
+    let text = match (state.error, state.layer) {
+        (Some(e), _) => e,
+        (None, Some(layer)) if layer != Layer::UserPreference => format!(
+            "有効な値: {value} ({})\nポリシーで上書きされているため、設定を変更しても反映されません",
+            layer.label()
+        ),
+        (None, _) => format!("現在の値: {value}"),
+    };
+    win::message_box(text, "Win11 Tweaks");
+}
+
+/// `value` が `None` の場合は値を削除する
+#[tauri::command]
+#[tracing::instrument]
+fn set_registry_value(cmd_id: u64, value: Option<String>) {
+    let Some(tweak) = tweak::find(&TWEAKS, cmd_id) else {
         win::message_box("コマンドが見つかりませんでした", "Win11 Tweaks");
+        return;
+    };
+    match tweak.apply(value.as_deref()) {
+        Ok(()) => (),
+        Err(tweak::Error::Virtualized(_)) => win::message_box(
+            format!(
+                "「{}」は管理者権限が無いため、現在のユーザーだけの仮想的な場所に書き込まれました。管理者として実行してください",
+                tweak.title()
+            ),
+            "Win11 Tweaks",
+        ),
+        Err(tweak::Error::Reverted(_)) => win::message_box(
+            format!(
+                "「{}」を書き込みましたが、別のプログラムによって元に戻されました",
+                tweak.title()
+            ),
+            "Win11 Tweaks",
+        ),
+        Err(e) => win::message_box(e.to_string(), "Win11 Tweaks"),
     }
 }
 
 #[tauri::command]
-fn set_registry_value(cmd_id: u64, value: &str) {
-    use win::reg::Registry;
-    println!("set_registry_value: Command ID={cmd_id}, Value={value}");
-    if let Some(cmd) = EDIT_OPT_MAP.get(&cmd_id) {
-        let r = Registry::new(cmd.def.root(), &cmd.def.sub_key, &cmd.def.value_name);
-        match r.set_value(cmd.def.data_type, value) {
-            Ok(_) => (),
-            Err(e) => win::message_box(format!("{e}"), "Win11 Tweaks"),
+#[tracing::instrument(err)]
+fn plan_registry_values(selections: Selections) -> Result<Plan, String> {
+    planner()
+        .plan(&selections, &LiveRegistry)
+        .map_err(|e| e.to_string())
+}
+
+/// 指定された項目の現在の状態をまとめて返す (同じキーは一度だけ開く)
+#[tauri::command]
+#[tracing::instrument(err)]
+async fn get_values(ids: Vec<u64>) -> Result<Vec<ValueChanged>, String> {
+    run_blocking(move || bulk::get_values(&TWEAKS, &ids)).await
+}
+
+/// 選択された値をまとめて書き込み、項目ごとの結果を返す
+#[tauri::command]
+#[tracing::instrument(err)]
+async fn apply_values(values: Selections) -> Result<Vec<bulk::ApplyResult>, String> {
+    run_blocking(move || bulk::apply_values(&EDIT_OPT_LIST, &TWEAKS, &values)).await
+}
+
+/// 検索語に一致する項目の ID を返す (空の場合はすべての項目)
+#[tauri::command]
+fn search_options(query: &str) -> Vec<u64> {
+    search::search(&TWEAKS, query)
+        .iter()
+        .map(|x| x.id())
+        .collect()
+}
+
+/// Windows の初期状態に戻す計画を作成する (適用は `apply_plan` で行う)
+#[tauri::command]
+#[tracing::instrument(err)]
+fn plan_reset(scope: ResetScope) -> Result<Plan, String> {
+    let selections = plan::default_selections(&TWEAKS, &scope).map_err(|e| e.to_string())?;
+    plan_registry_values(selections)
+}
+
+/// 右クリックメニューの変更の記録 (元に戻すときに使う)
+fn context_menu_journal(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    Ok(dir.join("context_menu.json"))
+}
+
+/// 記録を読み込んで `f` で書き換え、保存してからメニューの項目を読み直す
+/// `f` が途中で失敗した場合も、それまでの記録は保存する
+fn update_context_menu(
+    path: PathBuf,
+    f: impl FnOnce(&mut Journal) -> context_menu::Result<()>,
+) -> context_menu::Result<Vec<MenuEntry>> {
+    let mut journal = Journal::load(&path)?;
+    let ret = f(&mut journal);
+    journal.save(&path)?;
+    ret?;
+    context_menu::scan()
+}
+
+/// 進み具合を "task-progress" で通知しながら右クリックメニューの項目を列挙する
+#[tauri::command]
+#[tracing::instrument(skip(app, tasks), err)]
+async fn get_context_menu(
+    app: AppHandle,
+    tasks: State<'_, Tasks>,
+    task_id: u64,
+) -> Result<Vec<MenuEntry>, String> {
+    run_task(app, &tasks, task_id, |cancel, progress| {
+        context_menu::scan_with(cancel, progress)
+    })
+    .await?
+    .map_err(|e| e.to_string())
+}
+
+/// 右クリックメニューの項目を有効または無効にし、変更後の一覧を返す
+#[tauri::command]
+#[tracing::instrument(skip(app), err)]
+async fn set_context_menu_entry(
+    app: AppHandle,
+    id: String,
+    enabled: bool,
+) -> Result<Vec<MenuEntry>, String> {
+    let path = context_menu_journal(&app)?;
+    run_blocking(move || {
+        update_context_menu(path, |journal| {
+            let entry = context_menu::scan()?
+                .into_iter()
+                .find(|x| x.id == id)
+                .ok_or_else(|| context_menu::Error::UnknownEntry(id.clone()))?;
+            context_menu::set_enabled(&entry, enabled, None, journal)
+        })
+    })
+    .await?
+    .map_err(|e| e.to_string())
+}
+
+/// 右クリックメニューの項目 (`None` はすべて) を、このツールで変更する前の状態に戻す
+#[tauri::command]
+#[tracing::instrument(skip(app), err)]
+async fn revert_context_menu(app: AppHandle, id: Option<String>) -> Result<Vec<MenuEntry>, String> {
+    let path = context_menu_journal(&app)?;
+    run_blocking(move || update_context_menu(path, |journal| journal.revert(id.as_deref())))
+        .await?
+        .map_err(|e| e.to_string())
+}
+
+/// 進み具合を "task-progress" で通知しながらスタートアップ アプリを列挙する
+#[tauri::command]
+#[tracing::instrument(skip(app, tasks), err)]
+async fn get_startup_apps(
+    app: AppHandle,
+    tasks: State<'_, Tasks>,
+    task_id: u64,
+) -> Result<Vec<StartupEntry>, String> {
+    run_task(app, &tasks, task_id, |cancel, progress| {
+        startup::list_with(cancel, progress)
+    })
+    .await?
+    .map_err(|e| e.to_string())
+}
+
+/// スタートアップ アプリを有効または無効にし、変更後の一覧を返す
+#[tauri::command]
+#[tracing::instrument(err)]
+async fn set_startup_app(id: String, enabled: bool) -> Result<Vec<StartupEntry>, String> {
+    run_blocking(move || {
+        let entry = startup::list()?
+            .into_iter()
+            .find(|x| x.id == id)
+            .ok_or_else(|| startup::Error::UnknownEntry(id.clone()))?;
+        startup::set_enabled(&entry, enabled)?;
+        startup::list()
+    })
+    .await?
+    .map_err(|e| e.to_string())
+}
+
+/// 実行中の処理 (画面から指定された ID ごとの中断要求)
+#[derive(Default)]
+struct Tasks(Mutex<HashMap<u64, CancelToken>>);
+
+#[derive(Clone, Serialize)]
+struct TaskProgress {
+    task_id: u64,
+    #[serde(flatten)]
+    progress: Progress,
+}
+
+/// 同期処理を IPC のスレッドを塞がないよう別のスレッドで実行する
+/// ログがコマンドのスパンに紐付くよう、呼び出し元のスパンの中で実行する
+async fn run_blocking<T: Send + 'static>(
+    f: impl FnOnce() -> T + Send + 'static,
+) -> Result<T, String> {
+    let span = tracing::Span::current();
+    tauri::async_runtime::spawn_blocking(move || span.in_scope(f))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 進み具合を "task-progress" で通知しながら別のスレッドで処理を行う (`cancel_task` で中断できる)
+async fn run_task<T: Send + 'static>(
+    app: AppHandle,
+    tasks: &Tasks,
+    task_id: u64,
+    f: impl FnOnce(&CancelToken, &mut dyn FnMut(&Progress)) -> T + Send + 'static,
+) -> Result<T, String> {
+    let cancel = CancelToken::new();
+    tasks.0.lock().unwrap().insert(task_id, cancel.clone());
+    let ret = run_blocking(move || {
+        f(&cancel, &mut |progress| {
+            let payload = TaskProgress {
+                task_id,
+                progress: progress.clone(),
+            };
+            if let Err(e) = app.emit("task-progress", payload) {
+                tracing::warn!("Failed to emit task-progress: {e}");
+            }
+        })
+    })
+    .await;
+    tasks.0.lock().unwrap().remove(&task_id);
+    ret
+}
+
+/// 進み具合を "task-progress" で通知しながら計画を適用する
+#[tauri::command]
+#[tracing::instrument(skip(app, tasks, plan), fields(entries = plan.entries.len()), err)]
+async fn apply_plan(
+    app: AppHandle,
+    tasks: State<'_, Tasks>,
+    task_id: u64,
+    plan: Plan,
+) -> Result<(), String> {
+    planner().validate(&plan).map_err(|e| e.to_string())?;
+
+    run_task(app, &tasks, task_id, move |cancel, progress| {
+        planner().apply_with(&plan, cancel, progress)
+    })
+    .await?
+    .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+#[tracing::instrument(skip(tasks))]
+fn cancel_task(tasks: State<'_, Tasks>, task_id: u64) {
+    if let Some(cancel) = tasks.0.lock().unwrap().get(&task_id) {
+        cancel.cancel();
+    }
+}
+
+struct TauriEventSink(AppHandle);
+
+impl EventSink for TauriEventSink {
+    fn value_changed(&self, change: &ValueChanged) {
+        if let Err(e) = self.0.emit("registry-value-changed", change) {
+            tracing::warn!("Failed to emit registry-value-changed: {e}");
         }
-    } else {
-        win::message_box("コマンドが見つかりませんでした", "Win11 Tweaks");
     }
 }
 
+fn watch_registry(handle: AppHandle) {
+    let mut watcher = Watcher::new(&EDIT_OPT_LIST);
+    let keys = watcher
+        .keys()
+        .iter()
+        .map(|(root, sub_key, view)| (root.hkey(), sub_key.clone(), *view))
+        .collect::<Vec<_>>();
+    let mut notifier = match win::reg::KeyNotifier::new(&keys) {
+        Ok(v) => v,
+        Err(e) => {
+            tracing::error!("Failed to watch registry: {e}");
+            return;
+        }
+    };
+
+    watcher.prime(&LiveRegistry);
+    watcher.run(&mut notifier, &LiveRegistry, &TauriEventSink(handle));
+}
+
 fn inner_run() -> anyhow::Result<()> {
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
+        .manage(Tasks::default())
+        .setup(|app| {
+            match app.path().app_log_dir() {
+                Ok(dir) => {
+                    if let Err(e) = logging::init(&dir) {
+                        eprintln!("Failed to initialize logging: {e}");
+                    }
+                }
+                Err(e) => eprintln!("Failed to resolve log directory: {e}"),
+            }
+
+            let handle = app.handle().clone();
+            std::thread::spawn(move || watch_registry(handle));
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             log,
             get_default_components,
+            search_options,
             get_registry_value,
             set_registry_value,
+            get_values,
+            apply_values,
+            plan_registry_values,
+            plan_reset,
+            apply_plan,
+            cancel_task,
+            get_context_menu,
+            set_context_menu_entry,
+            revert_context_menu,
+            get_startup_apps,
+            set_startup_app,
         ])
         .run(tauri::generate_context!())
         .map_err(|_| anyhow::anyhow!("error while running tauri application"))?;
@@ -111,6 +524,174 @@ pub fn run() {
     }
 }
 
+/// ユーザーごとのキーを `--user` で指定されたユーザー (省略時は現在のユーザー) のキーにする
+fn options_for(user: Option<&String>) -> Vec<RegistryEditOption> {
+    let target = match user {
+        Some(sid) => win::reg::Target::Sid(sid.clone()),
+        None => win::reg::Target::CurrentUser,
+    };
+    EDIT_OPT_LIST
+        .iter()
+        .map(|x| x.for_target(&target))
+        .collect()
+}
+
+/// `audit <baseline.json> [--reg <export.reg>] [--user <SID>] [--format json|markdown|junit] [--output <path>]`
+/// 期待値どおりであれば `true` を返す
+fn run_audit(args: &[String]) -> anyhow::Result<bool> {
+    let mut baseline = None;
+    let mut reg = None;
+    let mut user = None;
+    let mut format = audit::Format::Json;
+    let mut output = None;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--reg" => reg = iter.next(),
+            "--user" => user = iter.next(),
+            "--format" => {
+                format = iter
+                    .next()
+                    .ok_or_else(|| anyhow::anyhow!("--format requires a value"))?
+                    .parse()?
+            }
+            "--output" => output = iter.next(),
+            _ if baseline.is_none() => baseline = Some(arg),
+            _ => anyhow::bail!("Unknown argument: {arg}"),
+        }
+    }
+
+    let baseline =
+        Baseline::load(baseline.ok_or_else(|| anyhow::anyhow!("Baseline not specified"))?)?;
+    let options = options_for(user);
+    // 別のユーザーを指定した場合、レジストリ以外の項目は現在のユーザーの状態になるため判定しない
+    let report = match (reg, user) {
+        (Some(path), _) => {
+            let state = RegFileState::new(RegFile::load(path)?);
+            audit::audit(&options, &TWEAKS, &baseline, &state, path.as_str())
+        }
+        (None, Some(_)) => audit::audit(
+            &options,
+            &TWEAKS,
+            &baseline,
+            &RegistryOnly(LiveRegistry),
+            "registry",
+        ),
+        (None, None) => audit::audit(&options, &TWEAKS, &baseline, &LiveRegistry, "registry"),
+    };
+
+    let text = report.render(format)?;
+    match output {
+        Some(path) => std::fs::write(path, text)?,
+        None => print!("{text}"),
+    }
+
+    Ok(report.is_compliant())
+}
+
+/// `export <profile.json> [--user <SID>] [--format powershell|cmd] [--output <path>]`
+fn run_export(args: &[String]) -> anyhow::Result<bool> {
+    let mut profile = None;
+    let mut user = None;
+    let mut format = export::Format::PowerShell;
+    let mut output = None;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--format" => {
+                format = iter
+                    .next()
+                    .ok_or_else(|| anyhow::anyhow!("--format requires a value"))?
+                    .parse()?
+            }
+            "--output" => output = iter.next(),
+            "--user" => user = iter.next(),
+            _ if profile.is_none() => profile = Some(arg),
+            _ => anyhow::bail!("Unknown argument: {arg}"),
+        }
+    }
+
+    let profile = Profile::load(profile.ok_or_else(|| anyhow::anyhow!("Profile not specified"))?)?;
+    let text = export::export(&options_for(user), &TWEAKS, &profile, format)?;
+    match output {
+        Some(path) => std::fs::write(path, text)?,
+        None => print!("{text}"),
+    }
+
+    Ok(true)
+}
+
+/// `admx <file.admx> <file.adml> [NAME...] [--output <path>]`
+/// 取り込んだときの設定項目 (表示名、レジストリのパス、候補) を表示する
+fn run_admx(args: &[String]) -> anyhow::Result<bool> {
+    let mut paths = Vec::new();
+    let mut names = Vec::new();
+    let mut output = None;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--output" => output = iter.next(),
+            _ if paths.len() < 2 => paths.push(arg),
+            _ => names.push(arg.as_str()),
+        }
+    }
+    let [admx_path, adml_path] = paths[..] else {
+        anyhow::bail!("ADMX and ADML files not specified");
+    };
+
+    let file = PolicyFile::load(admx_path)?;
+    let strings = StringTable::load(adml_path)?;
+    // 組み込みの項目の後に続けた場合の ID
+    let first_id = TWEAKS.iter().map(|x| x.id()).max().unwrap_or(0) + 1;
+    let options = admx::import(&file, &strings, &names, first_id)?;
+
+    let mut text = String::new();
+    for opt in options.iter() {
+        text.push_str(&format!("[{}] {}\n", opt.id, opt.title()));
+        text.push_str(&format!("  {}\n", opt.def));
+        match &opt.domain {
+            ValueDomain::Range { min, max } => text.push_str(&format!("  {min}..={max}\n")),
+            ValueDomain::Pattern(re) => text.push_str(&format!("  /{re}/\n")),
+            _ => (),
+        }
+        for candidate in opt.values.iter() {
+            let value = match candidate.absent {
+                true => "(削除)",
+                false => candidate.value.as_str(),
+            };
+            text.push_str(&format!("    {value}: {}\n", candidate.description));
+        }
+    }
+    match output {
+        Some(path) => std::fs::write(path, text)?,
+        None => print!("{text}"),
+    }
+
+    Ok(true)
+}
+
 fn main() {
-    run()
+    let args = std::env::args().skip(1).collect::<Vec<String>>();
+    let command: fn(&[String]) -> anyhow::Result<bool> = match args.first().map(String::as_str) {
+        Some("audit") => run_audit,
+        Some("export") => run_export,
+        Some("admx") => run_admx,
+        _ => return run(),
+    };
+
+    win::attach_parent_console();
+    let ret = command(&args[1..]);
+
+    let code = match ret {
+        Ok(true) => 0,
+        Ok(false) => 1,
+        Err(e) => {
+            eprintln!("{e}");
+            2
+        }
+    };
+    std::process::exit(code);
 }
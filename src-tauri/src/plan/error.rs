@@ -0,0 +1,60 @@
+use crate::domain::ValidationError;
+use crate::tweak;
+use crate::win;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Debug)]
+pub enum Error {
+    UnknownOption(u64),
+    /// Windows の初期状態が登録されていない
+    NoWindowsDefault(u64),
+    Validation(u64, ValidationError),
+    /// 計画の書き込み先が項目の定義と異なる
+    TargetMismatch(u64, String),
+    /// 同時に設定できない項目の組み合わせ
+    Conflict(u64, u64),
+    /// 適用が中断された (中断までに適用した手順の数)
+    Cancelled(usize),
+    Registry(win::reg::Error),
+    Tweak(tweak::Error),
+}
+
+impl std::error::Error for Error {}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnknownOption(id) => write!(f, "Unknown option (ID={id})"),
+            Self::NoWindowsDefault(id) => write!(f, "Windows default is unknown (ID={id})"),
+            Self::Validation(id, e) => write!(f, "{e} (ID={id})"),
+            Self::TargetMismatch(id, target) => {
+                write!(
+                    f,
+                    "Plan target does not match the option ({target}) (ID={id})"
+                )
+            }
+            Self::Conflict(id, other) => {
+                write!(
+                    f,
+                    "Option conflicts with another option (ID={id}, ID={other})"
+                )
+            }
+            Self::Cancelled(n) => write!(f, "Cancelled after {n} steps"),
+            Self::Registry(e) => e.fmt(f),
+            Self::Tweak(e) => e.fmt(f),
+        }
+    }
+}
+
+impl From<win::reg::Error> for Error {
+    fn from(value: win::reg::Error) -> Self {
+        Error::Registry(value)
+    }
+}
+
+impl From<tweak::Error> for Error {
+    fn from(value: tweak::Error) -> Self {
+        Error::Tweak(value)
+    }
+}
@@ -0,0 +1,10 @@
+mod error;
+mod planner;
+mod reset;
+mod rules;
+mod state;
+
+pub use error::{Error, Result};
+pub use planner::*;
+pub use reset::{default_selections, ResetScope};
+pub use state::{CurrentState, LiveRegistry, RegistryOnly};
@@ -0,0 +1,585 @@
+use super::{rules, CurrentState, Error, Result};
+use crate::command::RegistryEditOption;
+use crate::task::{CancelToken, Progress};
+use crate::tweak::Tweak;
+use crate::win::reg::{RegDef, Registry, Value};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// 設定項目の ID と選択された候補値の組 (`None` は値を削除する候補)
+pub type Selections = BTreeMap<u64, Option<String>>;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum PlanAction {
+    CreateKey,
+    SetValue {
+        old: Option<Value>,
+        new: Value,
+    },
+    DeleteValue {
+        old: Value,
+    },
+    /// レジストリ以外の項目に候補を適用する (`subject` は操作の対象、`None` は値が存在しない候補)
+    ApplyTweak {
+        subject: String,
+        old: Option<String>,
+        new: Option<String>,
+    },
+    NoOp,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlanEntry {
+    pub id: u64,
+    pub label: String,
+    /// 書き込むレジストリの値 (レジストリ以外の項目は `None`)
+    pub target: Option<RegDef>,
+    pub action: PlanAction,
+}
+
+/// 適用はできるが確認が必要な点
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlanWarning {
+    pub id: u64,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Plan {
+    pub entries: Vec<PlanEntry>,
+    #[serde(default)]
+    pub warnings: Vec<PlanWarning>,
+}
+
+impl Plan {
+    pub fn has_changes(&self) -> bool {
+        self.entries.iter().any(|x| x.action != PlanAction::NoOp)
+    }
+}
+
+pub struct Planner<'a> {
+    options: &'a [RegistryEditOption],
+    /// レジストリ以外の項目 (レジストリの項目が含まれていても `options` を優先する)
+    tweaks: &'a [Box<dyn Tweak>],
+}
+
+impl<'a> Planner<'a> {
+    pub fn new(options: &'a [RegistryEditOption]) -> Self {
+        Self {
+            options,
+            tweaks: &[],
+        }
+    }
+
+    pub fn with_tweaks(mut self, tweaks: &'a [Box<dyn Tweak>]) -> Self {
+        self.tweaks = tweaks;
+        self
+    }
+
+    /// 計画の内容をそのまま適用する
+    pub fn apply(&self, plan: &Plan) -> Result<()> {
+        self.apply_with(plan, &CancelToken::new(), |_| ())
+    }
+
+    /// 手順ごとに進み具合を通知しながら適用する
+    /// 中断された場合、それまでに適用した内容は元に戻さない
+    pub fn apply_with(
+        &self,
+        plan: &Plan,
+        cancel: &CancelToken,
+        mut progress: impl FnMut(&Progress),
+    ) -> Result<()> {
+        let total = plan.entries.len();
+        for (i, entry) in plan.entries.iter().enumerate() {
+            if cancel.is_cancelled() {
+                return Err(Error::Cancelled(i));
+            }
+
+            let Some(target) = &entry.target else {
+                if let PlanAction::ApplyTweak { subject, new, .. } = &entry.action {
+                    progress(&Progress {
+                        step: i + 1,
+                        total,
+                        key: subject.clone(),
+                    });
+                    self.find_tweak(entry.id)?.apply(new.as_deref())?;
+                }
+                continue;
+            };
+            progress(&Progress {
+                step: i + 1,
+                total,
+                key: target.to_string(),
+            });
+
+            let r = Registry::from_def(target);
+            match &entry.action {
+                PlanAction::CreateKey => r.create_key()?,
+                PlanAction::SetValue { new, .. } => r.write_value(new)?,
+                PlanAction::DeleteValue { .. } => r.delete_value()?,
+                PlanAction::ApplyTweak { .. } | PlanAction::NoOp => (),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// 選択された候補と現在の状態を比較して計画を作成する
+    /// 前提となる項目を先に書き込むよう並べ替える。レジストリへの書き込みは行わない
+    pub fn plan(&self, selections: &Selections, state: &impl CurrentState) -> Result<Plan> {
+        let mut parsed = rules::Parsed::new();
+        let mut tweaks = Vec::new();
+        for (id, value) in selections.iter() {
+            if !self.options.iter().any(|x| x.id == *id) {
+                if let Ok(tweak) = self.find_tweak(*id) {
+                    tweaks.push((tweak, value.as_deref()));
+                    continue;
+                }
+            }
+            let opt = self.find(*id)?;
+            let value = match value {
+                Some(v) => Some(opt.parse_value(v).map_err(|e| Error::Validation(*id, e))?),
+                None => {
+                    opt.validate_absent()
+                        .map_err(|e| Error::Validation(*id, e))?;
+                    None
+                }
+            };
+            parsed.insert(*id, value);
+        }
+
+        let mut plan = Plan {
+            entries: Vec::new(),
+            warnings: rules::check(self.options, &parsed, state)?,
+        };
+        for id in rules::order(self.options, &parsed) {
+            let opt = self.find(id)?;
+            let value = parsed[&id].clone();
+            for action in plan_actions(opt, value, state)? {
+                plan.entries.push(PlanEntry {
+                    id: opt.id,
+                    label: opt.title(),
+                    target: Some(opt.def.clone()),
+                    action,
+                });
+            }
+        }
+
+        // レジストリ以外の項目は前提や競合を持たないため、レジストリの後に選択の順に並べる
+        for (tweak, value) in tweaks {
+            tweak.info().check(value)?;
+            let current = tweak.read_state();
+            if let Some(e) = &current.error {
+                plan.warnings.push(PlanWarning {
+                    id: tweak.id(),
+                    message: format!("「{}」の現在の状態を読み取れません ({e})", tweak.title()),
+                });
+            }
+            let unchanged = current.error.is_none()
+                && match value {
+                    Some(v) => current.value.is_some() && current.candidate.as_deref() == Some(v),
+                    None => current.value.is_none(),
+                };
+            let action = match unchanged {
+                true => PlanAction::NoOp,
+                false => PlanAction::ApplyTweak {
+                    subject: tweak.describe(),
+                    old: current.value,
+                    new: value.map(|x| x.to_string()),
+                },
+            };
+            plan.entries.push(PlanEntry {
+                id: tweak.id(),
+                label: tweak.title(),
+                target: None,
+                action,
+            });
+        }
+
+        Ok(plan)
+    }
+
+    /// 確認後に編集された計画でも不正な値や項目以外の場所に書き込まないよう、適用前に検証する
+    pub fn validate(&self, plan: &Plan) -> Result<()> {
+        for entry in plan.entries.iter() {
+            let Some(target) = &entry.target else {
+                if let PlanAction::ApplyTweak { new, .. } = &entry.action {
+                    self.find_tweak(entry.id)?.info().check(new.as_deref())?;
+                }
+                continue;
+            };
+            let opt = self.find(entry.id)?;
+            if !target.same_target(&opt.def) {
+                return Err(Error::TargetMismatch(entry.id, target.to_string()));
+            }
+            let ret = match &entry.action {
+                PlanAction::SetValue { new, .. } => match opt.project(Some(new.clone()))? {
+                    Some(v) => opt.validate(&v),
+                    None => Ok(()),
+                },
+                PlanAction::DeleteValue { .. } => opt.validate_absent(),
+                PlanAction::CreateKey | PlanAction::NoOp => Ok(()),
+                PlanAction::ApplyTweak { .. } => {
+                    return Err(Error::TargetMismatch(entry.id, target.to_string()))
+                }
+            };
+            ret.map_err(|e| Error::Validation(entry.id, e))?;
+        }
+
+        Ok(())
+    }
+
+    fn find(&self, id: u64) -> Result<&'a RegistryEditOption> {
+        self.options
+            .iter()
+            .find(|x| x.id == id)
+            .ok_or(Error::UnknownOption(id))
+    }
+
+    fn find_tweak(&self, id: u64) -> Result<&'a dyn Tweak> {
+        self.tweaks
+            .iter()
+            .find(|x| x.id() == id && x.registry().is_none())
+            .map(|x| x.as_ref())
+            .ok_or(Error::UnknownOption(id))
+    }
+}
+
+fn plan_actions(
+    opt: &RegistryEditOption,
+    value: Option<Value>,
+    state: &impl CurrentState,
+) -> Result<Vec<PlanAction>> {
+    let def = &opt.def;
+    let Some(new) = value else {
+        let ret = match state.value(def)? {
+            Some(old) => PlanAction::DeleteValue { old },
+            None => PlanAction::NoOp,
+        };
+        return Ok(vec![ret]);
+    };
+
+    if !state.key_exists(def)? {
+        let new = opt.merge(None, &new)?;
+        return Ok(vec![
+            PlanAction::CreateKey,
+            PlanAction::SetValue { old: None, new },
+        ]);
+    }
+
+    let old = state.value(def)?;
+    let new = opt.merge(old.as_ref(), &new)?;
+    let ret = match old {
+        Some(old) if old == new => PlanAction::NoOp,
+        old => PlanAction::SetValue { old, new },
+    };
+    Ok(vec![ret])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command::OptionCandidate;
+    use crate::tweak::{self, TweakInfo};
+    use crate::watch::ValueChanged;
+    use crate::win::reg::{DataType, View};
+    use std::sync::Mutex;
+
+    /// 候補 "0" と "1" を持つレジストリ以外の項目
+    struct FakeTweak {
+        info: TweakInfo,
+        current: Mutex<Option<String>>,
+    }
+
+    impl Tweak for FakeTweak {
+        fn info(&self) -> &TweakInfo {
+            &self.info
+        }
+
+        fn read_state(&self) -> ValueChanged {
+            let value = self.current.lock().unwrap().clone();
+            ValueChanged {
+                candidate: value.clone(),
+                value,
+                ..ValueChanged::new(self.info.id)
+            }
+        }
+
+        fn apply(&self, candidate: Option<&str>) -> tweak::Result<()> {
+            self.info.check(candidate)?;
+            *self.current.lock().unwrap() = candidate.map(|x| x.to_string());
+            Ok(())
+        }
+
+        fn describe(&self) -> String {
+            "fake".into()
+        }
+    }
+
+    fn tweaks(current: &str) -> Vec<Box<dyn Tweak>> {
+        vec![Box::new(FakeTweak {
+            info: TweakInfo::new(
+                2,
+                "",
+                "偽物",
+                vec![
+                    OptionCandidate::new("0", "オフ"),
+                    OptionCandidate::new("1", "オン"),
+                ],
+            ),
+            current: Mutex::new(Some(current.into())),
+        })]
+    }
+
+    /// レジストリを読まない状態 (レジストリ以外の項目だけの計画に使う)
+    struct NoRegistry;
+
+    impl CurrentState for NoRegistry {
+        fn key_exists(&self, _def: &RegDef) -> crate::win::reg::Result<bool> {
+            Ok(false)
+        }
+
+        fn value(&self, _def: &RegDef) -> crate::win::reg::Result<Option<Value>> {
+            Ok(None)
+        }
+    }
+
+    fn select(id: u64, value: &str) -> Selections {
+        Selections::from([(id, Some(value.to_string()))])
+    }
+
+    fn options() -> Vec<RegistryEditOption> {
+        vec![RegistryEditOption {
+            id: 1,
+            label: "テスト".into(),
+            category: String::new(),
+            tags: Vec::new(),
+            def: RegDef::hkcu(r"Software\Win11Tweaks", "Value", DataType::DWord),
+            values: vec![
+                OptionCandidate::new("0", "オフ"),
+                OptionCandidate::new("1", "オン"),
+                OptionCandidate::absent("既定"),
+            ],
+            domain: Default::default(),
+            bits: None,
+            windows_default: None,
+            requires: Vec::new(),
+            conflicts_with: Vec::new(),
+            policies: Vec::new(),
+        }]
+    }
+
+    /// 項目のキーと値だけがある状態 (`value` はキーが存在する場合だけ使う)
+    struct FakeState {
+        key: bool,
+        value: Option<Value>,
+    }
+
+    impl CurrentState for FakeState {
+        fn key_exists(&self, _def: &RegDef) -> crate::win::reg::Result<bool> {
+            Ok(self.key)
+        }
+
+        fn value(&self, _def: &RegDef) -> crate::win::reg::Result<Option<Value>> {
+            Ok(self.value.clone().filter(|_| self.key))
+        }
+    }
+
+    fn actions(selection: Option<&str>, key: bool, value: Option<u32>) -> Vec<PlanAction> {
+        let options = options();
+        let selections = Selections::from([(1, selection.map(Into::into))]);
+        let state = FakeState {
+            key,
+            value: value.map(Value::DWord),
+        };
+        let plan = Planner::new(&options).plan(&selections, &state).unwrap();
+        assert!(plan.entries.iter().all(|x| x.id == 1 && x.target.is_some()));
+        plan.entries.into_iter().map(|x| x.action).collect()
+    }
+
+    fn plan(target: RegDef) -> Plan {
+        Plan {
+            entries: vec![PlanEntry {
+                id: 1,
+                label: "テスト".into(),
+                target: Some(target),
+                action: PlanAction::SetValue {
+                    old: None,
+                    new: Value::DWord(1),
+                },
+            }],
+            warnings: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn plan_creates_missing_key() {
+        assert_eq!(
+            actions(Some("1"), false, None),
+            [
+                PlanAction::CreateKey,
+                PlanAction::SetValue {
+                    old: None,
+                    new: Value::DWord(1)
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn plan_sets_changed_values() {
+        assert_eq!(
+            actions(Some("1"), true, None),
+            [PlanAction::SetValue {
+                old: None,
+                new: Value::DWord(1)
+            }]
+        );
+        assert_eq!(
+            actions(Some("1"), true, Some(0)),
+            [PlanAction::SetValue {
+                old: Some(Value::DWord(0)),
+                new: Value::DWord(1)
+            }]
+        );
+        assert_eq!(actions(Some("1"), true, Some(1)), [PlanAction::NoOp]);
+    }
+
+    #[test]
+    fn plan_deletes_existing_values() {
+        assert_eq!(
+            actions(None, true, Some(1)),
+            [PlanAction::DeleteValue {
+                old: Value::DWord(1)
+            }]
+        );
+        assert_eq!(actions(None, true, None), [PlanAction::NoOp]);
+        assert_eq!(actions(None, false, None), [PlanAction::NoOp]);
+    }
+
+    #[test]
+    fn plan_rejects_unknown_options_and_values() {
+        let options = options();
+        let planner = Planner::new(&options);
+        let state = FakeState {
+            key: true,
+            value: None,
+        };
+        assert!(matches!(
+            planner.plan(&select(9, "1"), &state),
+            Err(Error::UnknownOption(9))
+        ));
+        assert!(matches!(
+            planner.plan(&select(1, "x"), &state),
+            Err(Error::Validation(1, _))
+        ));
+        assert!(!planner
+            .plan(
+                &select(1, "1"),
+                &FakeState {
+                    key: true,
+                    value: Some(Value::DWord(1))
+                }
+            )
+            .unwrap()
+            .has_changes());
+    }
+
+    #[test]
+    fn validate_accepts_option_target() {
+        let options = options();
+        let target = RegDef::hkcu(r"SOFTWARE\win11tweaks", "Value", DataType::DWord);
+        assert!(Planner::new(&options).validate(&plan(target)).is_ok());
+    }
+
+    #[test]
+    fn validate_rejects_edited_target() {
+        let options = options();
+        let edited = [
+            RegDef::hklm(r"Software\Win11Tweaks", "Value", DataType::DWord),
+            RegDef::hkcu(r"Software\Other", "Value", DataType::DWord),
+            RegDef::hkcu(r"Software\Win11Tweaks", "Other", DataType::DWord),
+            RegDef::hkcu(r"Software\Win11Tweaks", "Value", DataType::QWord),
+            RegDef::hkcu(r"Software\Win11Tweaks", "Value", DataType::DWord).in_view(View::Bit32),
+        ];
+        for target in edited {
+            assert!(matches!(
+                Planner::new(&options).validate(&plan(target)),
+                Err(Error::TargetMismatch(1, _))
+            ));
+        }
+    }
+
+    #[test]
+    fn plan_includes_tweaks() {
+        let options = options();
+        let tweaks = tweaks("0");
+        let planner = Planner::new(&options).with_tweaks(&tweaks);
+
+        let plan = planner.plan(&select(2, "1"), &NoRegistry).unwrap();
+        assert_eq!(plan.entries.len(), 1);
+        assert!(plan.entries[0].target.is_none());
+        assert_eq!(
+            plan.entries[0].action,
+            PlanAction::ApplyTweak {
+                subject: "fake".into(),
+                old: Some("0".into()),
+                new: Some("1".into()),
+            }
+        );
+
+        let plan = planner.plan(&select(2, "0"), &NoRegistry).unwrap();
+        assert_eq!(plan.entries[0].action, PlanAction::NoOp);
+
+        assert!(matches!(
+            planner.plan(&select(2, "9"), &NoRegistry),
+            Err(Error::Tweak(_))
+        ));
+    }
+
+    #[test]
+    fn apply_runs_tweaks() {
+        let options = options();
+        let tweaks = tweaks("0");
+        let planner = Planner::new(&options).with_tweaks(&tweaks);
+
+        let plan = planner.plan(&select(2, "1"), &NoRegistry).unwrap();
+        planner.validate(&plan).unwrap();
+        planner.apply(&plan).unwrap();
+        assert_eq!(tweaks[0].read_state().value.as_deref(), Some("1"));
+    }
+
+    #[test]
+    fn validate_rejects_edited_tweak_entries() {
+        let options = options();
+        let tweaks = tweaks("0");
+        let planner = Planner::new(&options).with_tweaks(&tweaks);
+        let entry = |new: &str, target: Option<RegDef>| Plan {
+            entries: vec![PlanEntry {
+                id: 2,
+                label: "偽物".into(),
+                target,
+                action: PlanAction::ApplyTweak {
+                    subject: "fake".into(),
+                    old: None,
+                    new: Some(new.into()),
+                },
+            }],
+            warnings: Vec::new(),
+        };
+
+        assert!(planner.validate(&entry("1", None)).is_ok());
+        assert!(matches!(
+            planner.validate(&entry("9", None)),
+            Err(Error::Tweak(_))
+        ));
+        // レジストリの項目の ID でレジストリ以外の適用はできない
+        let mut plan = entry("1", None);
+        plan.entries[0].id = 1;
+        assert!(matches!(
+            planner.validate(&plan),
+            Err(Error::UnknownOption(1))
+        ));
+    }
+}
@@ -0,0 +1,105 @@
+use super::{Error, Result, Selections};
+use crate::tweak::Tweak;
+use serde::{Deserialize, Serialize};
+
+/// 初期状態に戻す範囲
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "value", rename_all = "kebab-case")]
+pub enum ResetScope {
+    Option(u64),
+    Category(String),
+    All,
+}
+
+/// 範囲内の項目を Windows の初期状態に戻す選択を作成する
+/// 範囲がまとめて指定された場合、初期状態が分からない項目と個別に戻す項目は対象から外す
+pub fn default_selections(tweaks: &[Box<dyn Tweak>], scope: &ResetScope) -> Result<Selections> {
+    let mut ret = Selections::new();
+    match scope {
+        ResetScope::Option(id) => {
+            let tweak = tweaks
+                .iter()
+                .find(|x| x.id() == *id)
+                .ok_or(Error::UnknownOption(*id))?;
+            let value = tweak
+                .default_selection()
+                .ok_or(Error::NoWindowsDefault(*id))?;
+            ret.insert(tweak.id(), value);
+        }
+        ResetScope::Category(category) => {
+            for tweak in tweaks.iter().filter(|x| x.category() == category) {
+                if let Some(value) = bulk_default(tweak.as_ref()) {
+                    ret.insert(tweak.id(), value);
+                }
+            }
+        }
+        ResetScope::All => {
+            for tweak in tweaks.iter() {
+                if let Some(value) = bulk_default(tweak.as_ref()) {
+                    ret.insert(tweak.id(), value);
+                }
+            }
+        }
+    }
+
+    Ok(ret)
+}
+
+/// まとめて戻す場合の初期状態
+fn bulk_default(tweak: &dyn Tweak) -> Option<Option<String>> {
+    match tweak.info().reset_individually {
+        true => None,
+        false => tweak.default_selection(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::tweak;
+
+    fn find(tweaks: &[Box<dyn Tweak>], label: &str) -> u64 {
+        tweaks.iter().find(|x| x.label() == label).unwrap().id()
+    }
+
+    #[test]
+    fn all_skips_options_without_default() {
+        let tweaks = tweak::with_registry(crate::default_edit_options());
+        let selections = default_selections(&tweaks, &ResetScope::All).unwrap();
+
+        let menu = find(&tweaks, "右クリックメニュー");
+        assert!(!selections.contains_key(&menu));
+        assert!(matches!(
+            default_selections(&tweaks, &ResetScope::Option(menu)),
+            Err(Error::NoWindowsDefault(_))
+        ));
+        // Windows 8 以降は削除の確認を表示しない
+        let confirm = find(&tweaks, "削除の確認");
+        assert_eq!(selections[&confirm], Some("1".into()));
+        // レジストリ以外の項目も初期状態が分かっていれば戻す
+        let theme = find(&tweaks, "テーマ");
+        assert_eq!(selections[&theme], None);
+    }
+
+    #[test]
+    fn bulk_reset_skips_individual_tweaks() {
+        let tweaks = tweak::with_registry(crate::default_edit_options());
+        let all = default_selections(&tweaks, &ResetScope::All).unwrap();
+        for label in [
+            r"PATH に %USERPROFILE%\bin を含める",
+            "SysMain (要管理者権限)",
+        ] {
+            let id = find(&tweaks, label);
+            let tweak = tweak::find(&tweaks, id).unwrap();
+            assert!(!all.contains_key(&id), "{label}");
+            let category =
+                default_selections(&tweaks, &ResetScope::Category(tweak.category().to_string()))
+                    .unwrap();
+            assert!(!category.contains_key(&id), "{label}");
+            // 項目を指定すれば戻せる
+            let single = default_selections(&tweaks, &ResetScope::Option(id)).unwrap();
+            assert_eq!(single[&id], tweak.default_selection().unwrap());
+        }
+    }
+}
@@ -0,0 +1,249 @@
+use super::{CurrentState, Error, PlanWarning, Result};
+use crate::command::{Condition, RegistryEditOption};
+use crate::policy;
+use crate::win::reg::Value;
+use std::collections::{BTreeMap, HashSet};
+
+/// 検証済みの選択 (`None` は値の削除)
+pub(super) type Parsed = BTreeMap<u64, Option<Value>>;
+
+/// 選択を適用した後の状態で `requires` と `conflicts_with` を評価する
+/// 前提を満たさない項目は警告にとどめ、衝突する組み合わせはエラーにする
+pub(super) fn check(
+    options: &[RegistryEditOption],
+    parsed: &Parsed,
+    state: &impl CurrentState,
+) -> Result<Vec<PlanWarning>> {
+    let mut warnings = Vec::new();
+    for (id, value) in parsed.iter() {
+        let opt = find(options, *id)?;
+
+        if state.covers(&opt.def) {
+            let resolution = policy::resolve(opt, state)?;
+            if resolution.masked() {
+                warnings.push(PlanWarning {
+                    id: opt.id,
+                    message: format!(
+                        "「{}」は{}で上書きされているため、変更しても反映されません",
+                        opt.title(),
+                        resolution.layer.label()
+                    ),
+                });
+            }
+        }
+
+        for rel in opt
+            .requires
+            .iter()
+            .filter(|x| opt.applies(x, value.as_ref()))
+        {
+            let target = find(options, rel.target.id)?;
+            if evaluate(target, &rel.target, parsed, state)? == Some(false) {
+                warnings.push(PlanWarning {
+                    id: opt.id,
+                    message: format!(
+                        "「{}」の設定は「{}」の状態が前提を満たしていないため効果がありません",
+                        opt.title(),
+                        target.title()
+                    ),
+                });
+            }
+        }
+
+        for rel in opt
+            .conflicts_with
+            .iter()
+            .filter(|x| opt.applies(x, value.as_ref()))
+        {
+            let target = find(options, rel.target.id)?;
+            if evaluate(target, &rel.target, parsed, state)? == Some(true) {
+                return Err(Error::Conflict(opt.id, target.id));
+            }
+        }
+    }
+
+    Ok(warnings)
+}
+
+/// `requires` で参照されている項目が先になるよう並べ替える
+pub(super) fn order(options: &[RegistryEditOption], parsed: &Parsed) -> Vec<u64> {
+    fn visit(
+        id: u64,
+        options: &[RegistryEditOption],
+        parsed: &Parsed,
+        visited: &mut HashSet<u64>,
+        ret: &mut Vec<u64>,
+    ) {
+        if !parsed.contains_key(&id) || !visited.insert(id) {
+            return;
+        }
+        if let Some(opt) = options.iter().find(|x| x.id == id) {
+            for rel in opt.requires.iter() {
+                visit(rel.target.id, options, parsed, visited, ret);
+            }
+        }
+        ret.push(id);
+    }
+
+    let mut visited = HashSet::new();
+    let mut ret = Vec::with_capacity(parsed.len());
+    for id in parsed.keys() {
+        visit(*id, options, parsed, &mut visited, &mut ret);
+    }
+    ret
+}
+
+/// 条件が成り立つか (状態の範囲外で判断できない場合は `None`)
+fn evaluate(
+    target: &RegistryEditOption,
+    cond: &Condition,
+    parsed: &Parsed,
+    state: &impl CurrentState,
+) -> Result<Option<bool>> {
+    let value = match parsed.get(&target.id) {
+        Some(v) => v.clone(),
+        None if !state.covers(&target.def) => return Ok(None),
+        None => policy::resolve(target, state)?.effective,
+    };
+    Ok(Some(target.satisfies(cond, value.as_ref())))
+}
+
+fn find(options: &[RegistryEditOption], id: u64) -> Result<&RegistryEditOption> {
+    options
+        .iter()
+        .find(|x| x.id == id)
+        .ok_or(Error::UnknownOption(id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command::{OptionCandidate, Relation};
+    use crate::regfile::{RegFile, RegFileState};
+    use crate::win::reg::{DataType, RegDef};
+
+    /// ID 1 は有効、ID 7 は無効になっている状態 (それ以外の値は存在しない)
+    const EXPORT: &str = r#"Windows Registry Editor Version 5.00
+
+[HKEY_CURRENT_USER\Software\Win11Tweaks]
+"Value1"=dword:00000001
+"Value7"=dword:00000000
+"#;
+
+    /// `id` の値が `value` であること
+    fn rel(id: u64, value: &str) -> Relation {
+        Relation {
+            when: vec![Some("1".into())],
+            target: Condition::new(id, &[Some(value)]),
+        }
+    }
+
+    fn option(
+        id: u64,
+        requires: Vec<Relation>,
+        conflicts_with: Vec<Relation>,
+    ) -> RegistryEditOption {
+        RegistryEditOption {
+            id,
+            label: format!("項目{id}"),
+            category: String::new(),
+            tags: Vec::new(),
+            def: RegDef::hkcu(
+                r"Software\Win11Tweaks",
+                format!("Value{id}"),
+                DataType::DWord,
+            ),
+            values: vec![
+                OptionCandidate::new("0", "オフ"),
+                OptionCandidate::new("1", "オン"),
+            ],
+            domain: Default::default(),
+            bits: None,
+            windows_default: None,
+            requires,
+            conflicts_with,
+            policies: Vec::new(),
+        }
+    }
+
+    /// 3 → 2 → 1 の前提の連鎖、4 と 5 の循環、6 と 7 の衝突
+    fn options() -> Vec<RegistryEditOption> {
+        vec![
+            option(1, Vec::new(), Vec::new()),
+            option(2, vec![rel(1, "1")], Vec::new()),
+            option(3, vec![rel(2, "1")], Vec::new()),
+            option(4, vec![rel(5, "1")], Vec::new()),
+            option(5, vec![rel(4, "1")], Vec::new()),
+            option(6, Vec::new(), vec![rel(7, "1")]),
+            option(7, Vec::new(), Vec::new()),
+        ]
+    }
+
+    fn parsed(values: &[(u64, u32)]) -> Parsed {
+        values
+            .iter()
+            .map(|(id, v)| (*id, Some(Value::DWord(*v))))
+            .collect()
+    }
+
+    fn state() -> RegFileState {
+        RegFileState::new(RegFile::parse(EXPORT).unwrap())
+    }
+
+    fn warned(values: &[(u64, u32)]) -> Vec<u64> {
+        check(&options(), &parsed(values), &state())
+            .unwrap()
+            .iter()
+            .map(|x| x.id)
+            .collect()
+    }
+
+    #[test]
+    fn orders_requires_chain() {
+        let options = options();
+        assert_eq!(
+            order(&options, &parsed(&[(3, 1), (2, 1), (1, 1)])),
+            [1, 2, 3]
+        );
+        // 選択されていない項目は飛ばす
+        assert_eq!(order(&options, &parsed(&[(3, 1), (1, 1)])), [1, 3]);
+    }
+
+    #[test]
+    fn orders_cycle_once() {
+        assert_eq!(order(&options(), &parsed(&[(5, 1), (4, 1)])), [5, 4]);
+        assert!(warned(&[(4, 1), (5, 1)]).is_empty());
+        assert_eq!(warned(&[(4, 1), (5, 0)]), [4]);
+    }
+
+    #[test]
+    fn warns_when_requirement_is_not_met() {
+        // 選択した値と現在の状態のどちらでも判定する
+        assert!(warned(&[(3, 1), (2, 1)]).is_empty());
+        assert_eq!(warned(&[(3, 1), (2, 0)]), [3]);
+        assert!(warned(&[(2, 1)]).is_empty());
+        assert_eq!(warned(&[(2, 1), (1, 0)]), [2]);
+        // 値が存在しない場合は前提を満たさない
+        assert_eq!(warned(&[(3, 1)]), [3]);
+        // `when` に一致しない値は前提を求めない
+        assert!(warned(&[(3, 0)]).is_empty());
+    }
+
+    #[test]
+    fn rejects_conflicts() {
+        assert!(matches!(
+            check(&options(), &parsed(&[(6, 1), (7, 1)]), &state()),
+            Err(Error::Conflict(6, 7))
+        ));
+        assert!(warned(&[(6, 1)]).is_empty());
+        assert!(warned(&[(6, 0), (7, 1)]).is_empty());
+        let options = vec![
+            option(6, Vec::new(), vec![rel(8, "1")]),
+            option(7, Vec::new(), Vec::new()),
+        ];
+        assert!(matches!(
+            check(&options, &parsed(&[(6, 1)]), &state()),
+            Err(Error::UnknownOption(8))
+        ));
+    }
+}
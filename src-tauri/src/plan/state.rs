@@ -0,0 +1,50 @@
+use crate::win::reg::{RegDef, Registry, Result, Value};
+
+/// 計画を立てる際に参照する現在の状態
+pub trait CurrentState {
+    /// 対象がこの状態の範囲に含まれているか (エクスポートファイルなどは一部のキーしか持たない)
+    fn covers(&self, _def: &RegDef) -> bool {
+        true
+    }
+    /// レジストリ以外の設定項目 (サービスやファイルなど) の現在の状態も表しているか
+    fn covers_tweaks(&self) -> bool {
+        false
+    }
+    fn key_exists(&self, def: &RegDef) -> Result<bool>;
+    fn value(&self, def: &RegDef) -> Result<Option<Value>>;
+}
+
+/// 実際のレジストリを読み取る (書き込みは一切行わない)
+pub struct LiveRegistry;
+
+impl CurrentState for LiveRegistry {
+    fn covers_tweaks(&self) -> bool {
+        true
+    }
+
+    fn key_exists(&self, def: &RegDef) -> Result<bool> {
+        Registry::from_def(def).key_exists()
+    }
+
+    fn value(&self, def: &RegDef) -> Result<Option<Value>> {
+        Registry::from_def(def).find_value(def.data_type)
+    }
+}
+
+/// レジストリ以外の設定項目の状態を表さないようにする
+/// (別のユーザーのキーを読む場合など、レジストリ以外は現在のユーザーの状態になってしまう場合に使う)
+pub struct RegistryOnly<S>(pub S);
+
+impl<S: CurrentState> CurrentState for RegistryOnly<S> {
+    fn covers(&self, def: &RegDef) -> bool {
+        self.0.covers(def)
+    }
+
+    fn key_exists(&self, def: &RegDef) -> Result<bool> {
+        self.0.key_exists(def)
+    }
+
+    fn value(&self, def: &RegDef) -> Result<Option<Value>> {
+        self.0.value(def)
+    }
+}
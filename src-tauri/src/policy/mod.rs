@@ -0,0 +1,66 @@
+mod resolver;
+
+pub use resolver::{resolve, Resolution};
+
+use crate::win::reg::RegDef;
+use serde::{Deserialize, Serialize};
+
+/// 値が決まる層 (上にあるものほど優先される)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Layer {
+    /// HKLM\SOFTWARE\Policies
+    MachinePolicy,
+    /// HKCU\Software\Policies
+    UserPolicy,
+    /// 項目そのものの値
+    UserPreference,
+}
+
+impl Layer {
+    /// 画面に表示する名前
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::MachinePolicy => "コンピューターのポリシー",
+            Self::UserPolicy => "ユーザーのポリシー",
+            Self::UserPreference => "ユーザーの設定",
+        }
+    }
+}
+
+impl std::fmt::Display for Layer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Self::MachinePolicy => "machine policy",
+            Self::UserPolicy => "user policy",
+            Self::UserPreference => "user preference",
+        };
+
+        name.fmt(f)
+    }
+}
+
+/// 項目の値を上書きするポリシーの値
+#[derive(Debug, Clone)]
+pub struct Policy {
+    pub layer: Layer,
+    pub def: RegDef,
+    /// ポリシーの値と、それによって決まる項目の値の対応 (空の場合は同じ値)
+    /// 対応に含まれない値のポリシーは項目を上書きしない
+    pub values: Vec<(String, String)>,
+}
+
+impl Policy {
+    pub fn new(layer: Layer, def: RegDef) -> Self {
+        Self {
+            layer,
+            def,
+            values: Vec::new(),
+        }
+    }
+
+    pub fn map(mut self, policy_value: impl Into<String>, value: impl Into<String>) -> Self {
+        self.values.push((policy_value.into(), value.into()));
+        self
+    }
+}
@@ -0,0 +1,171 @@
+use super::{Layer, Policy};
+use crate::command::RegistryEditOption;
+use crate::plan::CurrentState;
+use crate::win::reg::{Result, Value};
+use serde::Serialize;
+
+/// 実際に有効な値と、それを決めている層
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Resolution {
+    pub effective: Option<Value>,
+    pub layer: Layer,
+    /// 項目そのものの値
+    pub preference: Option<Value>,
+}
+
+impl Resolution {
+    /// 項目の値を変更してもポリシーによって上書きされるか
+    pub fn masked(&self) -> bool {
+        self.layer != Layer::UserPreference
+    }
+}
+
+/// ポリシーを考慮して項目の有効な値を求める
+pub fn resolve(opt: &RegistryEditOption, state: &impl CurrentState) -> Result<Resolution> {
+    let preference = opt.project(state.value(&opt.def)?)?;
+
+    let mut policies = opt.policies.iter().collect::<Vec<_>>();
+    policies.sort_by_key(|x| x.layer);
+    for policy in policies {
+        if !state.covers(&policy.def) {
+            continue;
+        }
+        if let Some(effective) = apply(opt, policy, state.value(&policy.def)?) {
+            return Ok(Resolution {
+                effective: Some(effective),
+                layer: policy.layer,
+                preference,
+            });
+        }
+    }
+
+    Ok(Resolution {
+        effective: preference.clone(),
+        layer: Layer::UserPreference,
+        preference,
+    })
+}
+
+/// ポリシーの値から項目の値を求める (ポリシーが項目を上書きしない場合は `None`)
+fn apply(opt: &RegistryEditOption, policy: &Policy, value: Option<Value>) -> Option<Value> {
+    let value = value?;
+    if policy.values.is_empty() {
+        return (value.data_type() == opt.value_type()).then_some(value);
+    }
+
+    let text = value.to_string();
+    policy
+        .values
+        .iter()
+        .find(|(x, _)| *x == text)
+        .and_then(|(_, x)| opt.domain.parse(opt.value_type(), x).ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command::OptionCandidate;
+    use crate::regfile::{RegFile, RegFileState};
+    use crate::win::reg::{DataType, RegDef};
+
+    const MACHINE: &str = r"HKEY_LOCAL_MACHINE\SOFTWARE\Policies\Win11Tweaks";
+    const USER: &str = r"HKEY_CURRENT_USER\Software\Policies\Win11Tweaks";
+    const PREFERENCE: &str = r"HKEY_CURRENT_USER\Software\Win11Tweaks";
+
+    fn option(policies: Vec<Policy>) -> RegistryEditOption {
+        RegistryEditOption {
+            id: 1,
+            label: "テスト".into(),
+            category: String::new(),
+            tags: Vec::new(),
+            def: RegDef::hkcu(r"Software\Win11Tweaks", "Value", DataType::DWord),
+            values: vec![
+                OptionCandidate::new("0", "オフ"),
+                OptionCandidate::new("1", "オン"),
+            ],
+            domain: Default::default(),
+            bits: None,
+            windows_default: None,
+            requires: Vec::new(),
+            conflicts_with: Vec::new(),
+            policies,
+        }
+    }
+
+    /// 層の順と逆に登録し、並べ替えられることを確かめる
+    fn policies() -> Vec<Policy> {
+        vec![
+            Policy::new(
+                Layer::UserPolicy,
+                RegDef::hkcu(r"Software\Policies\Win11Tweaks", "Value", DataType::DWord),
+            ),
+            Policy::new(
+                Layer::MachinePolicy,
+                RegDef::hklm(r"SOFTWARE\Policies\Win11Tweaks", "Value", DataType::DWord),
+            ),
+        ]
+    }
+
+    /// 各層の値 (`None` は値が存在しない) からエクスポートされたファイルを作る
+    fn state(machine: Option<u32>, user: Option<u32>, preference: Option<u32>) -> RegFileState {
+        let mut text = String::from("Windows Registry Editor Version 5.00\n");
+        for (key, value) in [(MACHINE, machine), (USER, user), (PREFERENCE, preference)] {
+            text.push_str(&format!("\n[{key}]\n"));
+            if let Some(v) = value {
+                text.push_str(&format!("\"Value\"=dword:{v:08x}\n"));
+            }
+        }
+        RegFileState::new(RegFile::parse(&text).unwrap())
+    }
+
+    #[test]
+    fn machine_policy_wins_over_user_policy_and_preference() {
+        let opt = option(policies());
+        // (コンピューター, ユーザー, 項目) => (有効な値, 層)
+        let table = [
+            ((None, None, None), (None, Layer::UserPreference)),
+            ((None, None, Some(1)), (Some(1), Layer::UserPreference)),
+            ((None, Some(0), None), (Some(0), Layer::UserPolicy)),
+            ((None, Some(0), Some(1)), (Some(0), Layer::UserPolicy)),
+            ((Some(1), None, None), (Some(1), Layer::MachinePolicy)),
+            ((Some(1), None, Some(0)), (Some(1), Layer::MachinePolicy)),
+            ((Some(1), Some(0), None), (Some(1), Layer::MachinePolicy)),
+            ((Some(0), Some(1), Some(1)), (Some(0), Layer::MachinePolicy)),
+        ];
+        for ((machine, user, preference), (effective, layer)) in table {
+            let r = resolve(&opt, &state(machine, user, preference)).unwrap();
+            let case = (machine, user, preference);
+            assert_eq!(r.effective, effective.map(Value::DWord), "{case:?}");
+            assert_eq!(r.layer, layer, "{case:?}");
+            assert_eq!(r.preference, preference.map(Value::DWord), "{case:?}");
+            assert_eq!(r.masked(), layer != Layer::UserPreference, "{case:?}");
+        }
+    }
+
+    #[test]
+    fn uncovered_policies_are_skipped() {
+        let text = format!(
+            "Windows Registry Editor Version 5.00\n\n[{PREFERENCE}]\n\"Value\"=dword:00000001\n"
+        );
+        let state = RegFileState::new(RegFile::parse(&text).unwrap());
+        let r = resolve(&option(policies()), &state).unwrap();
+        assert_eq!(r.effective, Some(Value::DWord(1)));
+        assert_eq!(r.layer, Layer::UserPreference);
+    }
+
+    #[test]
+    fn mapped_policy_values() {
+        // ポリシーの 1 は項目の 0 にあたる。対応に無い値は上書きしない
+        let mut policies = policies();
+        policies[1] = policies[1].clone().map("1", "0");
+        let opt = option(policies);
+
+        let r = resolve(&opt, &state(Some(1), None, Some(1))).unwrap();
+        assert_eq!(r.effective, Some(Value::DWord(0)));
+        assert_eq!(r.layer, Layer::MachinePolicy);
+
+        let r = resolve(&opt, &state(Some(2), Some(1), Some(0))).unwrap();
+        assert_eq!(r.effective, Some(Value::DWord(1)));
+        assert_eq!(r.layer, Layer::UserPolicy);
+    }
+}
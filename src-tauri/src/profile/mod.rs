@@ -0,0 +1,27 @@
+mod error;
+
+pub use error::{Error, Result};
+
+use crate::plan::Selections;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// 保存された設定値の組 (設定項目の ID と候補値、`null` は値の削除)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Profile {
+    #[serde(default)]
+    pub name: String,
+    pub values: Selections,
+}
+
+impl Profile {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let text = std::fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&text)?)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
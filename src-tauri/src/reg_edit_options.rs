@@ -1,8 +1,11 @@
-use crate::command::{OptionCandidate, RegistryEditOption};
+use crate::command::{Condition, OptionCandidate, RegistryEditOption, WindowsDefault};
+use crate::policy::{Layer, Policy};
 use crate::win;
 
 struct Builder {
     id_counter: u64,
+    category: String,
+    category_tags: Vec<String>,
     buffer: Vec<RegistryEditOption>,
 }
 
@@ -10,15 +13,24 @@ impl Builder {
     pub fn with_capacity(cap: usize) -> Self {
         Self {
             id_counter: 0,
+            category: String::new(),
+            category_tags: Vec::new(),
             buffer: Vec::with_capacity(cap),
         }
     }
+
+    /// 以降に追加する項目の分類と、分類の別名
+    pub fn category(&mut self, name: impl Into<String>, tags: &[&str]) {
+        self.category = name.into();
+        self.category_tags = tags.iter().map(|x| x.to_string()).collect();
+    }
+
     pub fn push(
         &mut self,
         label: impl Into<String>,
         def: win::reg::RegDef,
         values: Vec<OptionCandidate>,
-    ) {
+    ) -> &mut RegistryEditOption {
         if self.id_counter < u64::MAX {
             self.id_counter += 1;
         } else {
@@ -28,9 +40,18 @@ impl Builder {
         self.buffer.push(RegistryEditOption {
             id: self.id_counter,
             label: label.into(),
+            category: self.category.clone(),
+            tags: self.category_tags.clone(),
             def,
             values,
+            domain: Default::default(),
+            bits: None,
+            windows_default: None,
+            requires: Vec::new(),
+            conflicts_with: Vec::new(),
+            policies: Vec::new(),
         });
+        self.buffer.last_mut().unwrap()
     }
 
     pub fn into_vec(self) -> Vec<RegistryEditOption> {
@@ -38,22 +59,20 @@ impl Builder {
     }
 }
 
-fn get_sid() -> String {
-    let user = win::get_username().expect("Failed to get username");
-    win::ps::get_sid(&user).expect("Failed to get SID")
-}
-
 pub fn default_edit_options() -> Vec<RegistryEditOption> {
     use win::reg::DataType;
     use win::reg::RegDef as R;
+    use win::reg::View;
     use OptionCandidate as O;
-
-    let sid = get_sid();
+    use WindowsDefault as D;
 
     let mut b = Builder::with_capacity(8);
+    b.category("エクスプローラ", &["explorer", "file explorer"]);
+    // 初期状態はキー自体が存在しないため、値の削除だけでは戻せない
+    // そのため初期状態は登録せず、初期状態に戻す対象から外す
     b.push(
-        "エクスプローラ - 右クリックメニュー",
-        R::hkcu(
+        "右クリックメニュー",
+        R::user(
             r"Software\Classes\CLSID\{86ca1aa0-34aa-4e8b-a509-50c905bae2a2}\InprocServer32",
             "",
             DataType::String,
@@ -62,27 +81,24 @@ pub fn default_edit_options() -> Vec<RegistryEditOption> {
             O::new("", "従来のメニュー"),
             O::new("", "TODO: Windows11 のメニュー"),
         ],
-    );
+    )
+    .set_tags(&["context menu", "right click", "migikurikku", "menu"]);
     b.push(
-        "エクスプローラ - 起動画面",
-        R::hku(
-            format!(
-                "{}\\Software\\Microsoft\\Windows\\CurrentVersion\\Explorer\\Advanced",
-                sid.as_str()
-            ),
+        "起動画面",
+        R::user(
+            r"Software\Microsoft\Windows\CurrentVersion\Explorer\Advanced",
             "LaunchTo",
             DataType::DWord,
         ),
         vec![O::new("1", "PC"), O::new("2", "ホーム")],
-    );
+    )
+    .set_windows_default(D::Absent)
+    .set_tags(&["launch to", "this pc", "home", "kidou"]);
 
     b.push(
-        "エクスプローラ - ファイル拡張子",
-        R::hku(
-            format!(
-                "{}\\Software\\Microsoft\\Windows\\CurrentVersion\\Explorer\\Advanced",
-                sid.as_str()
-            ),
+        "ファイル拡張子",
+        R::user(
+            r"Software\Microsoft\Windows\CurrentVersion\Explorer\Advanced",
             "HideFileExt",
             DataType::DWord,
         ),
@@ -90,76 +106,183 @@ pub fn default_edit_options() -> Vec<RegistryEditOption> {
             O::new("0", "登録された拡張子を表示する"),
             O::new("1", "登録された拡張子を表示しない"),
         ],
-    );
+    )
+    .set_windows_default(D::Value("1".into()))
+    .set_tags(&["file extension", "kakuchoushi"]);
 
+    b.category("タスクバー", &["taskbar"]);
     b.push(
-        "タスクバー - スタートメニュー位置",
-        R::hkcu(
+        "スタートメニュー位置",
+        R::user(
             r"SOFTWARE\Microsoft\Windows\CurrentVersion\Explorer\Advanced",
             "TaskbarAl",
             DataType::DWord,
         ),
         vec![O::new("0", "左揃え"), O::new("1", "中央揃え")],
-    );
+    )
+    .set_windows_default(D::Absent)
+    .set_tags(&["start menu", "alignment", "center", "left"]);
 
-    b.push(
-        "タスクバー - 検索ボックス",
-        R::hkcu(
-            r"SOFTWARE\Microsoft\Windows\CurrentVersion\Search",
-            "SearchBoxTaskbarMode",
-            DataType::DWord,
-        ),
-        vec![
-            O::new("0", "非表示"),
-            O::new("1", "検索アイコンのみ"),
-            O::new("2", "検索ボックス"),
-            O::new("3", "検索アイコンとラベル"),
-        ],
-    );
+    // ポリシーは 32 ビット版のビルドからも 64 ビットのビューを参照する
+    let search_box = b
+        .push(
+            "検索ボックス",
+            R::user(
+                r"SOFTWARE\Microsoft\Windows\CurrentVersion\Search",
+                "SearchBoxTaskbarMode",
+                DataType::DWord,
+            ),
+            vec![
+                O::new("0", "非表示"),
+                O::new("1", "検索アイコンのみ"),
+                O::new("2", "検索ボックス"),
+                O::new("3", "検索アイコンとラベル"),
+            ],
+        )
+        .set_windows_default(D::Value("2".into()))
+        .set_tags(&["search", "kensaku"])
+        .add_policy(Policy::new(
+            Layer::MachinePolicy,
+            R::hklm(
+                r"SOFTWARE\Policies\Microsoft\Windows\Windows Search",
+                "SearchOnTaskbarMode",
+                DataType::DWord,
+            )
+            .in_view(View::Bit64),
+        ))
+        .id;
 
     b.push(
-        "タスクバー - タスクビュー",
-        R::hkcu(
+        "タスクビュー",
+        R::user(
             r"SOFTWARE\Microsoft\Windows\CurrentVersion\Explorer\Advanced",
             "ShowTaskViewButton",
             DataType::DWord,
         ),
         vec![O::new("0", "非表示"), O::new("1", "表示")],
-    );
+    )
+    .set_windows_default(D::Absent)
+    .set_tags(&["task view"]);
     b.push(
-        "タスクバー - チャット",
-        R::hku(
-            format!(
-                "{}\\Software\\Microsoft\\Windows\\CurrentVersion\\Explorer\\Advanced",
-                sid.as_str()
-            ),
+        "チャット",
+        R::user(
+            r"Software\Microsoft\Windows\CurrentVersion\Explorer\Advanced",
             "TaskbarMn",
             DataType::DWord,
         ),
         vec![O::new("0", "非表示"), O::new("1", "表示")],
+    )
+    .set_windows_default(D::Absent)
+    .set_tags(&["chat", "teams"])
+    .add_policy(
+        Policy::new(
+            Layer::MachinePolicy,
+            R::hklm(
+                r"SOFTWARE\Policies\Microsoft\Windows\Windows Chat",
+                "ChatIcon",
+                DataType::DWord,
+            )
+            .in_view(View::Bit64),
+        )
+        .map("1", "1")
+        .map("2", "0")
+        .map("3", "0"),
     );
 
     b.push(
-        "タスクバー - ウィジェット非表示 (要管理者権限)",
-        R::hkcu(
+        "ウィジェット非表示 (要管理者権限)",
+        R::user(
             r"SOFTWARE\Microsoft\Windows\CurrentVersion\Explorer\Advanced",
             "TaskbarDa",
             DataType::DWord,
         ),
         vec![O::new("0", "非表示"), O::new("1", "表示")],
+    )
+    .set_windows_default(D::Absent)
+    .set_tags(&["widgets", "news"])
+    .add_policy(
+        Policy::new(
+            Layer::MachinePolicy,
+            R::hklm(
+                r"SOFTWARE\Policies\Microsoft\Dsh",
+                "AllowNewsAndInterests",
+                DataType::DWord,
+            )
+            .in_view(View::Bit64),
+        )
+        .map("0", "0"),
     );
 
     b.push(
-        "タスクバー - Windows の Copilot (プレビュー)",
-        R::hku(
-            format!(
-                "{}\\Software\\Microsoft\\Windows\\CurrentVersion\\Explorer\\Advanced",
-                sid.as_str()
-            ),
+        "Windows の Copilot (プレビュー)",
+        R::user(
+            r"Software\Microsoft\Windows\CurrentVersion\Explorer\Advanced",
             "ShowCopilotButton",
             DataType::DWord,
         ),
         vec![O::new("0", "非表示"), O::new("1", "表示")],
+    )
+    .set_windows_default(D::Absent)
+    .set_tags(&["copilot", "ai"])
+    .add_policy(
+        Policy::new(
+            Layer::MachinePolicy,
+            R::hklm(
+                r"SOFTWARE\Policies\Microsoft\Windows\WindowsCopilot",
+                "TurnOffWindowsCopilot",
+                DataType::DWord,
+            )
+            .in_view(View::Bit64),
+        )
+        .map("1", "0"),
+    )
+    .add_policy(
+        Policy::new(
+            Layer::UserPolicy,
+            R::user(
+                r"Software\Policies\Microsoft\Windows\WindowsCopilot",
+                "TurnOffWindowsCopilot",
+                DataType::DWord,
+            )
+            .in_view(View::Bit64),
+        )
+        .map("1", "0"),
+    );
+
+    b.category("エクスプローラ", &["explorer", "file explorer"]);
+    // SHELLSTATE の 5 バイト目に fNoConfirmRecycle が含まれる (Windows 8 以降は確認しないのが初期状態)
+    b.push(
+        "削除の確認",
+        R::user(
+            r"Software\Microsoft\Windows\CurrentVersion\Explorer",
+            "ShellState",
+            DataType::Binary,
+        ),
+        vec![O::new("0", "確認する"), O::new("1", "確認しない")],
+    )
+    .set_bit_field(win::reg::BitField::Byte {
+        offset: 4,
+        mask: 0x04,
+    })
+    .set_windows_default(D::Value("1".into()))
+    .set_tags(&["delete confirmation", "recycle bin", "gomibako", "sakujo"]);
+
+    b.category("タスクバー", &["taskbar"]);
+    // 検索ボックスが非表示の場合は表示する設定にしても効果がない
+    b.push(
+        "検索のハイライト",
+        R::user(
+            r"Software\Microsoft\Windows\CurrentVersion\SearchSettings",
+            "IsDynamicSearchBoxEnabled",
+            DataType::DWord,
+        ),
+        vec![O::new("0", "表示しない"), O::new("1", "表示する")],
+    )
+    .set_windows_default(D::Absent)
+    .set_tags(&["search highlights", "kensaku"])
+    .requires(
+        &[Some("1")],
+        Condition::new(search_box, &[Some("1"), Some("2"), Some("3")]),
     );
 
     b.into_vec()
@@ -0,0 +1,28 @@
+use std::io;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    InvalidHeader(String),
+    Syntax { line: usize, text: String },
+}
+
+impl std::error::Error for Error {}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(e) => e.fmt(f),
+            Self::InvalidHeader(h) => write!(f, "Invalid .reg header ({h})"),
+            Self::Syntax { line, text } => write!(f, "Syntax error at line {line} ({text})"),
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(value: io::Error) -> Self {
+        Error::Io(value)
+    }
+}
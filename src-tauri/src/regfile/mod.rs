@@ -0,0 +1,7 @@
+mod error;
+mod parser;
+mod state;
+
+pub use error::{Error, Result};
+pub use parser::{RegData, RegFile};
+pub use state::RegFileState;
@@ -0,0 +1,215 @@
+use super::{Error, Result};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+const HEADER_V5: &str = "Windows Registry Editor Version 5.00";
+const HEADER_V4: &str = "REGEDIT4";
+
+/// `.reg` ファイルに書かれた値
+#[derive(Debug, Clone, PartialEq)]
+pub enum RegData {
+    String(String),
+    DWord(u32),
+    /// `hex:` (REG_BINARY) と `hex(n):` 形式の値 (型番号, 生データ)
+    Hex(u32, Vec<u8>),
+}
+
+impl RegData {
+    pub fn type_str(&self) -> &'static str {
+        match self {
+            Self::String(_) => "REG_SZ",
+            Self::DWord(_) => "REG_DWORD",
+            Self::Hex(t, _) => match t {
+                0 => "REG_NONE",
+                1 => "REG_SZ",
+                2 => "REG_EXPAND_SZ",
+                3 => "REG_BINARY",
+                4 => "REG_DWORD",
+                7 => "REG_MULTI_SZ",
+                11 => "REG_QWORD",
+                _ => "UNKNOWN",
+            },
+        }
+    }
+}
+
+/// regedit でエクスポートされた `.reg` ファイルの内容
+/// キーのパスは大文字、値の名前は小文字に揃えて保持する (既定値は空文字)
+#[derive(Debug, Default)]
+pub struct RegFile {
+    keys: BTreeMap<String, BTreeMap<String, RegData>>,
+}
+
+impl RegFile {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let bytes = std::fs::read(path)?;
+        Self::parse(&decode(&bytes))
+    }
+
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut ret = Self::default();
+        let mut lines = logical_lines(text).into_iter();
+
+        match lines.next() {
+            Some((_, h)) if h == HEADER_V5 || h == HEADER_V4 => (),
+            Some((_, h)) => return Err(Error::InvalidHeader(h)),
+            None => return Err(Error::InvalidHeader(String::new())),
+        }
+
+        // 削除指定 ([-KEY]) されたキーの値は無視する
+        let mut current: Option<String> = None;
+        for (no, line) in lines {
+            if line.is_empty() || line.starts_with(';') {
+                continue;
+            }
+
+            if let Some(key) = line.strip_prefix('[') {
+                let key = key.strip_suffix(']').ok_or_else(|| syntax(no, &line))?;
+                if key.starts_with('-') {
+                    current = None;
+                } else {
+                    let key = normalize_key(key);
+                    ret.keys.entry(key.clone()).or_default();
+                    current = Some(key);
+                }
+                continue;
+            }
+
+            let Some(key) = current.as_ref() else {
+                continue;
+            };
+            let (name, data) = parse_value_line(&line).ok_or_else(|| syntax(no, &line))?;
+            let values = ret.keys.entry(key.clone()).or_default();
+            match data {
+                Some(data) => values.insert(name.to_lowercase(), data),
+                None => values.remove(&name.to_lowercase()),
+            };
+        }
+
+        Ok(ret)
+    }
+
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.keys.contains_key(&normalize_key(key))
+    }
+
+    /// `key` 自身かその親キーがファイルに含まれているか
+    pub fn covers(&self, key: &str) -> bool {
+        let key = normalize_key(key);
+        self.keys
+            .keys()
+            .any(|k| key == *k || key.starts_with(&format!("{k}\\")))
+    }
+
+    pub fn get(&self, key: &str, value_name: &str) -> Option<&RegData> {
+        self.keys
+            .get(&normalize_key(key))
+            .and_then(|x| x.get(&value_name.to_lowercase()))
+    }
+}
+
+fn syntax(line: usize, text: &str) -> Error {
+    Error::Syntax {
+        line,
+        text: text.into(),
+    }
+}
+
+fn normalize_key(key: &str) -> String {
+    key.trim().trim_end_matches('\\').to_uppercase()
+}
+
+/// regedit は UTF-16LE (BOM 付き)、REGEDIT4 形式は ANSI で出力する
+fn decode(bytes: &[u8]) -> String {
+    if let Some(body) = bytes.strip_prefix(&[0xFF, 0xFE]) {
+        let utf16: Vec<u16> = body
+            .chunks_exact(2)
+            .map(|x| u16::from_le_bytes([x[0], x[1]]))
+            .collect();
+        String::from_utf16_lossy(&utf16)
+    } else {
+        let body = bytes.strip_prefix(&[0xEF, 0xBB, 0xBF]).unwrap_or(bytes);
+        String::from_utf8_lossy(body).into_owned()
+    }
+}
+
+/// 行末の `\` で折り返された hex 値を 1 行にまとめる (行番号は 1 始まり)
+fn logical_lines(text: &str) -> Vec<(usize, String)> {
+    let mut ret = Vec::new();
+    let mut buf: Option<(usize, String)> = None;
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim();
+        let (no, mut joined) = buf.take().unwrap_or((i + 1, String::new()));
+        joined.push_str(line);
+        if !joined.starts_with('[') && joined.ends_with('\\') {
+            joined.pop();
+            buf = Some((no, joined));
+            continue;
+        }
+        ret.push((no, joined));
+    }
+    if let Some(x) = buf {
+        ret.push(x);
+    }
+
+    ret
+}
+
+/// `"name"=data` を解析する。値の削除 (`"name"=-`) は `None` を返す
+fn parse_value_line(line: &str) -> Option<(String, Option<RegData>)> {
+    let (name, rest) = if let Some(rest) = line.strip_prefix('@') {
+        (String::new(), rest)
+    } else {
+        parse_quoted(line)?
+    };
+    let data = rest.trim_start().strip_prefix('=')?.trim();
+
+    if data == "-" {
+        return Some((name, None));
+    }
+
+    if data.starts_with('"') {
+        let (s, rest) = parse_quoted(data)?;
+        if !rest.trim().is_empty() {
+            return None;
+        }
+        return Some((name, Some(RegData::String(s))));
+    }
+
+    if let Some(v) = data.strip_prefix("dword:") {
+        let v = u32::from_str_radix(v.trim(), 16).ok()?;
+        return Some((name, Some(RegData::DWord(v))));
+    }
+
+    let (data_type, hex) = if let Some(hex) = data.strip_prefix("hex:") {
+        (3, hex)
+    } else {
+        let rest = data.strip_prefix("hex(")?;
+        let (t, hex) = rest.split_once("):")?;
+        (u32::from_str_radix(t, 16).ok()?, hex)
+    };
+
+    let bytes = hex
+        .split(',')
+        .map(str::trim)
+        .filter(|x| !x.is_empty())
+        .map(|x| u8::from_str_radix(x, 16).ok())
+        .collect::<Option<Vec<u8>>>()?;
+    Some((name, Some(RegData::Hex(data_type, bytes))))
+}
+
+/// 先頭の `"..."` を取り出し、`\\` と `\"` のエスケープを戻す
+fn parse_quoted(s: &str) -> Option<(String, &str)> {
+    let body = s.strip_prefix('"')?;
+    let mut ret = String::new();
+    let mut chars = body.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\\' => ret.push(chars.next()?.1),
+            '"' => return Some((ret, &body[i + 1..])),
+            c => ret.push(c),
+        }
+    }
+
+    None
+}
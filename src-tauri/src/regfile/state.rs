@@ -0,0 +1,58 @@
+use super::{RegData, RegFile};
+use crate::plan::CurrentState;
+use crate::win::reg::{DataType, Error, RegDef, Result, Value};
+
+/// エクスポートされた `.reg` ファイルを現在の状態として扱う
+pub struct RegFileState {
+    file: RegFile,
+}
+
+impl RegFileState {
+    pub fn new(file: RegFile) -> Self {
+        Self { file }
+    }
+
+    /// エクスポートされたファイルには 32 ビットのビューも WOW6432Node のパスで書かれる
+    fn key_path(def: &RegDef) -> String {
+        format!("{}\\{}", def.root.long_name(), def.physical_sub_key())
+    }
+}
+
+impl CurrentState for RegFileState {
+    fn covers(&self, def: &RegDef) -> bool {
+        self.file.covers(&Self::key_path(def))
+    }
+
+    fn key_exists(&self, def: &RegDef) -> Result<bool> {
+        Ok(self.file.contains_key(&Self::key_path(def)))
+    }
+
+    fn value(&self, def: &RegDef) -> Result<Option<Value>> {
+        let Some(data) = self.file.get(&Self::key_path(def), &def.value_name) else {
+            return Ok(None);
+        };
+
+        let ret = match (def.data_type, data) {
+            (DataType::Binary, RegData::Hex(3, b)) => Value::Binary(b.clone()),
+            (DataType::DWord, RegData::DWord(v)) => Value::DWord(*v),
+            (DataType::DWord, RegData::Hex(4, b)) if b.len() == 4 => {
+                Value::DWord(u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            }
+            (DataType::String, RegData::String(s)) => Value::String(s.clone()),
+            (DataType::String, RegData::Hex(1, b)) => {
+                let utf16: Vec<u16> = b
+                    .chunks_exact(2)
+                    .map(|x| u16::from_le_bytes([x[0], x[1]]))
+                    .collect();
+                Value::String(
+                    String::from_utf16_lossy(&utf16)
+                        .trim_end_matches('\0')
+                        .to_string(),
+                )
+            }
+            (t, d) => return Err(Error::UnexpectedDataType((t.str(), d.type_str()))),
+        };
+
+        Ok(Some(ret))
+    }
+}
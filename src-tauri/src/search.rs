@@ -0,0 +1,152 @@
+use crate::tweak::Tweak;
+
+/// 空白で区切った語をすべて含む項目を返す
+/// ラベル、分類、タグ、候補の説明、レジストリのパス (レジストリ以外は操作の対象) を対象にし、
+/// 大文字小文字、全角半角、ひらがなとカタカナは区別しない
+pub fn search<'a>(tweaks: &'a [Box<dyn Tweak>], query: &str) -> Vec<&'a dyn Tweak> {
+    let terms = normalize(query)
+        .split_whitespace()
+        .map(|x| x.to_string())
+        .collect::<Vec<_>>();
+
+    tweaks
+        .iter()
+        .map(|x| x.as_ref())
+        .filter(|tweak| {
+            let haystack = haystack(*tweak);
+            terms.iter().all(|x| haystack.contains(x.as_str()))
+        })
+        .collect()
+}
+
+fn haystack(tweak: &dyn Tweak) -> String {
+    let mut ret = vec![
+        tweak.label().to_string(),
+        tweak.category().to_string(),
+        tweak.describe(),
+    ];
+    if let Some(opt) = tweak.registry() {
+        ret.extend(opt.tags.iter().cloned());
+    }
+    ret.extend(tweak.candidates().iter().map(|x| x.description.clone()));
+    normalize(&ret.join("\n"))
+}
+
+/// 半角カタカナ (U+FF66..=U+FF9D) に対応する全角カタカナ
+const HALFWIDTH_KANA: &str =
+    "ヲァィゥェォャュョッーアイウエオカキクケコサシスセソタチツテトナニヌネノハヒフヘホマミムメモヤユヨラリルレロワン";
+
+/// 全角英数記号を半角に、ひらがなと半角カタカナを全角カタカナにして小文字にそろえる
+fn normalize(s: &str) -> String {
+    let mut ret = String::with_capacity(s.len());
+    for c in s.chars() {
+        let c = match c {
+            '\u{3000}' => ' ',
+            '\u{FF01}'..='\u{FF5E}' => char::from_u32(c as u32 - 0xFEE0).unwrap_or(c),
+            'ぁ'..='ゖ' => char::from_u32(c as u32 + 0x60).unwrap_or(c),
+            '\u{FF66}'..='\u{FF9D}' => HALFWIDTH_KANA.chars().nth(c as usize - 0xFF66).unwrap_or(c),
+            // 濁点と半濁点は前の文字と合わせる
+            '\u{FF9E}' | '\u{FF9F}' => {
+                if let Some(voiced) = ret.chars().last().and_then(|x| voice(x, c == '\u{FF9F}')) {
+                    ret.pop();
+                    ret.push(voiced);
+                    continue;
+                }
+                c
+            }
+            c => c,
+        };
+        ret.push(c);
+    }
+    ret.to_lowercase()
+}
+
+/// カタカナに濁点 (`semi` の場合は半濁点) を付けた文字
+fn voice(c: char, semi: bool) -> Option<char> {
+    match (c, semi) {
+        ('ウ', false) => Some('ヴ'),
+        (c, false) if "カキクケコサシスセソタチツテトハヒフヘホ".contains(c) => {
+            char::from_u32(c as u32 + 1)
+        }
+        (c, true) if "ハヒフヘホ".contains(c) => char::from_u32(c as u32 + 2),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command::{OptionCandidate, RegistryEditOption};
+    use crate::tweak::RegistryTweak;
+    use crate::win::reg::{DataType, RegDef};
+
+    fn tweak(id: u64, category: &str, label: &str, tags: &[&str]) -> Box<dyn Tweak> {
+        Box::new(RegistryTweak::new(RegistryEditOption {
+            id,
+            label: label.into(),
+            category: category.into(),
+            tags: tags.iter().map(|x| x.to_string()).collect(),
+            def: RegDef::hkcu(
+                r"Software\Win11Tweaks",
+                format!("Value{id}"),
+                DataType::DWord,
+            ),
+            values: vec![
+                OptionCandidate::new("0", "表示しない"),
+                OptionCandidate::new("1", "表示する"),
+            ],
+            domain: Default::default(),
+            bits: None,
+            windows_default: None,
+            requires: Vec::new(),
+            conflicts_with: Vec::new(),
+            policies: Vec::new(),
+        }))
+    }
+
+    fn tweaks() -> Vec<Box<dyn Tweak>> {
+        vec![
+            tweak(
+                1,
+                "エクスプローラ",
+                "ファイル拡張子",
+                &["file extension", "kakuchoushi"],
+            ),
+            tweak(2, "タスクバー", "検索ボックス", &["search box", "kensaku"]),
+            tweak(3, "エクスプローラ", "隠しファイル", &["hidden files"]),
+        ]
+    }
+
+    fn ids(query: &str) -> Vec<u64> {
+        let tweaks = tweaks();
+        search(&tweaks, query).iter().map(|x| x.id()).collect()
+    }
+
+    #[test]
+    fn normalizes_width_case_and_kana() {
+        assert_eq!(normalize("ＡＢＣ　ｄｅｆ１"), "abc def1");
+        assert_eq!(normalize("Windows"), "windows");
+        assert_eq!(normalize("えくすぷろーら"), "エクスプローラ");
+        assert_eq!(normalize("ｴｸｽﾌﾟﾛｰﾗ"), "エクスプローラ");
+        assert_eq!(normalize("ｶﾞｲﾄﾞ ｳﾞ ﾊﾟ"), "ガイド ヴ パ");
+        // 合わせられない濁点はそのまま残す
+        assert_eq!(normalize("ｱﾞ"), "ア\u{FF9E}");
+    }
+
+    #[test]
+    fn matches_all_terms_across_fields() {
+        assert_eq!(ids(""), [1, 2, 3]);
+        // 分類
+        assert_eq!(ids("えくすぷろーら"), [1, 3]);
+        assert_eq!(ids("ﾀｽｸﾊﾞｰ"), [2]);
+        // タグ (英語やローマ字表記)
+        assert_eq!(ids("KAKUCHOUSHI"), [1]);
+        assert_eq!(ids("ｈｉｄｄｅｎ"), [3]);
+        // すべての語を含む項目だけ
+        assert_eq!(ids("エクスプローラ file"), [1, 3]);
+        assert_eq!(ids("エクスプローラ extension"), [1]);
+        assert_eq!(ids("タスクバー hidden"), Vec::<u64>::new());
+        // 候補の説明とレジストリのパス
+        assert_eq!(ids("表示しない value2"), [2]);
+    }
+}
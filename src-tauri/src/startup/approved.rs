@@ -0,0 +1,169 @@
+//! `Explorer\StartupApproved` の値 (タスク マネージャーのスタートアップ アプリの有効/無効)
+//! 先頭 4 バイトがフラグ (最下位ビットが 1 なら無効)、続く 8 バイトが無効にした日時の FILETIME
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// 1601-01-01 から 1970-01-01 までの 100 ナノ秒単位の数
+const UNIX_EPOCH_FILETIME: u64 = 116_444_736_000_000_000;
+
+/// 無効を表すフラグ
+const DISABLED: u32 = 0x01;
+
+/// タスク マネージャーが有効にしたときに書き込むフラグ
+const ENABLED_FLAGS: u32 = 0x02;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Approval {
+    pub flags: u32,
+    /// 無効にした日時 (FILETIME。有効な場合は 0)
+    pub changed: u64,
+}
+
+impl Approval {
+    /// 書き込むときの長さ
+    pub const LEN: usize = 12;
+
+    /// 値が無い場合と同じ、有効な状態
+    pub fn enabled() -> Self {
+        Self {
+            flags: ENABLED_FLAGS,
+            changed: 0,
+        }
+    }
+
+    /// フラグより短い値は `None` (日時が欠けている場合は 0 とする)
+    pub fn decode(data: &[u8]) -> Option<Self> {
+        let flags = u32::from_le_bytes(data.get(0..4)?.try_into().ok()?);
+        let changed = data
+            .get(4..12)
+            .and_then(|x| x.try_into().ok())
+            .map(u64::from_le_bytes)
+            .unwrap_or(0);
+        Some(Self { flags, changed })
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut ret = Vec::with_capacity(Self::LEN);
+        ret.extend_from_slice(&self.flags.to_le_bytes());
+        ret.extend_from_slice(&self.changed.to_le_bytes());
+        ret
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.flags & DISABLED == 0
+    }
+
+    /// 有効/無効を切り替えた値 (他のフラグは残す。無効にする場合は `now` を記録する)
+    pub fn with_enabled(&self, enabled: bool, now: u64) -> Self {
+        match enabled {
+            true => Self {
+                flags: self.flags & !DISABLED,
+                changed: 0,
+            },
+            false => Self {
+                flags: self.flags | DISABLED,
+                changed: now,
+            },
+        }
+    }
+}
+
+/// 日時を FILETIME (1601-01-01 からの 100 ナノ秒単位) にする
+pub fn filetime(time: SystemTime) -> u64 {
+    match time.duration_since(UNIX_EPOCH) {
+        Ok(d) => UNIX_EPOCH_FILETIME + (d.as_nanos() / 100) as u64,
+        Err(_) => 0,
+    }
+}
+
+/// FILETIME を UNIX 時間 (秒) にする (1970 年より前は `None`)
+pub fn unix_seconds(filetime: u64) -> Option<u64> {
+    filetime
+        .checked_sub(UNIX_EPOCH_FILETIME)
+        .map(|x| x / 10_000_000)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    /// 2024-01-01T00:00:00Z
+    const CHANGED: u64 = 133_485_408_000_000_000;
+    const CHANGED_BYTES: [u8; 8] = [0x00, 0xc0, 0x89, 0x76, 0x45, 0x3c, 0xda, 0x01];
+
+    fn blob(flags: u8, changed: [u8; 8]) -> Vec<u8> {
+        [[flags, 0, 0, 0].as_slice(), changed.as_slice()].concat()
+    }
+
+    #[test]
+    fn round_trips_task_manager_values() {
+        for (flags, changed, enabled) in [
+            (0x02, [0; 8], true),
+            (0x03, CHANGED_BYTES, false),
+            (0x06, [0; 8], true),
+            (0x07, CHANGED_BYTES, false),
+        ] {
+            let data = blob(flags, changed);
+            let approval = Approval::decode(&data).unwrap();
+            assert_eq!(approval.flags, flags as u32);
+            assert_eq!(approval.changed, u64::from_le_bytes(changed));
+            assert_eq!(approval.is_enabled(), enabled);
+            assert_eq!(approval.encode(), data);
+        }
+    }
+
+    #[test]
+    fn short_values() {
+        assert_eq!(Approval::decode(&[]), None);
+        assert_eq!(Approval::decode(&[0x03, 0, 0]), None);
+        for len in 4..Approval::LEN {
+            let data = &blob(0x03, CHANGED_BYTES)[..len];
+            assert_eq!(
+                Approval::decode(data),
+                Some(Approval {
+                    flags: 0x03,
+                    changed: 0
+                })
+            );
+        }
+    }
+
+    #[test]
+    fn with_enabled_keeps_other_flags() {
+        let disabled = Approval {
+            flags: 0x06,
+            changed: 0,
+        }
+        .with_enabled(false, CHANGED);
+        assert_eq!(
+            disabled,
+            Approval {
+                flags: 0x07,
+                changed: CHANGED
+            }
+        );
+        assert!(!disabled.is_enabled());
+
+        let enabled = disabled.with_enabled(true, 0);
+        assert_eq!(
+            enabled,
+            Approval {
+                flags: 0x06,
+                changed: 0
+            }
+        );
+        assert_eq!(Approval::enabled().encode(), blob(0x02, [0; 8]));
+    }
+
+    #[test]
+    fn converts_filetime() {
+        let time = UNIX_EPOCH + Duration::from_secs(1_704_067_200);
+        assert_eq!(filetime(time), CHANGED);
+        assert_eq!(filetime(UNIX_EPOCH), UNIX_EPOCH_FILETIME);
+        assert_eq!(unix_seconds(CHANGED), Some(1_704_067_200));
+        assert_eq!(unix_seconds(CHANGED + 9_999_999), Some(1_704_067_200));
+        assert_eq!(unix_seconds(UNIX_EPOCH_FILETIME - 1), None);
+        assert_eq!(unix_seconds(0), None);
+    }
+}
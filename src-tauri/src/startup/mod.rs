@@ -0,0 +1,233 @@
+//! サインイン時に起動するアプリ (Run / RunOnce とスタートアップ フォルダー)
+//! 有効/無効はタスク マネージャーと同じく `Explorer\StartupApproved` に記録する
+
+mod approved;
+mod error;
+
+pub use approved::{filetime, unix_seconds, Approval};
+pub use error::{Error, Result};
+
+use crate::task::{CancelToken, Progress};
+use crate::win::reg::{self, DataType, ProcessEnvironment, RegDef, Registry, Value, View};
+use serde::Serialize;
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+const RUN_KEY: &str = r"Software\Microsoft\Windows\CurrentVersion\Run";
+const RUN_ONCE_KEY: &str = r"Software\Microsoft\Windows\CurrentVersion\RunOnce";
+const APPROVED_KEY: &str = r"Software\Microsoft\Windows\CurrentVersion\Explorer\StartupApproved";
+
+/// 登録されている場所
+/// HKLM はエクスプローラと同じく 64 ビットのビューを参照し、32 ビットのビュー (WOW6432Node) は別に扱う
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Source {
+    UserRun,
+    UserRunOnce,
+    MachineRun,
+    MachineRun32,
+    MachineRunOnce,
+    /// `%APPDATA%\...\Startup`
+    UserFolder,
+    /// `%ProgramData%\...\StartUp`
+    CommonFolder,
+}
+
+impl Source {
+    pub const ALL: [Self; 7] = [
+        Self::UserRun,
+        Self::UserRunOnce,
+        Self::MachineRun,
+        Self::MachineRun32,
+        Self::MachineRunOnce,
+        Self::UserFolder,
+        Self::CommonFolder,
+    ];
+
+    pub fn str(&self) -> &'static str {
+        match self {
+            Self::UserRun => "user-run",
+            Self::UserRunOnce => "user-run-once",
+            Self::MachineRun => "machine-run",
+            Self::MachineRun32 => "machine-run32",
+            Self::MachineRunOnce => "machine-run-once",
+            Self::UserFolder => "user-folder",
+            Self::CommonFolder => "common-folder",
+        }
+    }
+
+    /// 値の名前がアプリの名前になるキー (スタートアップ フォルダーは `None`)
+    fn run_key(&self) -> Option<RegDef> {
+        let ret = match self {
+            Self::UserRun => RegDef::user(RUN_KEY, "", DataType::String),
+            Self::UserRunOnce => RegDef::user(RUN_ONCE_KEY, "", DataType::String),
+            Self::MachineRun => RegDef::hklm(RUN_KEY, "", DataType::String).in_view(View::Bit64),
+            Self::MachineRun32 => RegDef::hklm(RUN_KEY, "", DataType::String).in_view(View::Bit32),
+            Self::MachineRunOnce => {
+                RegDef::hklm(RUN_ONCE_KEY, "", DataType::String).in_view(View::Bit64)
+            }
+            Self::UserFolder | Self::CommonFolder => return None,
+        };
+        Some(ret)
+    }
+
+    /// 有効/無効を記録する値 (RunOnce は切り替えられないため `None`)
+    fn approved(&self, name: &str) -> Option<RegDef> {
+        let (user, sub_key) = match self {
+            Self::UserRun => (true, "Run"),
+            Self::MachineRun => (false, "Run"),
+            Self::MachineRun32 => (false, "Run32"),
+            Self::UserFolder => (true, "StartupFolder"),
+            Self::CommonFolder => (false, "StartupFolder"),
+            Self::UserRunOnce | Self::MachineRunOnce => return None,
+        };
+        let sub_key = format!(r"{APPROVED_KEY}\{sub_key}");
+        let ret = match user {
+            true => RegDef::user(sub_key, name, DataType::Binary),
+            false => RegDef::hklm(sub_key, name, DataType::Binary).in_view(View::Bit64),
+        };
+        Some(ret)
+    }
+
+    /// 進み具合に表示する場所 (キーのパスまたはフォルダー)
+    fn location(&self) -> String {
+        match (self.run_key(), self.folder()) {
+            (Some(key), _) => key.to_string(),
+            (None, Some(dir)) => dir.to_string_lossy().into_owned(),
+            (None, None) => self.str().to_string(),
+        }
+    }
+
+    fn folder(&self) -> Option<PathBuf> {
+        let path = match self {
+            Self::UserFolder => r"%APPDATA%\Microsoft\Windows\Start Menu\Programs\Startup",
+            Self::CommonFolder => r"%ProgramData%\Microsoft\Windows\Start Menu\Programs\StartUp",
+            _ => return None,
+        };
+        Some(PathBuf::from(reg::expand(path, &ProcessEnvironment)))
+    }
+}
+
+/// スタートアップ アプリの 1 つの項目
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StartupEntry {
+    /// 画面で項目を指す ID
+    pub id: String,
+    pub source: Source,
+    /// 値の名前、またはフォルダー内のファイル名
+    pub name: String,
+    /// 実行するコマンド、またはファイルのパス
+    pub command: String,
+    pub enabled: bool,
+    /// 無効にした日時 (UNIX 時間の秒。記録が無い場合は `None`)
+    pub disabled_at: Option<u64>,
+    /// 有効/無効を切り替えられるか
+    pub toggleable: bool,
+}
+
+/// スタートアップ アプリをすべて列挙する
+pub fn list() -> Result<Vec<StartupEntry>> {
+    list_with(&CancelToken::new(), |_| ())
+}
+
+/// 登録されている場所ごとに進み具合を通知しながら列挙する
+pub fn list_with(
+    cancel: &CancelToken,
+    mut progress: impl FnMut(&Progress),
+) -> Result<Vec<StartupEntry>> {
+    let mut ret = Vec::new();
+    let total = Source::ALL.len();
+    for (i, source) in Source::ALL.into_iter().enumerate() {
+        if cancel.is_cancelled() {
+            return Err(Error::Cancelled);
+        }
+        progress(&Progress {
+            step: i + 1,
+            total,
+            key: source.location(),
+        });
+        for (name, command) in source_items(source)? {
+            ret.push(read_entry(source, name, command)?);
+        }
+    }
+    Ok(ret)
+}
+
+/// 名前とコマンド (またはパス) の組
+fn source_items(source: Source) -> Result<Vec<(String, String)>> {
+    if let Some(key) = source.run_key() {
+        let mut ret = Vec::new();
+        for name in Registry::from_def(&key).value_names()? {
+            let def = RegDef {
+                value_name: name.clone(),
+                ..key.clone()
+            };
+            let command = match Registry::from_def(&def).find_any_value()? {
+                Some(Value::String(v)) | Some(Value::ExpandString(v)) => v,
+                Some(v) => v.to_string(),
+                None => continue,
+            };
+            ret.push((name, command));
+        }
+        return Ok(ret);
+    }
+
+    let Some(dir) = source.folder() else {
+        return Ok(Vec::new());
+    };
+    let entries = match std::fs::read_dir(&dir) {
+        Ok(v) => v,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+    let mut ret = Vec::new();
+    for entry in entries {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if entry.file_type()?.is_dir() || name.eq_ignore_ascii_case("desktop.ini") {
+            continue;
+        }
+        ret.push((name, entry.path().to_string_lossy().into_owned()));
+    }
+    Ok(ret)
+}
+
+fn read_entry(source: Source, name: String, command: String) -> Result<StartupEntry> {
+    // 値が無い場合や解釈できない場合は、タスク マネージャーと同じく有効とみなす
+    let approval = match source.approved(&name) {
+        Some(def) => read_approval(&def)?,
+        None => None,
+    };
+    let enabled = approval.is_none_or(|x| x.is_enabled());
+    Ok(StartupEntry {
+        id: format!("{}:{name}", source.str()),
+        source,
+        name,
+        command,
+        enabled,
+        disabled_at: approval
+            .filter(|_| !enabled)
+            .and_then(|x| unix_seconds(x.changed)),
+        toggleable: source.approved("").is_some(),
+    })
+}
+
+fn read_approval(def: &RegDef) -> Result<Option<Approval>> {
+    match Registry::from_def(def).find_any_value()? {
+        Some(Value::Binary(v)) => Ok(Approval::decode(&v)),
+        _ => Ok(None),
+    }
+}
+
+/// タスク マネージャーと同じく、StartupApproved の値を書き換えて有効/無効を切り替える
+/// (Run の値やフォルダーのファイルはそのまま残す)
+pub fn set_enabled(entry: &StartupEntry, enabled: bool) -> Result<()> {
+    let def = entry
+        .source
+        .approved(&entry.name)
+        .ok_or_else(|| Error::Unsupported(entry.name.clone()))?;
+    let current = read_approval(&def)?.unwrap_or_else(Approval::enabled);
+    let ret = current.with_enabled(enabled, filetime(SystemTime::now()));
+    Registry::from_def(&def).write_value(&Value::Binary(ret.encode()))?;
+    Ok(())
+}
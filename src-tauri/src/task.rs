@@ -0,0 +1,32 @@
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// 時間のかかる処理の進み具合
+#[derive(Debug, Clone, Serialize)]
+pub struct Progress {
+    /// 1 から始まる現在の手順
+    pub step: usize,
+    pub total: usize,
+    /// 処理中のキー
+    pub key: String,
+}
+
+/// 処理の中断要求 (別のスレッドから `cancel` する)
+/// 中断は手順の区切りで確認するため、処理中の手順は最後まで行われる
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
@@ -0,0 +1,157 @@
+use super::{Error, Result, Tweak, TweakInfo};
+use crate::watch::ValueChanged;
+use crate::win::ps;
+
+/// 新しいユーザーに追加される (プロビジョニングされた) AppX パッケージを操作する
+pub trait AppxProvisioning: Send + Sync {
+    fn is_provisioned(&self, name: &str) -> Result<bool>;
+    fn remove(&self, name: &str) -> Result<()>;
+}
+
+/// PowerShell の `Get-AppxProvisionedPackage` と `Remove-AppxProvisionedPackage` を使う
+pub struct WinAppxProvisioning;
+
+/// すべてのパッケージの名前 (結果は `ps::query` でほかの項目と共有する)
+const PACKAGE_NAMES: &str = "Get-AppxProvisionedPackage -Online | ForEach-Object DisplayName";
+
+impl AppxProvisioning for WinAppxProvisioning {
+    fn is_provisioned(&self, name: &str) -> Result<bool> {
+        Ok(ps::query(PACKAGE_NAMES)?
+            .lines()
+            .any(|x| x.trim().eq_ignore_ascii_case(name)))
+    }
+
+    fn remove(&self, name: &str) -> Result<()> {
+        let script = format!(
+            "Get-AppxProvisionedPackage -Online | Where-Object DisplayName -eq {} \
+             | Remove-AppxProvisionedPackage -Online | Out-Null",
+            ps::quote(name)
+        );
+        ps::run(&script)?;
+        Ok(())
+    }
+}
+
+/// プロビジョニングされたパッケージ (候補の値は `1` が残す、`0` が削除)
+/// 削除したパッケージを元に戻すことはできない
+pub struct ProvisionedAppTweak<P = WinAppxProvisioning> {
+    info: TweakInfo,
+    name: String,
+    provisioning: P,
+}
+
+impl ProvisionedAppTweak {
+    pub fn new(info: TweakInfo, name: impl Into<String>) -> Self {
+        Self::with_provisioning(info, name, WinAppxProvisioning)
+    }
+}
+
+impl<P: AppxProvisioning> ProvisionedAppTweak<P> {
+    pub fn with_provisioning(info: TweakInfo, name: impl Into<String>, provisioning: P) -> Self {
+        Self {
+            info,
+            name: name.into(),
+            provisioning,
+        }
+    }
+}
+
+impl<P: AppxProvisioning> Tweak for ProvisionedAppTweak<P> {
+    fn info(&self) -> &TweakInfo {
+        &self.info
+    }
+
+    fn read_state(&self) -> ValueChanged {
+        let state = self
+            .provisioning
+            .is_provisioned(&self.name)
+            .map(|x| Some(if x { "1" } else { "0" }.to_string()));
+        self.info.status(state)
+    }
+
+    fn apply(&self, candidate: Option<&str>) -> Result<()> {
+        let candidate = self.info.check(candidate)?;
+        let provisioned = self.provisioning.is_provisioned(&self.name)?;
+        match (candidate.value.as_str(), provisioned) {
+            ("0", true) => self.provisioning.remove(&self.name),
+            ("0", false) | ("1", true) => Ok(()),
+            (v, _) => Err(Error::Unsupported(self.info.id, v.into())),
+        }
+    }
+
+    fn describe(&self) -> String {
+        format!("AppX: {}", self.name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command::OptionCandidate;
+    use std::sync::Mutex;
+
+    /// プロビジョニングされたパッケージの名前
+    struct FakeProvisioning(Mutex<Vec<String>>);
+
+    impl AppxProvisioning for FakeProvisioning {
+        fn is_provisioned(&self, name: &str) -> Result<bool> {
+            Ok(self.0.lock().unwrap().iter().any(|x| x == name))
+        }
+
+        fn remove(&self, name: &str) -> Result<()> {
+            self.0.lock().unwrap().retain(|x| x != name);
+            Ok(())
+        }
+    }
+
+    fn tweak(packages: &[&str]) -> ProvisionedAppTweak<FakeProvisioning> {
+        ProvisionedAppTweak::with_provisioning(
+            TweakInfo::new(
+                1,
+                "",
+                "",
+                vec![
+                    OptionCandidate::new("0", "追加しない"),
+                    OptionCandidate::new("1", "追加する"),
+                ],
+            ),
+            "Microsoft.BingNews",
+            FakeProvisioning(Mutex::new(packages.iter().map(|x| x.to_string()).collect())),
+        )
+    }
+
+    #[test]
+    fn reads_provisioned_state() {
+        let state = tweak(&["Microsoft.BingNews"]).read_state();
+        assert_eq!(state.value.as_deref(), Some("1"));
+        assert_eq!(state.candidate.as_deref(), Some("1"));
+        let state = tweak(&["Microsoft.BingWeather"]).read_state();
+        assert_eq!(state.value.as_deref(), Some("0"));
+    }
+
+    #[test]
+    fn removes_only_the_package() {
+        let tweak = tweak(&["Microsoft.BingNews", "Microsoft.BingWeather"]);
+        tweak.apply(Some("0")).unwrap();
+        assert_eq!(
+            *tweak.provisioning.0.lock().unwrap(),
+            ["Microsoft.BingWeather"]
+        );
+        // 削除済みの場合は何もしない
+        tweak.apply(Some("0")).unwrap();
+    }
+
+    #[test]
+    fn cannot_provision_removed_package() {
+        assert!(tweak(&["Microsoft.BingNews"]).apply(Some("1")).is_ok());
+        let removed = tweak(&[]);
+        assert!(matches!(
+            removed.apply(Some("1")),
+            Err(Error::Unsupported(1, v)) if v == "1"
+        ));
+        assert!(matches!(
+            removed.apply(Some("2")),
+            Err(Error::UnknownCandidate(1, _))
+        ));
+    }
+}
@@ -0,0 +1,69 @@
+use crate::domain::ValidationError;
+use crate::win;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Debug)]
+pub enum Error {
+    Validation(u64, ValidationError),
+    /// 候補に含まれない値
+    UnknownCandidate(u64, String),
+    /// 現在の状態からは適用できない (削除したパッケージの再インストールなど)
+    Unsupported(u64, String),
+    /// 読み取った状態を解釈できない
+    UnexpectedState(String),
+    /// 設定ファイルを解釈できない
+    InvalidFile(String),
+    /// 管理者権限が無いため、現在のユーザーだけの仮想的な場所に書き込まれた
+    Virtualized(u64),
+    /// 書き込んだ直後に別のプログラムによって元に戻された
+    Reverted(u64),
+    Io(std::io::Error),
+    Registry(win::reg::Error),
+    PowerShell(win::ps::Error),
+    Win32(windows::core::Error),
+}
+
+impl std::error::Error for Error {}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Validation(id, e) => write!(f, "{e} (ID={id})"),
+            Self::UnknownCandidate(id, v) => write!(f, "Unknown candidate ({v}) (ID={id})"),
+            Self::Unsupported(id, v) => write!(f, "Cannot apply {v} (ID={id})"),
+            Self::UnexpectedState(v) => write!(f, "Unexpected state ({v})"),
+            Self::InvalidFile(e) => write!(f, "Invalid file ({e})"),
+            Self::Virtualized(id) => write!(f, "Value was written to the virtual store (ID={id})"),
+            Self::Reverted(id) => write!(f, "Value was reverted by another program (ID={id})"),
+            Self::Io(e) => e.fmt(f),
+            Self::Registry(e) => e.fmt(f),
+            Self::PowerShell(e) => e.fmt(f),
+            Self::Win32(e) => e.fmt(f),
+        }
+    }
+}
+
+impl From<win::reg::Error> for Error {
+    fn from(value: win::reg::Error) -> Self {
+        Error::Registry(value)
+    }
+}
+
+impl From<win::ps::Error> for Error {
+    fn from(value: win::ps::Error) -> Self {
+        Error::PowerShell(value)
+    }
+}
+
+impl From<windows::core::Error> for Error {
+    fn from(value: windows::core::Error) -> Self {
+        Error::Win32(value)
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(value: std::io::Error) -> Self {
+        Error::Io(value)
+    }
+}
@@ -0,0 +1,101 @@
+//! INI ファイルの 1 つのキーだけを書き換える
+//! 他の行 (コメントや空行を含む) と改行コードはそのまま残す
+
+/// セクション (`""` は最初のセクションより前) のキーの値を読み取る
+pub fn get(text: &str, section: &str, key: &str) -> Option<String> {
+    let mut current = String::new();
+    for line in text.lines() {
+        match parse_line(line) {
+            Line::Section(name) => current = name.to_string(),
+            Line::Entry(k, v)
+                if current.eq_ignore_ascii_case(section) && k.eq_ignore_ascii_case(key) =>
+            {
+                return Some(v.to_string())
+            }
+            _ => (),
+        }
+    }
+    None
+}
+
+/// キーの値を書き換える (`None` はキーの行を削除する)
+/// キーやセクションが無い場合はセクションの最後 (またはファイルの最後) に追加する
+pub fn set(text: &str, section: &str, key: &str, value: Option<&str>) -> String {
+    let newline = if text.contains("\r\n") { "\r\n" } else { "\n" };
+    let mut lines: Vec<String> = text.lines().map(str::to_string).collect();
+
+    let mut current = String::new();
+    let mut found = None;
+    // セクションの最後の空行以外の行 (`""` の場合は先頭)
+    let mut section_end = section.is_empty().then_some(None);
+    for (i, line) in lines.iter().enumerate() {
+        match parse_line(line) {
+            Line::Section(name) => current = name.to_string(),
+            Line::Entry(k, _)
+                if current.eq_ignore_ascii_case(section) && k.eq_ignore_ascii_case(key) =>
+            {
+                found = Some(i);
+                break;
+            }
+            Line::Blank => continue,
+            _ => (),
+        }
+        if current.eq_ignore_ascii_case(section) {
+            section_end = Some(Some(i));
+        }
+    }
+
+    match (found, value) {
+        (Some(i), Some(v)) => {
+            // キーの書き方 (大文字小文字や `=` の前の空白) は残す
+            let head = lines[i].split_once('=').map(|x| x.0).unwrap_or(key);
+            lines[i] = format!("{head}={v}");
+        }
+        (Some(i), None) => {
+            lines.remove(i);
+        }
+        (None, None) => return text.to_string(),
+        (None, Some(v)) => match section_end {
+            Some(Some(i)) => lines.insert(i + 1, format!("{key}={v}")),
+            Some(None) => lines.insert(0, format!("{key}={v}")),
+            None => {
+                if lines.last().is_some_and(|x| !x.trim().is_empty()) {
+                    lines.push(String::new());
+                }
+                lines.push(format!("[{section}]"));
+                lines.push(format!("{key}={v}"));
+            }
+        },
+    }
+
+    let mut ret = lines.join(newline);
+    if text.is_empty() || text.ends_with('\n') {
+        ret.push_str(newline);
+    }
+    ret
+}
+
+enum Line<'a> {
+    Section(&'a str),
+    Entry(&'a str, &'a str),
+    /// コメントなど
+    Other,
+    Blank,
+}
+
+fn parse_line(line: &str) -> Line<'_> {
+    let trimmed = line.trim();
+    if trimmed.is_empty() {
+        return Line::Blank;
+    }
+    if trimmed.starts_with([';', '#']) {
+        return Line::Other;
+    }
+    if let Some(name) = trimmed.strip_prefix('[').and_then(|x| x.strip_suffix(']')) {
+        return Line::Section(name.trim());
+    }
+    match trimmed.split_once('=') {
+        Some((k, v)) => Line::Entry(k.trim(), v.trim()),
+        None => Line::Other,
+    }
+}
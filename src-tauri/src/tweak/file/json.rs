@@ -0,0 +1,558 @@
+//! コメント付きの JSON (JSONC) の一部だけを書き換える
+//! 書き換える値の範囲だけを置き換え、それ以外の書式やコメントはそのまま残す
+
+use super::super::{Error, Result};
+
+/// JSON Pointer (RFC 6901) を要素に分ける
+pub fn parse_pointer(pointer: &str) -> Result<Vec<String>> {
+    if pointer.is_empty() {
+        return Ok(Vec::new());
+    }
+    let Some(rest) = pointer.strip_prefix('/') else {
+        return Err(Error::InvalidFile(format!(
+            "Invalid JSON pointer ({pointer})"
+        )));
+    };
+    Ok(rest
+        .split('/')
+        .map(|x| x.replace("~1", "/").replace("~0", "~"))
+        .collect())
+}
+
+/// 値を読み取る (存在しない場合は `None`)
+pub fn get(text: &str, pointer: &[String]) -> Result<Option<serde_json::Value>> {
+    match locate(text, pointer)? {
+        Location::Found { value, .. } => {
+            let raw = strip_comments(&text[value.0..value.1]);
+            let ret = serde_json::from_str(&raw).map_err(|e| Error::InvalidFile(e.to_string()))?;
+            Ok(Some(ret))
+        }
+        Location::Missing { .. } => Ok(None),
+    }
+}
+
+/// 値を書き換える (`None` はメンバーを削除する)
+/// 途中のオブジェクトが無い場合は作成する
+pub fn set(text: &str, pointer: &[String], value: Option<&serde_json::Value>) -> Result<String> {
+    if pointer.is_empty() {
+        let Some(value) = value else {
+            return Err(Error::InvalidFile("Cannot delete the root value".into()));
+        };
+        return Ok(render(value, "", &indent_unit(text), newline(text)));
+    }
+
+    let nl = newline(text);
+    let mut ret = text.to_string();
+    match (locate(text, pointer)?, value) {
+        (Location::Found { value: span, .. }, Some(v)) => {
+            let indent = line_indent(text, span.0);
+            ret.replace_range(span.0..span.1, &render(v, &indent, &indent_unit(text), nl));
+        }
+        (Location::Found { member, .. }, None) => {
+            let Some(member) = member else {
+                return Err(Error::InvalidFile("Cannot delete an array element".into()));
+            };
+            let (start, end) = removal_range(text, &member);
+            ret.replace_range(start..end, "");
+        }
+        (Location::Missing { .. }, None) => (),
+        (
+            Location::Missing {
+                object,
+                depth,
+                last_member,
+            },
+            Some(v),
+        ) => {
+            // 残りの要素はオブジェクトを入れ子にして作る
+            let rest = &pointer[depth..];
+            let mut nested = v.clone();
+            for key in rest[1..].iter().rev() {
+                let mut map = serde_json::Map::new();
+                map.insert(key.clone(), nested);
+                nested = serde_json::Value::Object(map);
+            }
+            let key = serde_json::to_string(&rest[0]).unwrap_or_default();
+            let unit = indent_unit(text);
+
+            match last_member {
+                Some(last) => {
+                    let indent = line_indent(text, last.key);
+                    let member = format!("{key}: {}", render(&nested, &indent, &unit, nl));
+                    // 末尾のカンマが既にある場合はその後ろに追加する
+                    let mut p = Parser::new(text, last.value.1);
+                    p.skip_trivia();
+                    if p.peek() == Some(b',') {
+                        ret.insert_str(p.pos + 1, &format!("{nl}{indent}{member},"));
+                    } else {
+                        ret.insert_str(last.value.1, &format!(",{nl}{indent}{member}"));
+                    }
+                }
+                None => {
+                    let outer = line_indent(text, object.0);
+                    let indent = format!("{outer}{unit}");
+                    let member = format!("{key}: {}", render(&nested, &indent, &unit, nl));
+                    // 空のオブジェクトは中の空白ごと置き換える
+                    ret.replace_range(
+                        object.0..object.1 + 1,
+                        &format!("{{{nl}{indent}{member}{nl}{outer}}}"),
+                    );
+                }
+            }
+        }
+    }
+    Ok(ret)
+}
+
+/// オブジェクトのメンバーの位置
+#[derive(Debug, Clone, Copy)]
+struct Member {
+    /// キーの文字列の先頭
+    key: usize,
+    value: (usize, usize),
+}
+
+enum Location {
+    Found {
+        value: (usize, usize),
+        /// 配列の要素の場合は `None`
+        member: Option<Member>,
+    },
+    Missing {
+        /// 見つかった一番深いオブジェクトの `{` と `}` の位置
+        object: (usize, usize),
+        /// `object` までにたどった要素の数
+        depth: usize,
+        last_member: Option<Member>,
+    },
+}
+
+fn locate(text: &str, pointer: &[String]) -> Result<Location> {
+    let mut p = Parser::new(text, 0);
+    p.skip_trivia();
+    let mut member = None;
+    for (depth, key) in pointer.iter().enumerate() {
+        let start = p.pos;
+        match p.peek() {
+            Some(b'{') => {
+                let (members, close) = p.object()?;
+                match members.iter().find(|(k, _)| k == key) {
+                    Some((_, m)) => {
+                        member = Some(*m);
+                        p.pos = m.value.0;
+                    }
+                    None => {
+                        return Ok(Location::Missing {
+                            object: (start, close),
+                            depth,
+                            last_member: members.last().map(|x| x.1),
+                        })
+                    }
+                }
+            }
+            Some(b'[') => {
+                let items = p.array()?;
+                let index = key.parse::<usize>().ok();
+                match index.and_then(|x| items.get(x)) {
+                    Some(item) => {
+                        member = None;
+                        p.pos = item.0;
+                    }
+                    None => {
+                        return Err(Error::InvalidFile(format!(
+                            "Array index out of range ({key})"
+                        )))
+                    }
+                }
+            }
+            _ => {
+                return Err(Error::InvalidFile(format!(
+                    "Not an object or array at /{}",
+                    pointer[..depth].join("/")
+                )))
+            }
+        }
+    }
+
+    let start = p.pos;
+    p.value()?;
+    Ok(Location::Found {
+        value: (start, p.pos),
+        member,
+    })
+}
+
+/// メンバーを削除する範囲 (前後のカンマと、メンバーだけの行を含める)
+fn removal_range(text: &str, member: &Member) -> (usize, usize) {
+    let mut p = Parser::new(text, member.value.1);
+    p.skip_trivia();
+    if p.peek() == Some(b',') {
+        // 後ろのカンマまで消し、行ごと消せる場合は行末の改行も消す
+        let mut end = p.pos + 1;
+        let rest = &text[end..];
+        let line_end = rest.find('\n').map(|x| end + x + 1);
+        if let Some(line_end) = line_end {
+            if text[end..line_end].trim().is_empty() {
+                end = line_end;
+            }
+        }
+        let start = line_start(text, member.key).unwrap_or(member.key);
+        (start, end)
+    } else {
+        // 最後のメンバーは前のカンマから消す
+        let before = text[..member.key].trim_end();
+        let start = match before.strip_suffix(',') {
+            Some(x) => x.len(),
+            None => line_start(text, member.key).unwrap_or(member.key),
+        };
+        (start, member.value.1)
+    }
+}
+
+/// 位置の前が空白だけの場合は行頭の位置
+fn line_start(text: &str, pos: usize) -> Option<usize> {
+    let start = text[..pos].rfind('\n').map(|x| x + 1).unwrap_or(0);
+    text[start..pos].trim().is_empty().then_some(start)
+}
+
+fn line_indent(text: &str, pos: usize) -> String {
+    let start = text[..pos].rfind('\n').map(|x| x + 1).unwrap_or(0);
+    text[start..pos]
+        .chars()
+        .take_while(|x| *x == ' ' || *x == '\t')
+        .collect()
+}
+
+/// ファイルで使われているインデントの単位 (分からない場合は空白 4 つ)
+fn indent_unit(text: &str) -> String {
+    text.lines()
+        .map(|x| {
+            x.chars()
+                .take_while(|c| *c == ' ' || *c == '\t')
+                .collect::<String>()
+        })
+        .filter(|x| !x.is_empty())
+        .min_by_key(|x| x.len())
+        .unwrap_or_else(|| "    ".into())
+}
+
+/// ファイルで使われている改行 (CRLF を含む場合は CRLF)
+fn newline(text: &str) -> &'static str {
+    if text.contains("\r\n") {
+        "\r\n"
+    } else {
+        "\n"
+    }
+}
+
+/// 値をファイルのインデントと改行に合わせて整形する
+fn render(value: &serde_json::Value, indent: &str, unit: &str, nl: &str) -> String {
+    use serde::Serialize;
+
+    match value {
+        serde_json::Value::Object(_) | serde_json::Value::Array(_) => {
+            let mut buf = Vec::new();
+            let formatter = serde_json::ser::PrettyFormatter::with_indent(unit.as_bytes());
+            let mut ser = serde_json::Serializer::with_formatter(&mut buf, formatter);
+            let _ = value.serialize(&mut ser);
+            String::from_utf8_lossy(&buf).replace('\n', &format!("{nl}{indent}"))
+        }
+        _ => value.to_string(),
+    }
+}
+
+/// 値の範囲に含まれるコメントを取り除く (文字列の中は残す)
+fn strip_comments(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut ret = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'"' => {
+                let end = string_end(bytes, i);
+                ret.extend_from_slice(&bytes[i..end]);
+                i = end;
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'/') => {
+                while i < bytes.len() && bytes[i] != b'\n' {
+                    i += 1;
+                }
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                i += 2;
+                while i < bytes.len() && !(bytes[i] == b'*' && bytes.get(i + 1) == Some(&b'/')) {
+                    i += 1;
+                }
+                i = (i + 2).min(bytes.len());
+            }
+            // 末尾のカンマは JSON として読めないため取り除く
+            b',' => {
+                let next = bytes[i + 1..]
+                    .iter()
+                    .find(|x| !x.is_ascii_whitespace())
+                    .copied();
+                if !matches!(next, Some(b'}') | Some(b']')) {
+                    ret.push(b',');
+                }
+                i += 1;
+            }
+            c => {
+                ret.push(c);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&ret).into_owned()
+}
+
+/// `"` で始まる文字列の終わり (閉じる `"` の次の位置)
+fn string_end(bytes: &[u8], start: usize) -> usize {
+    let mut i = start + 1;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 2,
+            b'"' => return i + 1,
+            _ => i += 1,
+        }
+    }
+    bytes.len()
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(text: &'a str, pos: usize) -> Self {
+        Self {
+            text,
+            bytes: text.as_bytes(),
+            pos,
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.pos).copied()
+    }
+
+    fn error(&self, message: &str) -> Error {
+        let line = self.text[..self.pos.min(self.text.len())]
+            .lines()
+            .count()
+            .max(1);
+        Error::InvalidFile(format!("{message} (line {line})"))
+    }
+
+    /// 空白とコメントを読み飛ばす
+    fn skip_trivia(&mut self) {
+        loop {
+            match self.peek() {
+                Some(c) if c.is_ascii_whitespace() => self.pos += 1,
+                Some(b'/') if self.bytes.get(self.pos + 1) == Some(&b'/') => {
+                    while self.peek().is_some_and(|x| x != b'\n') {
+                        self.pos += 1;
+                    }
+                }
+                Some(b'/') if self.bytes.get(self.pos + 1) == Some(&b'*') => {
+                    match self.text[self.pos + 2..].find("*/") {
+                        Some(x) => self.pos += x + 4,
+                        None => self.pos = self.bytes.len(),
+                    }
+                }
+                _ => return,
+            }
+        }
+    }
+
+    fn expect(&mut self, c: u8) -> Result<()> {
+        self.skip_trivia();
+        if self.peek() != Some(c) {
+            return Err(self.error(&format!("Expected '{}'", c as char)));
+        }
+        self.pos += 1;
+        Ok(())
+    }
+
+    fn string(&mut self) -> Result<String> {
+        let start = self.pos;
+        let end = string_end(self.bytes, start);
+        if self.bytes.get(end - 1) != Some(&b'"') || end == start + 1 {
+            return Err(self.error("Unterminated string"));
+        }
+        self.pos = end;
+        serde_json::from_str(&self.text[start..end]).map_err(|_| self.error("Invalid string"))
+    }
+
+    /// 値を読み飛ばす
+    fn value(&mut self) -> Result<()> {
+        self.skip_trivia();
+        match self.peek() {
+            Some(b'{') => self.object().map(|_| ()),
+            Some(b'[') => self.array().map(|_| ()),
+            Some(b'"') => self.string().map(|_| ()),
+            Some(_) => {
+                let start = self.pos;
+                while self
+                    .peek()
+                    .is_some_and(|x| x.is_ascii_alphanumeric() || matches!(x, b'-' | b'+' | b'.'))
+                {
+                    self.pos += 1;
+                }
+                if start == self.pos {
+                    return Err(self.error("Unexpected character"));
+                }
+                Ok(())
+            }
+            None => Err(self.error("Unexpected end of file")),
+        }
+    }
+
+    /// メンバーと閉じる `}` の位置 (読み終わると `}` の次に進む)
+    fn object(&mut self) -> Result<(Vec<(String, Member)>, usize)> {
+        self.expect(b'{')?;
+        let mut members = Vec::new();
+        loop {
+            self.skip_trivia();
+            match self.peek() {
+                Some(b'}') => break,
+                Some(b'"') => {
+                    let key_pos = self.pos;
+                    let key = self.string()?;
+                    self.expect(b':')?;
+                    self.skip_trivia();
+                    let start = self.pos;
+                    self.value()?;
+                    members.push((
+                        key,
+                        Member {
+                            key: key_pos,
+                            value: (start, self.pos),
+                        },
+                    ));
+                    self.skip_trivia();
+                    if self.peek() == Some(b',') {
+                        self.pos += 1;
+                    }
+                }
+                _ => return Err(self.error("Expected a key")),
+            }
+        }
+        let close = self.pos;
+        self.pos += 1;
+        Ok((members, close))
+    }
+
+    /// 要素の範囲 (読み終わると `]` の次に進む)
+    fn array(&mut self) -> Result<Vec<(usize, usize)>> {
+        self.expect(b'[')?;
+        let mut items = Vec::new();
+        loop {
+            self.skip_trivia();
+            if self.peek() == Some(b']') {
+                break;
+            }
+            let start = self.pos;
+            self.value()?;
+            items.push((start, self.pos));
+            self.skip_trivia();
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(b']') => (),
+                _ => return Err(self.error("Expected ',' or ']'")),
+            }
+        }
+        self.pos += 1;
+        Ok(items)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn set_at(text: &str, pointer: &str, value: Option<serde_json::Value>) -> String {
+        set(text, &parse_pointer(pointer).unwrap(), value.as_ref()).unwrap()
+    }
+
+    fn get_at(text: &str, pointer: &str) -> Option<serde_json::Value> {
+        get(text, &parse_pointer(pointer).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn keeps_comments_and_trailing_commas() {
+        let text = r#"{
+    // 先頭のコメント
+    "a": 1, /* 行内のコメント */
+    "b": true,
+    "list": [1, 2, /* 途中 */ 3,],
+}
+"#;
+        assert_eq!(get_at(text, "/list"), Some(json!([1, 2, 3])));
+        assert_eq!(
+            set_at(text, "/b", Some(json!(false))),
+            text.replace(r#""b": true"#, r#""b": false"#)
+        );
+        assert_eq!(
+            set_at(text, "/c", Some(json!("x"))),
+            text.replace("3,],\n}", "3,],\n    \"c\": \"x\",\n}")
+        );
+    }
+
+    #[test]
+    fn inserts_into_empty_object() {
+        assert_eq!(set_at("{}", "/a", Some(json!(1))), "{\n    \"a\": 1\n}");
+        assert_eq!(
+            set_at(
+                "{\n  \"profiles\": {}\n}",
+                "/profiles/defaults/font",
+                Some(json!(1))
+            ),
+            "{\n  \"profiles\": {\n    \"defaults\": {\n      \"font\": 1\n    }\n  }\n}"
+        );
+    }
+
+    #[test]
+    fn inserts_nested_objects_after_last_member() {
+        let text = "{\n    \"a\": 1\n}\n";
+        assert_eq!(
+            set_at(text, "/b/c", Some(json!(2))),
+            "{\n    \"a\": 1,\n    \"b\": {\n        \"c\": 2\n    }\n}\n"
+        );
+    }
+
+    #[test]
+    fn deletes_members() {
+        let text = "{\n    \"a\": 1,\n    \"b\": 2,\n    \"c\": 3\n}\n";
+        assert_eq!(
+            set_at(text, "/a", None),
+            "{\n    \"b\": 2,\n    \"c\": 3\n}\n"
+        );
+        assert_eq!(
+            set_at(text, "/b", None),
+            "{\n    \"a\": 1,\n    \"c\": 3\n}\n"
+        );
+        assert_eq!(
+            set_at(text, "/c", None),
+            "{\n    \"a\": 1,\n    \"b\": 2\n}\n"
+        );
+        // 存在しないメンバーの削除は何もしない
+        assert_eq!(set_at(text, "/d", None), text);
+    }
+
+    #[test]
+    fn keeps_crlf() {
+        let text = "{\r\n    \"a\": 1,\r\n    \"b\": 2\r\n}\r\n";
+        assert_eq!(
+            set_at(text, "/a", Some(json!(3))),
+            "{\r\n    \"a\": 3,\r\n    \"b\": 2\r\n}\r\n"
+        );
+        assert_eq!(
+            set_at(text, "/c", Some(json!({ "d": true }))),
+            "{\r\n    \"a\": 1,\r\n    \"b\": 2,\r\n    \"c\": {\r\n        \"d\": true\r\n    }\r\n}\r\n"
+        );
+        assert_eq!(set_at(text, "/a", None), "{\r\n    \"b\": 2\r\n}\r\n");
+    }
+}
@@ -0,0 +1,283 @@
+mod ini;
+mod json;
+
+use super::{Error, Result, Tweak, TweakInfo};
+use crate::watch::ValueChanged;
+use crate::win::reg::{self, Environment, ProcessEnvironment};
+use std::path::{Path, PathBuf};
+
+/// 設定ファイルを読み書きする
+/// パスの `%NAME%` は `Environment` の環境変数で展開する
+pub trait FileAccess: Environment + Send + Sync {
+    /// ファイルが無い場合は `None`
+    fn read(&self, path: &Path) -> Result<Option<Vec<u8>>>;
+    fn write(&self, path: &Path, data: &[u8]) -> Result<()>;
+}
+
+/// 実際のファイルと環境変数を使う
+pub struct StdFiles;
+
+impl FileAccess for StdFiles {
+    fn read(&self, path: &Path) -> Result<Option<Vec<u8>>> {
+        match std::fs::read(path) {
+            Ok(v) => Ok(Some(v)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(Error::Io(e)),
+        }
+    }
+
+    /// 途中で失敗しても元のファイルが壊れないよう、同じフォルダーの一時ファイルに書いてから置き換える
+    fn write(&self, path: &Path, data: &[u8]) -> Result<()> {
+        use std::io::Write;
+
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let mut temp = path.as_os_str().to_owned();
+        temp.push(".win11-tweaks.tmp");
+        let temp = PathBuf::from(temp);
+
+        let ret = std::fs::File::create(&temp)
+            .and_then(|mut file| {
+                file.write_all(data)?;
+                file.sync_all()
+            })
+            .and_then(|_| std::fs::rename(&temp, path));
+        if let Err(e) = ret {
+            let _ = std::fs::remove_file(&temp);
+            return Err(Error::Io(e));
+        }
+        Ok(())
+    }
+}
+
+impl Environment for StdFiles {
+    fn var(&self, name: &str) -> Option<String> {
+        ProcessEnvironment.var(name)
+    }
+}
+
+/// ファイルの中の値の場所
+#[derive(Debug, Clone)]
+pub enum FileLocation {
+    /// JSON (コメントと末尾のカンマを許す) の JSON Pointer
+    Json(String),
+    /// INI のセクションとキー (`section` が空の場合は最初のセクションより前)
+    Ini { section: String, key: String },
+}
+
+/// 設定ファイルの値
+/// JSON の候補の値は JSON として読める場合はその値、読めない場合は文字列として扱う
+pub struct FileTweak<F = StdFiles> {
+    info: TweakInfo,
+    /// `%LOCALAPPDATA%` などの環境変数を含むパス
+    path: String,
+    location: FileLocation,
+    files: F,
+}
+
+impl FileTweak {
+    pub fn new(info: TweakInfo, path: impl Into<String>, location: FileLocation) -> Self {
+        Self::with_files(info, path, location, StdFiles)
+    }
+}
+
+impl<F: FileAccess> FileTweak<F> {
+    pub fn with_files(
+        info: TweakInfo,
+        path: impl Into<String>,
+        location: FileLocation,
+        files: F,
+    ) -> Self {
+        Self {
+            info,
+            path: path.into(),
+            location,
+            files,
+        }
+    }
+
+    fn path(&self) -> Result<PathBuf> {
+        let (path, unset) = reg::expand_checked(&self.path, &self.files);
+        match unset.first() {
+            Some(name) => Err(Error::UnexpectedState(format!("%{name}% is not set"))),
+            None => Ok(PathBuf::from(path)),
+        }
+    }
+
+    fn read(&self) -> Result<Option<String>> {
+        let path = self.path()?;
+        let Some(data) = self.files.read(&path)? else {
+            return Ok(None);
+        };
+        let (text, _) = TextEncoding::decode(&data)?;
+
+        match &self.location {
+            FileLocation::Json(pointer) => {
+                let ret = json::get(&text, &json::parse_pointer(pointer)?)?;
+                Ok(ret.map(|x| match x {
+                    serde_json::Value::String(s) => s,
+                    v => v.to_string(),
+                }))
+            }
+            FileLocation::Ini { section, key } => Ok(ini::get(&text, section, key)),
+        }
+    }
+
+    fn write(&self, value: Option<&str>) -> Result<()> {
+        let path = self.path()?;
+        let (text, encoding) = match self.files.read(&path)? {
+            Some(data) => TextEncoding::decode(&data)?,
+            None if value.is_none() => return Ok(()),
+            None => (String::new(), TextEncoding::Utf8),
+        };
+
+        let ret = match &self.location {
+            FileLocation::Json(pointer) => {
+                let text = if text.trim().is_empty() { "{}" } else { &text };
+                let value = value.map(|x| {
+                    serde_json::from_str(x).unwrap_or_else(|_| serde_json::Value::String(x.into()))
+                });
+                json::set(text, &json::parse_pointer(pointer)?, value.as_ref())?
+            }
+            FileLocation::Ini { section, key } => ini::set(&text, section, key, value),
+        };
+        if ret == text {
+            return Ok(());
+        }
+        self.files.write(&path, &encoding.encode(&ret))
+    }
+}
+
+impl<F: FileAccess> Tweak for FileTweak<F> {
+    fn info(&self) -> &TweakInfo {
+        &self.info
+    }
+
+    fn read_state(&self) -> ValueChanged {
+        self.info.status(self.read())
+    }
+
+    fn apply(&self, candidate: Option<&str>) -> Result<()> {
+        let candidate = self.info.check(candidate)?;
+        match candidate.absent {
+            true => self.write(None),
+            false => self.write(Some(&candidate.value)),
+        }
+    }
+
+    fn describe(&self) -> String {
+        match &self.location {
+            FileLocation::Json(pointer) => format!("{} ({pointer})", self.path),
+            FileLocation::Ini { section, key } => format!("{} ([{section}] {key})", self.path),
+        }
+    }
+}
+
+/// 書き戻すときに元の文字コードと BOM を保つ
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum TextEncoding {
+    Utf8,
+    Utf8Bom,
+    /// desktop.ini などで使われる
+    Utf16LeBom,
+}
+
+impl TextEncoding {
+    fn decode(data: &[u8]) -> Result<(String, Self)> {
+        if let Some(rest) = data.strip_prefix(&[0xFF, 0xFE]) {
+            let units = rest
+                .chunks_exact(2)
+                .map(|x| u16::from_le_bytes([x[0], x[1]]))
+                .collect::<Vec<u16>>();
+            let text = String::from_utf16(&units)
+                .map_err(|_| Error::InvalidFile("Invalid UTF-16 text".into()))?;
+            return Ok((text, Self::Utf16LeBom));
+        }
+
+        let (rest, encoding) = match data.strip_prefix(&[0xEF, 0xBB, 0xBF]) {
+            Some(rest) => (rest, Self::Utf8Bom),
+            None => (data, Self::Utf8),
+        };
+        let text = String::from_utf8(rest.to_vec())
+            .map_err(|_| Error::InvalidFile("Invalid UTF-8 text".into()))?;
+        Ok((text, encoding))
+    }
+
+    fn encode(&self, text: &str) -> Vec<u8> {
+        match self {
+            Self::Utf8 => text.as_bytes().to_vec(),
+            Self::Utf8Bom => [&[0xEF, 0xBB, 0xBF], text.as_bytes()].concat(),
+            Self::Utf16LeBom => {
+                let mut ret = vec![0xFF, 0xFE];
+                ret.extend(text.encode_utf16().flat_map(|x| x.to_le_bytes()));
+                ret
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    /// ファイルを持たず、固定の環境変数だけを使う
+    struct NoFiles(HashMap<String, String>);
+
+    impl Environment for NoFiles {
+        fn var(&self, name: &str) -> Option<String> {
+            self.0.var(name)
+        }
+    }
+
+    impl FileAccess for NoFiles {
+        fn read(&self, _: &Path) -> Result<Option<Vec<u8>>> {
+            Ok(None)
+        }
+
+        fn write(&self, _: &Path, _: &[u8]) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    fn tweak(path: &str) -> FileTweak<NoFiles> {
+        let env = HashMap::from([("LocalAppData".to_string(), r"C:\Local".to_string())]);
+        FileTweak::with_files(
+            TweakInfo::new(1, "", "", Vec::new()),
+            path,
+            FileLocation::Json("/theme".into()),
+            NoFiles(env),
+        )
+    }
+
+    #[test]
+    fn path_expands_variables() {
+        let path = tweak(r"%LOCALAPPDATA%\100%\settings.json").path().unwrap();
+        assert_eq!(path, PathBuf::from(r"C:\Local\100%\settings.json"));
+    }
+
+    #[test]
+    fn path_rejects_unset_variables() {
+        assert!(matches!(
+            tweak(r"%APPDATA%\settings.json").path(),
+            Err(Error::UnexpectedState(_))
+        ));
+    }
+
+    #[test]
+    fn std_files_replaces_existing_file() {
+        let dir = std::env::temp_dir().join(format!("win11-tweaks-{}", std::process::id()));
+        let path = dir.join("settings.json");
+        StdFiles.write(&path, b"old").unwrap();
+        StdFiles.write(&path, b"new").unwrap();
+
+        assert_eq!(StdFiles.read(&path).unwrap().as_deref(), Some(&b"new"[..]));
+        let names = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|x| x.unwrap().file_name())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["settings.json"]);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
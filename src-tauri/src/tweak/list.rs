@@ -0,0 +1,382 @@
+use super::{Error, Result, Tweak, TweakInfo};
+use crate::command::OptionCandidate;
+use crate::watch::ValueChanged;
+use crate::win;
+use crate::win::reg::{DataType, ProcessEnvironment, RegDef, Registry, Root, Value};
+
+/// 環境変数のキー (変更したら WM_SETTINGCHANGE で通知する)
+const USER_ENVIRONMENT: &str = "Environment";
+const SYSTEM_ENVIRONMENT: &str = r"SYSTEM\CurrentControlSet\Control\Session Manager\Environment";
+
+/// 項目を追加する位置
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Position {
+    First,
+    Last,
+}
+
+/// リストに対する操作
+/// 項目は前後の空白を除いて大文字小文字を区別せずに比べる (`%VAR%` は展開しない)
+#[derive(Debug, Clone, PartialEq)]
+pub enum ListOperation {
+    /// 無ければ `position` に追加する (既にある場合は位置を変えない)
+    EnsurePresent {
+        item: String,
+        position: Position,
+    },
+    EnsureAbsent {
+        item: String,
+    },
+    /// 含まれている項目をこの順で先頭に並べる (無い項目は追加しない)
+    Reorder {
+        items: Vec<String>,
+    },
+}
+
+impl ListOperation {
+    pub fn present(item: impl Into<String>, position: Position) -> Self {
+        Self::EnsurePresent {
+            item: item.into(),
+            position,
+        }
+    }
+
+    pub fn absent(item: impl Into<String>) -> Self {
+        Self::EnsureAbsent { item: item.into() }
+    }
+
+    pub fn reorder(items: &[&str]) -> Self {
+        Self::Reorder {
+            items: items.iter().map(|x| x.to_string()).collect(),
+        }
+    }
+
+    /// 現在のリストがこの操作を満たしているか
+    pub fn is_satisfied(&self, list: &[String]) -> bool {
+        match self {
+            Self::EnsurePresent { item, .. } => list.iter().any(|x| same_item(x, item)),
+            Self::EnsureAbsent { item } => !list.iter().any(|x| same_item(x, item)),
+            Self::Reorder { items } => {
+                let order = reordered(list, items);
+                order.len() == list.len() && order.iter().zip(list).all(|(a, b)| a == b)
+            }
+        }
+    }
+
+    /// 操作を適用したリストを返す
+    pub fn apply(&self, list: &[String]) -> Vec<String> {
+        match self {
+            Self::EnsurePresent { item, .. } if list.iter().any(|x| same_item(x, item)) => {
+                list.to_vec()
+            }
+            Self::EnsurePresent { item, position } => {
+                let mut ret = list.to_vec();
+                match position {
+                    Position::First => ret.insert(0, item.clone()),
+                    // 末尾の空の項目 (`a;b;` の最後の `;`) はそのまま末尾に残す
+                    Position::Last => {
+                        let at = ret
+                            .iter()
+                            .rposition(|x| !x.trim().is_empty())
+                            .map_or(0, |i| i + 1);
+                        ret.insert(at, item.clone())
+                    }
+                }
+                ret
+            }
+            Self::EnsureAbsent { item } => list
+                .iter()
+                .filter(|x| !same_item(x, item))
+                .cloned()
+                .collect(),
+            Self::Reorder { items } => reordered(list, items),
+        }
+    }
+
+    /// 操作の対象の項目
+    fn items(&self) -> &[String] {
+        match self {
+            Self::EnsurePresent { item, .. } | Self::EnsureAbsent { item } => {
+                std::slice::from_ref(item)
+            }
+            Self::Reorder { items } => items,
+        }
+    }
+}
+
+fn same_item(a: &str, b: &str) -> bool {
+    a.trim().to_lowercase() == b.trim().to_lowercase()
+}
+
+fn reordered(list: &[String], items: &[String]) -> Vec<String> {
+    let mut ret: Vec<String> = items
+        .iter()
+        .filter_map(|item| list.iter().find(|x| same_item(x, item)).cloned())
+        .collect();
+    ret.extend(
+        list.iter()
+            .filter(|x| !items.iter().any(|item| same_item(x, item)))
+            .cloned(),
+    );
+    ret
+}
+
+/// `items` に一致する項目の重複を取り除く (先に出てきた方を残す)
+/// それ以外の項目は、空の項目や重複も含めてそのまま残す
+fn dedupe(list: &[String], items: &[String]) -> Vec<String> {
+    let mut ret: Vec<String> = Vec::with_capacity(list.len());
+    for x in list {
+        let touched = items.iter().any(|item| same_item(x, item));
+        if !touched || !ret.iter().any(|y| same_item(x, y)) {
+            ret.push(x.clone());
+        }
+    }
+    ret
+}
+
+/// 値をリストとして読む (`;` 区切りの文字列は空なら空のリスト)
+fn split(value: Value) -> Result<Vec<String>> {
+    match value {
+        Value::MultiString(v) => Ok(v),
+        Value::String(v) | Value::ExpandString(v) if v.is_empty() => Ok(Vec::new()),
+        Value::String(v) | Value::ExpandString(v) => Ok(v.split(';').map(str::to_string).collect()),
+        v => Err(Error::UnexpectedState(format!(
+            "{} is not a list",
+            v.data_type().str()
+        ))),
+    }
+}
+
+/// リストを `data_type` の値にする
+fn join(list: Vec<String>, data_type: DataType) -> Value {
+    match data_type {
+        DataType::MultiString => Value::MultiString(list),
+        DataType::ExpandString => Value::ExpandString(list.join(";")),
+        _ => Value::String(list.join(";")),
+    }
+}
+
+/// REG_MULTI_SZ の値や `;` 区切りの文字列 (PATH など) を 1 項目ずつ扱う設定項目
+/// 候補ごとに操作の組を持ち、すべての操作を満たす最初の候補を現在の状態とする
+pub struct ListTweak {
+    info: TweakInfo,
+    def: RegDef,
+    /// 候補の値と、その候補を適用するときの操作
+    operations: Vec<(String, Vec<ListOperation>)>,
+}
+
+impl ListTweak {
+    pub fn new(info: TweakInfo, def: RegDef) -> Self {
+        Self {
+            info,
+            def,
+            operations: Vec::new(),
+        }
+    }
+
+    /// 候補 `candidate` を選んだときの操作
+    pub fn on(mut self, candidate: impl Into<String>, operations: Vec<ListOperation>) -> Self {
+        self.operations.push((candidate.into(), operations));
+        self
+    }
+
+    fn operations(&self, candidate: &str) -> &[ListOperation] {
+        self.operations
+            .iter()
+            .find(|(v, _)| v == candidate)
+            .map(|(_, ops)| ops.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// リストと、書き戻すときの型 (値が存在しない場合は `None`)
+    /// 定義と異なる型 (REG_SZ の Path など) でも読み取り、その型のまま書き戻す
+    fn read(&self) -> Result<Option<(Vec<String>, DataType)>> {
+        match Registry::from_def(&self.def).find_any_value()? {
+            None => Ok(None),
+            Some(v) => {
+                let data_type = v.data_type();
+                Ok(Some((split(v)?, data_type)))
+            }
+        }
+    }
+
+    fn write(&self, list: Vec<String>, data_type: DataType) -> Result<()> {
+        let value = join(list, data_type);
+        Registry::from_def(&self.def).write_value(&value)?;
+        self.notify();
+        Ok(())
+    }
+
+    /// 環境変数を変えた場合は、開いているプログラムが読み直せるように通知する
+    fn notify(&self) {
+        if self.is_environment() {
+            win::broadcast_setting_change("Environment");
+        }
+    }
+
+    fn is_environment(&self) -> bool {
+        let sub_key = self.def.sub_key.trim_matches('\\');
+        match self.def.root {
+            Root::CurrentUser | Root::User => sub_key.eq_ignore_ascii_case(USER_ENVIRONMENT),
+            // HKU\<SID>\Environment
+            Root::Users => sub_key
+                .split_once('\\')
+                .is_some_and(|(_, x)| x.eq_ignore_ascii_case(USER_ENVIRONMENT)),
+            Root::LocalMachine => sub_key.eq_ignore_ascii_case(SYSTEM_ENVIRONMENT),
+        }
+    }
+
+    /// 操作をすべて満たす最初の候補
+    fn detect(&self, list: &[String]) -> Option<&OptionCandidate> {
+        self.info.values.iter().find(|x| {
+            !x.absent
+                && self
+                    .operations(&x.value)
+                    .iter()
+                    .all(|op| op.is_satisfied(list))
+        })
+    }
+}
+
+impl Tweak for ListTweak {
+    fn info(&self) -> &TweakInfo {
+        &self.info
+    }
+
+    fn read_state(&self) -> ValueChanged {
+        let (list, data_type) = match self.read() {
+            Ok(Some((v, data_type))) => (Some(v), data_type),
+            Ok(None) => (None, self.def.data_type),
+            Err(e) => return self.info.status(Err(e)),
+        };
+        let candidate = match &list {
+            Some(v) => self.detect(v),
+            None => self
+                .info
+                .values
+                .iter()
+                .find(|x| x.absent)
+                .or_else(|| self.detect(&[])),
+        };
+        // MULTI_SZ の各項目は展開されないため、展開するのは REG_EXPAND_SZ だけ
+        let raw = match (&list, data_type) {
+            (Some(v), DataType::ExpandString) => Some(Value::ExpandString(v.join(";"))),
+            _ => None,
+        };
+        ValueChanged {
+            value: list.map(|x| x.join(";")),
+            candidate: candidate.map(|x| x.value.clone()),
+            description: candidate.map(|x| x.description.clone()),
+            ..ValueChanged::new(self.info.id)
+        }
+        .with_expansion(raw.as_ref(), &ProcessEnvironment)
+    }
+
+    fn apply(&self, candidate: Option<&str>) -> Result<()> {
+        let candidate = self.info.check(candidate)?;
+        if candidate.absent {
+            Registry::from_def(&self.def).delete_value()?;
+            self.notify();
+            return Ok(());
+        }
+
+        let (current, data_type) = self
+            .read()?
+            .unwrap_or_else(|| (Vec::new(), self.def.data_type));
+        let operations = self.operations(&candidate.value);
+        let list = operations
+            .iter()
+            .fold(current.clone(), |list, op| op.apply(&list));
+        let touched = operations
+            .iter()
+            .flat_map(|x| x.items())
+            .cloned()
+            .collect::<Vec<_>>();
+        let list = dedupe(&list, &touched);
+        if list == current {
+            return Ok(());
+        }
+        self.write(list, data_type)
+    }
+
+    fn describe(&self) -> String {
+        self.def.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn list(text: &str) -> Vec<String> {
+        split(Value::String(text.into())).unwrap()
+    }
+
+    #[test]
+    fn present_keeps_existing_position() {
+        let op = ListOperation::present(r"%USERPROFILE%\bin", Position::First);
+        let current = list(r"C:\a;%userprofile%\BIN ;C:\b");
+        assert!(op.is_satisfied(&current));
+        assert_eq!(op.apply(&current), current);
+    }
+
+    #[test]
+    fn present_last_stays_before_trailing_separator() {
+        let op = ListOperation::present(r"C:\c", Position::Last);
+        let current = list(r"C:\a;C:\b;");
+        assert_eq!(op.apply(&current), list(r"C:\a;C:\b;C:\c;"));
+        assert_eq!(op.apply(&[]), list(r"C:\c"));
+    }
+
+    #[test]
+    fn absent_removes_every_match() {
+        let op = ListOperation::absent(r"C:\b");
+        assert_eq!(op.apply(&list(r"C:\a;c:\B;C:\b ;C:\c")), list(r"C:\a;C:\c"));
+    }
+
+    #[test]
+    fn reorder_moves_only_existing_items() {
+        let op = ListOperation::reorder(&[r"C:\c", r"C:\x", r"C:\a"]);
+        let current = list(r"C:\a;C:\b;C:\c");
+        assert!(!op.is_satisfied(&current));
+        let ret = op.apply(&current);
+        assert_eq!(ret, list(r"C:\c;C:\a;C:\b"));
+        assert!(op.is_satisfied(&ret));
+    }
+
+    #[test]
+    fn dedupe_only_touches_given_items() {
+        let current = list(r"C:\a;;C:\b;c:\A;C:\b;C:\a");
+        let ret = dedupe(&current, &[r"C:\a".to_string()]);
+        assert_eq!(ret, list(r"C:\a;;C:\b;C:\b"));
+    }
+
+    #[test]
+    fn split_and_join_keep_type() {
+        assert!(split(Value::ExpandString(String::new()))
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            split(Value::MultiString(vec!["a;b".into()])).unwrap(),
+            vec!["a;b".to_string()]
+        );
+        assert!(matches!(
+            split(Value::DWord(1)),
+            Err(Error::UnexpectedState(_))
+        ));
+
+        let items = list(r"%SystemRoot%;C:\a");
+        assert!(matches!(
+            join(items.clone(), DataType::String),
+            Value::String(v) if v == r"%SystemRoot%;C:\a"
+        ));
+        assert!(matches!(
+            join(items.clone(), DataType::ExpandString),
+            Value::ExpandString(v) if v == r"%SystemRoot%;C:\a"
+        ));
+        assert!(matches!(
+            join(items.clone(), DataType::MultiString),
+            Value::MultiString(v) if v == items
+        ));
+    }
+}
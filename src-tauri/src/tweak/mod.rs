@@ -0,0 +1,310 @@
+mod appx;
+mod error;
+mod file;
+mod list;
+mod registry;
+mod scheduled_task;
+mod service;
+mod stuck_rects;
+mod system_parameter;
+
+pub use appx::{AppxProvisioning, ProvisionedAppTweak, WinAppxProvisioning};
+pub use error::{Error, Result};
+pub use file::{FileAccess, FileLocation, FileTweak, StdFiles};
+pub use list::{ListOperation, ListTweak, Position};
+pub use registry::RegistryTweak;
+pub use scheduled_task::{ScheduledTaskTweak, TaskScheduler, WinTaskScheduler};
+pub use service::{ServiceManager, ServiceTweak, WinServices};
+pub use stuck_rects::{Edge, StuckRects, StuckRectsField, StuckRectsTweak};
+pub use system_parameter::{SystemParameterTweak, SystemParameters, WinSystemParameters};
+
+use crate::command::{OptionCandidate, RegistryEditOption};
+use crate::domain::ValidationError;
+use crate::watch::ValueChanged;
+use crate::win::reg::{DataType, RegDef};
+use windows::Win32::UI::WindowsAndMessaging::{
+    SPI_GETCLIENTAREAANIMATION, SPI_SETCLIENTAREAANIMATION,
+};
+
+/// 種類によらない設定項目の操作
+/// 状態の検出、適用、表示を同じ形で扱う
+pub trait Tweak: Send + Sync {
+    fn info(&self) -> &TweakInfo;
+
+    fn id(&self) -> u64 {
+        self.info().id
+    }
+
+    fn label(&self) -> &str {
+        &self.info().label
+    }
+
+    fn category(&self) -> &str {
+        &self.info().category
+    }
+
+    /// 分類を含めた表示名
+    fn title(&self) -> String {
+        if self.category().is_empty() {
+            self.label().to_string()
+        } else {
+            format!("{} - {}", self.category(), self.label())
+        }
+    }
+
+    fn candidates(&self) -> &[OptionCandidate] {
+        &self.info().values
+    }
+
+    /// 初期状態に戻すときの選択 (`None` は初期状態が分からない項目、`Some(None)` は値の削除)
+    fn default_selection(&self) -> Option<Option<String>> {
+        self.info().windows_default.clone()
+    }
+
+    /// レジストリの値を扱う項目の定義 (計画、監査、スクリプト出力で使う)
+    fn registry(&self) -> Option<&RegistryEditOption> {
+        None
+    }
+
+    /// 現在の状態 (読み取れない場合は `error` に理由が入る)
+    fn read_state(&self) -> ValueChanged;
+
+    /// 候補の値を適用する (`None` は値が存在しない候補)
+    fn apply(&self, candidate: Option<&str>) -> Result<()>;
+
+    /// 操作の対象 (レジストリのパスやサービス名など。画面やログに表示する)
+    fn describe(&self) -> String;
+}
+
+/// 設定項目に共通する情報
+pub struct TweakInfo {
+    pub id: u64,
+    pub label: String,
+    pub category: String,
+    pub values: Vec<OptionCandidate>,
+    /// Windows の初期状態の候補 (`None` は分からない、`Some(None)` は値が存在しない候補)
+    pub windows_default: Option<Option<String>>,
+    /// 項目を指定したときだけ初期状態に戻す (カテゴリやすべての項目をまとめて戻すときは対象にしない)
+    pub reset_individually: bool,
+}
+
+impl TweakInfo {
+    pub fn new(
+        id: u64,
+        category: impl Into<String>,
+        label: impl Into<String>,
+        values: Vec<OptionCandidate>,
+    ) -> Self {
+        Self {
+            id,
+            label: label.into(),
+            category: category.into(),
+            values,
+            windows_default: None,
+            reset_individually: false,
+        }
+    }
+
+    pub fn with_default(mut self, value: Option<&str>) -> Self {
+        self.windows_default = Some(value.map(|x| x.to_string()));
+        self
+    }
+
+    pub fn reset_individually(mut self) -> Self {
+        self.reset_individually = true;
+        self
+    }
+
+    /// 読み取った状態 (`None` は存在しない) を一致する候補と合わせて返す
+    fn status(&self, state: Result<Option<String>>) -> ValueChanged {
+        match state {
+            Ok(value) => {
+                let candidate = self.values.iter().find(|x| match value.as_deref() {
+                    Some(v) => !x.absent && x.value.eq_ignore_ascii_case(v),
+                    None => x.absent,
+                });
+                ValueChanged {
+                    value,
+                    candidate: candidate.map(|x| x.value.clone()),
+                    description: candidate.map(|x| x.description.clone()),
+                    ..ValueChanged::new(self.id)
+                }
+            }
+            Err(e) => ValueChanged::error(self.id, e),
+        }
+    }
+
+    /// 適用する値が候補に含まれているかを確認する
+    pub fn check(&self, candidate: Option<&str>) -> Result<&OptionCandidate> {
+        match candidate {
+            Some(v) => self
+                .values
+                .iter()
+                .find(|x| !x.absent && x.value == v)
+                .ok_or_else(|| Error::UnknownCandidate(self.id, v.into())),
+            None => self
+                .values
+                .iter()
+                .find(|x| x.absent)
+                .ok_or(Error::Validation(
+                    self.id,
+                    ValidationError::AbsentNotAllowed,
+                )),
+        }
+    }
+}
+
+/// Windows ターミナル (ストア版) の設定ファイル
+const TERMINAL_SETTINGS: &str =
+    r"%LOCALAPPDATA%\Packages\Microsoft.WindowsTerminal_8wekyb3d8bbwe\LocalState\settings.json";
+
+/// レジストリ以外の組み込みの設定項目 (ID は `first_id` から順に振る)
+pub fn default_tweaks(first_id: u64) -> Vec<Box<dyn Tweak>> {
+    use OptionCandidate as O;
+
+    let mut id = first_id;
+    let mut next_id = || {
+        let ret = id;
+        id += 1;
+        ret
+    };
+
+    vec![
+        Box::new(SystemParameterTweak::new(
+            TweakInfo::new(
+                next_id(),
+                "視覚効果",
+                "アニメーション効果",
+                vec![O::new("0", "オフ"), O::new("1", "オン")],
+            )
+            .with_default(Some("1")),
+            SPI_GETCLIENTAREAANIMATION.0,
+            SPI_SETCLIENTAREAANIMATION.0,
+        )),
+        Box::new(ServiceTweak::new(
+            TweakInfo::new(
+                next_id(),
+                "サービス",
+                "SysMain (要管理者権限)",
+                vec![
+                    O::new("Automatic", "自動"),
+                    O::new("Manual", "手動"),
+                    O::new("Disabled", "無効"),
+                ],
+            )
+            .with_default(Some("Automatic"))
+            // 利用者が意図して無効にしていることが多く、管理者権限も必要なため
+            .reset_individually(),
+            "SysMain",
+        )),
+        Box::new(ScheduledTaskTweak::new(
+            TweakInfo::new(
+                next_id(),
+                "タスク",
+                "互換性の診断データの収集 (要管理者権限)",
+                vec![O::new("0", "無効"), O::new("1", "有効")],
+            )
+            .with_default(Some("1")),
+            r"\Microsoft\Windows\Application Experience\",
+            "Microsoft Compatibility Appraiser",
+        )),
+        // 削除したパッケージは追加し直せないため、初期状態に戻す対象にしない
+        Box::new(ProvisionedAppTweak::new(
+            TweakInfo::new(
+                next_id(),
+                "アプリ",
+                "新しいユーザーへのニュースアプリの追加 (要管理者権限)",
+                vec![O::new("0", "追加しない"), O::new("1", "追加する")],
+            ),
+            "Microsoft.BingNews",
+        )),
+        Box::new(FileTweak::new(
+            TweakInfo::new(
+                next_id(),
+                "ターミナル",
+                "選択したテキストをコピー",
+                vec![
+                    O::new("false", "コピーしない"),
+                    O::new("true", "コピーする"),
+                ],
+            )
+            .with_default(Some("false")),
+            TERMINAL_SETTINGS,
+            FileLocation::Json("/copyOnSelect".into()),
+        )),
+        Box::new(FileTweak::new(
+            TweakInfo::new(
+                next_id(),
+                "ターミナル",
+                "テーマ",
+                vec![
+                    O::absent("Windows の設定に合わせる"),
+                    O::new("dark", "ダーク"),
+                    O::new("light", "ライト"),
+                ],
+            )
+            .with_default(None),
+            TERMINAL_SETTINGS,
+            FileLocation::Json("/theme".into()),
+        )),
+        Box::new(
+            ListTweak::new(
+                TweakInfo::new(
+                    next_id(),
+                    "環境変数",
+                    r"PATH に %USERPROFILE%\bin を含める",
+                    vec![O::new("0", "含めない"), O::new("1", "先頭に含める")],
+                )
+                .with_default(Some("0"))
+                // 利用者が自分で追加したパスも取り除くことになるため
+                .reset_individually(),
+                RegDef::user("Environment", "Path", DataType::ExpandString),
+            )
+            .on("0", vec![ListOperation::absent(r"%USERPROFILE%\bin")])
+            .on(
+                "1",
+                vec![
+                    ListOperation::present(r"%USERPROFILE%\bin", Position::First),
+                    ListOperation::reorder(&[r"%USERPROFILE%\bin"]),
+                ],
+            ),
+        ),
+        Box::new(StuckRectsTweak::new(
+            TweakInfo::new(
+                next_id(),
+                "タスクバー",
+                "自動的に隠す (エクスプローラの再起動後に反映)",
+                vec![O::new("0", "隠さない"), O::new("1", "隠す")],
+            )
+            .with_default(Some("0")),
+            StuckRectsField::AutoHide,
+        )),
+        Box::new(StuckRectsTweak::new(
+            TweakInfo::new(
+                next_id(),
+                "タスクバー",
+                "画面上の位置を下に戻す (エクスプローラの再起動後に反映)",
+                // Windows 11 のタスクバーは下以外の位置に対応していない
+                vec![O::new("3", "下")],
+            )
+            .with_default(Some("3")),
+            StuckRectsField::Edge,
+        )),
+    ]
+}
+
+/// レジストリの項目の後に、レジストリ以外の組み込みの項目を続けた一覧
+/// 画面とコマンドラインはこの一覧から項目を探す
+pub fn with_registry(options: Vec<RegistryEditOption>) -> Vec<Box<dyn Tweak>> {
+    let next = options.iter().map(|x| x.id).max().unwrap_or(0) + 1;
+    let mut ret = options
+        .into_iter()
+        .map(|x| Box::new(RegistryTweak::new(x)) as Box<dyn Tweak>)
+        .collect::<Vec<_>>();
+    ret.extend(default_tweaks(next));
+    ret
+}
+
+pub fn find(tweaks: &[Box<dyn Tweak>], id: u64) -> Option<&dyn Tweak> {
+    tweaks.iter().find(|x| x.id() == id).map(|x| x.as_ref())
+}
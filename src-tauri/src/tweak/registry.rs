@@ -0,0 +1,70 @@
+use super::{Error, Result, Tweak, TweakInfo};
+use crate::command::RegistryEditOption;
+use crate::plan::LiveRegistry;
+use crate::watch::{self, ValueChanged};
+use crate::win::reg::{Registry, Value, WriteOutcome};
+
+/// レジストリの値を書き換える設定項目
+pub struct RegistryTweak {
+    info: TweakInfo,
+    opt: RegistryEditOption,
+}
+
+impl RegistryTweak {
+    pub fn new(opt: RegistryEditOption) -> Self {
+        Self {
+            info: TweakInfo {
+                windows_default: opt.default_selection(),
+                ..TweakInfo::new(opt.id, &opt.category, &opt.label, opt.values.clone())
+            },
+            opt,
+        }
+    }
+}
+
+impl Tweak for RegistryTweak {
+    fn info(&self) -> &TweakInfo {
+        &self.info
+    }
+
+    fn registry(&self) -> Option<&RegistryEditOption> {
+        Some(&self.opt)
+    }
+
+    fn read_state(&self) -> ValueChanged {
+        watch::status(&self.opt, &LiveRegistry)
+    }
+
+    fn apply(&self, candidate: Option<&str>) -> Result<()> {
+        let opt = &self.opt;
+        let r = Registry::from_def(&opt.def);
+        match candidate {
+            Some(v) => {
+                let v = opt
+                    .parse_value(v)
+                    .map_err(|e| Error::Validation(opt.id, e))?;
+                let outcome = match opt.bits {
+                    Some(field) => {
+                        r.set_bits_verified(opt.def.data_type, field, v != Value::DWord(0))?
+                    }
+                    None => r.write_value_verified(&v)?,
+                };
+                match outcome {
+                    WriteOutcome::Applied => Ok(()),
+                    WriteOutcome::AppliedButVirtualized => Err(Error::Virtualized(opt.id)),
+                    WriteOutcome::Reverted => Err(Error::Reverted(opt.id)),
+                }
+            }
+            None => {
+                opt.validate_absent()
+                    .map_err(|e| Error::Validation(opt.id, e))?;
+                r.delete_value()?;
+                Ok(())
+            }
+        }
+    }
+
+    fn describe(&self) -> String {
+        self.opt.def.to_string()
+    }
+}
@@ -0,0 +1,164 @@
+use super::{Error, Result, Tweak, TweakInfo};
+use crate::watch::ValueChanged;
+use crate::win::ps;
+
+/// タスク スケジューラのタスクを有効・無効にする
+pub trait TaskScheduler: Send + Sync {
+    fn is_enabled(&self, path: &str, name: &str) -> Result<bool>;
+    fn set_enabled(&self, path: &str, name: &str, enabled: bool) -> Result<()>;
+}
+
+/// PowerShell の ScheduledTasks モジュールを使う
+pub struct WinTaskScheduler;
+
+impl TaskScheduler for WinTaskScheduler {
+    fn is_enabled(&self, path: &str, name: &str) -> Result<bool> {
+        let script = format!(
+            "(Get-ScheduledTask -TaskPath {} -TaskName {}).State.ToString()",
+            ps::quote(path),
+            ps::quote(name)
+        );
+        // 実行中 (Running) や待機中 (Ready, Queued) は有効
+        match ps::query(&script)?.as_str() {
+            "Disabled" => Ok(false),
+            "Ready" | "Running" | "Queued" => Ok(true),
+            v => Err(Error::UnexpectedState(v.into())),
+        }
+    }
+
+    fn set_enabled(&self, path: &str, name: &str, enabled: bool) -> Result<()> {
+        let command = if enabled {
+            "Enable-ScheduledTask"
+        } else {
+            "Disable-ScheduledTask"
+        };
+        let script = format!(
+            "{command} -TaskPath {} -TaskName {} | Out-Null",
+            ps::quote(path),
+            ps::quote(name)
+        );
+        ps::run(&script)?;
+        Ok(())
+    }
+}
+
+/// スケジュールされたタスクの有効・無効 (候補の値は `1` が有効、`0` が無効)
+pub struct ScheduledTaskTweak<S = WinTaskScheduler> {
+    info: TweakInfo,
+    path: String,
+    name: String,
+    scheduler: S,
+}
+
+impl ScheduledTaskTweak {
+    pub fn new(info: TweakInfo, path: impl Into<String>, name: impl Into<String>) -> Self {
+        Self::with_scheduler(info, path, name, WinTaskScheduler)
+    }
+}
+
+impl<S: TaskScheduler> ScheduledTaskTweak<S> {
+    pub fn with_scheduler(
+        info: TweakInfo,
+        path: impl Into<String>,
+        name: impl Into<String>,
+        scheduler: S,
+    ) -> Self {
+        Self {
+            info,
+            path: path.into(),
+            name: name.into(),
+            scheduler,
+        }
+    }
+}
+
+impl<S: TaskScheduler> Tweak for ScheduledTaskTweak<S> {
+    fn info(&self) -> &TweakInfo {
+        &self.info
+    }
+
+    fn read_state(&self) -> ValueChanged {
+        let state = self
+            .scheduler
+            .is_enabled(&self.path, &self.name)
+            .map(|x| Some(if x { "1" } else { "0" }.to_string()));
+        self.info.status(state)
+    }
+
+    fn apply(&self, candidate: Option<&str>) -> Result<()> {
+        let candidate = self.info.check(candidate)?;
+        let enabled = match candidate.value.as_str() {
+            "1" => true,
+            "0" => false,
+            v => return Err(Error::UnknownCandidate(self.info.id, v.into())),
+        };
+        self.scheduler.set_enabled(&self.path, &self.name, enabled)
+    }
+
+    fn describe(&self) -> String {
+        format!("Task: {}{}", self.path, self.name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command::OptionCandidate;
+    use std::sync::Mutex;
+
+    /// 1 つのタスクの有効・無効
+    struct FakeScheduler(Mutex<bool>);
+
+    impl TaskScheduler for FakeScheduler {
+        fn is_enabled(&self, path: &str, name: &str) -> Result<bool> {
+            assert_eq!((path, name), (r"\Test\", "Task"));
+            Ok(*self.0.lock().unwrap())
+        }
+
+        fn set_enabled(&self, path: &str, name: &str, enabled: bool) -> Result<()> {
+            assert_eq!((path, name), (r"\Test\", "Task"));
+            *self.0.lock().unwrap() = enabled;
+            Ok(())
+        }
+    }
+
+    fn tweak(enabled: bool, values: Vec<OptionCandidate>) -> ScheduledTaskTweak<FakeScheduler> {
+        ScheduledTaskTweak::with_scheduler(
+            TweakInfo::new(1, "", "", values),
+            r"\Test\",
+            "Task",
+            FakeScheduler(Mutex::new(enabled)),
+        )
+    }
+
+    fn candidates() -> Vec<OptionCandidate> {
+        vec![
+            OptionCandidate::new("0", "無効"),
+            OptionCandidate::new("1", "有効"),
+        ]
+    }
+
+    #[test]
+    fn reads_and_toggles_task() {
+        let tweak = tweak(true, candidates());
+        assert_eq!(tweak.read_state().candidate.as_deref(), Some("1"));
+
+        tweak.apply(Some("0")).unwrap();
+        assert!(!*tweak.scheduler.0.lock().unwrap());
+        assert_eq!(tweak.read_state().description.as_deref(), Some("無効"));
+
+        tweak.apply(Some("1")).unwrap();
+        assert!(*tweak.scheduler.0.lock().unwrap());
+        assert_eq!(tweak.describe(), r"Task: \Test\Task");
+    }
+
+    #[test]
+    fn rejects_values_other_than_zero_and_one() {
+        let tweak = tweak(true, vec![OptionCandidate::new("2", "不明")]);
+        assert!(matches!(
+            tweak.apply(Some("2")),
+            Err(Error::UnknownCandidate(1, v)) if v == "2"
+        ));
+        assert!(*tweak.scheduler.0.lock().unwrap());
+    }
+}
@@ -0,0 +1,155 @@
+use super::{Error, Result, Tweak, TweakInfo};
+use crate::watch::ValueChanged;
+use crate::win::ps;
+use crate::win::reg::{DataType, RegDef, Registry, Value};
+
+/// サービスのスタートアップの種類を読み書きする
+pub trait ServiceManager: Send + Sync {
+    /// `Automatic`、`Manual`、`Disabled` など
+    fn start_type(&self, name: &str) -> Result<String>;
+    fn set_start_type(&self, name: &str, start_type: &str) -> Result<()>;
+}
+
+/// 読み取りはサービスのキーの `Start`、書き込みは PowerShell の `Set-Service` を使う
+/// (読み取りのたびに PowerShell を起動しない)
+pub struct WinServices;
+
+const SERVICES: &str = r"SYSTEM\CurrentControlSet\Services";
+
+impl ServiceManager for WinServices {
+    fn start_type(&self, name: &str) -> Result<String> {
+        let def = RegDef::hklm(format!(r"{SERVICES}\{name}"), "Start", DataType::DWord);
+        // ServiceStartMode の名前
+        let ret = match Registry::from_def(&def).find_value(DataType::DWord)? {
+            Some(Value::DWord(0)) => "Boot",
+            Some(Value::DWord(1)) => "System",
+            Some(Value::DWord(2)) => "Automatic",
+            Some(Value::DWord(3)) => "Manual",
+            Some(Value::DWord(4)) => "Disabled",
+            Some(v) => return Err(Error::UnexpectedState(format!("{def} = {v}"))),
+            None => return Err(Error::UnexpectedState(format!("{name} does not exist"))),
+        };
+        Ok(ret.into())
+    }
+
+    fn set_start_type(&self, name: &str, start_type: &str) -> Result<()> {
+        let script = format!(
+            "Set-Service -Name {} -StartupType {}",
+            ps::quote(name),
+            ps::quote(start_type)
+        );
+        ps::run(&script)?;
+        Ok(())
+    }
+}
+
+/// サービスのスタートアップの種類 (候補の値は種類の名前)
+pub struct ServiceTweak<M = WinServices> {
+    info: TweakInfo,
+    name: String,
+    manager: M,
+}
+
+impl ServiceTweak {
+    pub fn new(info: TweakInfo, name: impl Into<String>) -> Self {
+        Self::with_manager(info, name, WinServices)
+    }
+}
+
+impl<M: ServiceManager> ServiceTweak<M> {
+    pub fn with_manager(info: TweakInfo, name: impl Into<String>, manager: M) -> Self {
+        Self {
+            info,
+            name: name.into(),
+            manager,
+        }
+    }
+}
+
+impl<M: ServiceManager> Tweak for ServiceTweak<M> {
+    fn info(&self) -> &TweakInfo {
+        &self.info
+    }
+
+    fn read_state(&self) -> ValueChanged {
+        self.info
+            .status(self.manager.start_type(&self.name).map(Some))
+    }
+
+    fn apply(&self, candidate: Option<&str>) -> Result<()> {
+        let candidate = self.info.check(candidate)?;
+        self.manager.set_start_type(&self.name, &candidate.value)
+    }
+
+    fn describe(&self) -> String {
+        format!("Service: {}", self.name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command::OptionCandidate;
+    use std::sync::Mutex;
+
+    /// 1 つのサービスのスタートアップの種類
+    struct FakeServices(Mutex<String>);
+
+    impl ServiceManager for FakeServices {
+        fn start_type(&self, name: &str) -> Result<String> {
+            match name {
+                "SysMain" => Ok(self.0.lock().unwrap().clone()),
+                _ => Err(Error::UnexpectedState(name.into())),
+            }
+        }
+
+        fn set_start_type(&self, name: &str, start_type: &str) -> Result<()> {
+            assert_eq!(name, "SysMain");
+            *self.0.lock().unwrap() = start_type.into();
+            Ok(())
+        }
+    }
+
+    fn tweak(name: &str, start_type: &str) -> ServiceTweak<FakeServices> {
+        ServiceTweak::with_manager(
+            TweakInfo::new(
+                1,
+                "",
+                "",
+                vec![
+                    OptionCandidate::new("Automatic", "自動"),
+                    OptionCandidate::new("Disabled", "無効"),
+                ],
+            ),
+            name,
+            FakeServices(Mutex::new(start_type.into())),
+        )
+    }
+
+    #[test]
+    fn reads_start_type() {
+        let state = tweak("SysMain", "Automatic").read_state();
+        assert_eq!(state.value.as_deref(), Some("Automatic"));
+        assert_eq!(state.description.as_deref(), Some("自動"));
+
+        // 候補に無い種類も値として表示する
+        let state = tweak("SysMain", "Manual").read_state();
+        assert_eq!(state.value.as_deref(), Some("Manual"));
+        assert_eq!(state.candidate, None);
+
+        assert!(tweak("Missing", "Automatic").read_state().error.is_some());
+    }
+
+    #[test]
+    fn applies_candidates() {
+        let tweak = tweak("SysMain", "Automatic");
+        tweak.apply(Some("Disabled")).unwrap();
+        assert_eq!(*tweak.manager.0.lock().unwrap(), "Disabled");
+        assert!(matches!(
+            tweak.apply(Some("Manual")),
+            Err(Error::UnknownCandidate(1, _))
+        ));
+        assert!(matches!(tweak.apply(None), Err(Error::Validation(1, _))));
+        assert_eq!(*tweak.manager.0.lock().unwrap(), "Disabled");
+    }
+}
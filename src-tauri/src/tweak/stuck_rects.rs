@@ -0,0 +1,268 @@
+use super::{Error, Result, Tweak, TweakInfo};
+use crate::watch::ValueChanged;
+use crate::win::reg::{self, DataType, RegDef, Registry, Value};
+
+/// タスクバーの位置や自動的に隠す設定を持つ値 (エクスプローラの再起動後に反映される)
+const STUCK_RECTS: &str = r"Software\Microsoft\Windows\CurrentVersion\Explorer\StuckRects3";
+
+/// フラグ (0x01 が自動的に隠す)
+const FLAGS: usize = 8;
+const AUTO_HIDE: u32 = 0x01;
+/// 画面の端 (`Edge`)
+const EDGE: usize = 12;
+/// タスクバーの幅と高さ
+const SIZE: usize = 16;
+/// 扱うフィールドを含む長さ (実際の値は 48 バイト)
+const MIN_LEN: usize = 24;
+
+/// タスクバーを表示する画面の端 (ABE_LEFT などと同じ値)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Edge {
+    Left,
+    Top,
+    Right,
+    Bottom,
+}
+
+impl Edge {
+    pub fn from_u32(value: u32) -> Option<Self> {
+        match value {
+            0 => Some(Self::Left),
+            1 => Some(Self::Top),
+            2 => Some(Self::Right),
+            3 => Some(Self::Bottom),
+            _ => None,
+        }
+    }
+
+    pub fn to_u32(self) -> u32 {
+        match self {
+            Self::Left => 0,
+            Self::Top => 1,
+            Self::Right => 2,
+            Self::Bottom => 3,
+        }
+    }
+}
+
+/// `StuckRects3\Settings` の値
+/// 扱うフィールドだけを書き換え、それ以外のバイトはそのまま残す
+#[derive(Debug, Clone, PartialEq)]
+pub struct StuckRects {
+    data: Vec<u8>,
+}
+
+impl StuckRects {
+    pub fn decode(data: &[u8]) -> reg::Result<Self> {
+        if data.len() < MIN_LEN {
+            return Err(reg::Error::MalformedData("StuckRects3", data.len()));
+        }
+        Ok(Self {
+            data: data.to_vec(),
+        })
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        self.data.clone()
+    }
+
+    pub fn auto_hide(&self) -> bool {
+        self.u32_at(FLAGS) & AUTO_HIDE != 0
+    }
+
+    pub fn set_auto_hide(&mut self, on: bool) {
+        let flags = self.u32_at(FLAGS);
+        let flags = if on {
+            flags | AUTO_HIDE
+        } else {
+            flags & !AUTO_HIDE
+        };
+        self.set_u32_at(FLAGS, flags);
+    }
+
+    /// 知らない値の場合は `None`
+    pub fn edge(&self) -> Option<Edge> {
+        Edge::from_u32(self.u32_at(EDGE))
+    }
+
+    pub fn set_edge(&mut self, edge: Edge) {
+        self.set_u32_at(EDGE, edge.to_u32());
+    }
+
+    /// タスクバーの幅と高さ (ピクセル)
+    pub fn size(&self) -> (u32, u32) {
+        (self.u32_at(SIZE), self.u32_at(SIZE + 4))
+    }
+
+    pub fn set_size(&mut self, width: u32, height: u32) {
+        self.set_u32_at(SIZE, width);
+        self.set_u32_at(SIZE + 4, height);
+    }
+
+    fn u32_at(&self, offset: usize) -> u32 {
+        let bytes = &self.data[offset..offset + 4];
+        u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+    }
+
+    fn set_u32_at(&mut self, offset: usize, value: u32) {
+        self.data[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+    }
+}
+
+/// `StuckRects3` の中の 1 つのフィールド
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StuckRectsField {
+    /// 候補は 0/1
+    AutoHide,
+    /// 候補は `Edge` の値 (0〜3)
+    Edge,
+}
+
+/// `StuckRects3` のフィールドを読み書きする設定項目
+/// 値は Windows が作成するため、値が無い場合は書き込まない
+pub struct StuckRectsTweak {
+    info: TweakInfo,
+    field: StuckRectsField,
+    def: RegDef,
+}
+
+impl StuckRectsTweak {
+    pub fn new(info: TweakInfo, field: StuckRectsField) -> Self {
+        Self {
+            info,
+            field,
+            def: RegDef::user(STUCK_RECTS, "Settings", DataType::Binary),
+        }
+    }
+
+    fn read(&self) -> Result<Option<StuckRects>> {
+        match Registry::from_def(&self.def).find_value(DataType::Binary)? {
+            Some(Value::Binary(v)) => Ok(Some(StuckRects::decode(&v)?)),
+            _ => Ok(None),
+        }
+    }
+
+    fn get(&self, blob: &StuckRects) -> Result<String> {
+        match self.field {
+            StuckRectsField::AutoHide => Ok((blob.auto_hide() as u32).to_string()),
+            StuckRectsField::Edge => match blob.edge() {
+                Some(v) => Ok(v.to_u32().to_string()),
+                None => Err(Error::UnexpectedState(format!(
+                    "Unknown taskbar edge ({})",
+                    blob.u32_at(EDGE)
+                ))),
+            },
+        }
+    }
+
+    fn set(&self, blob: &mut StuckRects, value: &str) -> Result<()> {
+        let unknown = || Error::UnknownCandidate(self.info.id, value.into());
+        match self.field {
+            StuckRectsField::AutoHide => blob.set_auto_hide(value == "1"),
+            StuckRectsField::Edge => {
+                let edge = value
+                    .parse::<u32>()
+                    .ok()
+                    .and_then(Edge::from_u32)
+                    .ok_or_else(unknown)?;
+                blob.set_edge(edge);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Tweak for StuckRectsTweak {
+    fn info(&self) -> &TweakInfo {
+        &self.info
+    }
+
+    fn read_state(&self) -> ValueChanged {
+        let state = self
+            .read()
+            .and_then(|x| x.map(|blob| self.get(&blob)).transpose());
+        self.info.status(state)
+    }
+
+    fn apply(&self, candidate: Option<&str>) -> Result<()> {
+        let candidate = self.info.check(candidate)?;
+        let Some(mut blob) = self.read()? else {
+            return Err(Error::UnexpectedState(format!(
+                "{} does not exist",
+                self.def
+            )));
+        };
+        let before = blob.clone();
+        self.set(&mut blob, &candidate.value)?;
+        if blob == before {
+            return Ok(());
+        }
+        Registry::from_def(&self.def).write_value(&Value::Binary(blob.encode()))?;
+        Ok(())
+    }
+
+    fn describe(&self) -> String {
+        self.def.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 下に表示し、自動的に隠さない状態の `Settings` の値 (Windows 11 23H2)
+    const BOTTOM: [u8; 48] = [
+        0x30, 0x00, 0x00, 0x00, 0xfe, 0xff, 0xff, 0xff, 0x7a, 0xf4, 0x00, 0x00, 0x03, 0x00, 0x00,
+        0x00, 0x3c, 0x00, 0x00, 0x00, 0x30, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x18, 0x04,
+        0x00, 0x00, 0x80, 0x07, 0x00, 0x00, 0x48, 0x04, 0x00, 0x00, 0x60, 0x00, 0x00, 0x00, 0x01,
+        0x00, 0x00, 0x00,
+    ];
+
+    /// `BOTTOM` のフラグと画面の端を変えた値
+    fn capture(auto_hide: bool, edge: u8) -> [u8; 48] {
+        let mut ret = BOTTOM;
+        ret[FLAGS] = if auto_hide { 0x7b } else { 0x7a };
+        ret[EDGE] = edge;
+        ret
+    }
+
+    #[test]
+    fn decodes_captured_values() {
+        for (auto_hide, edge) in [
+            (false, Edge::Bottom),
+            (true, Edge::Bottom),
+            (false, Edge::Top),
+            (true, Edge::Top),
+        ] {
+            let data = capture(auto_hide, edge.to_u32() as u8);
+            let blob = StuckRects::decode(&data).unwrap();
+            assert_eq!(blob.auto_hide(), auto_hide);
+            assert_eq!(blob.edge(), Some(edge));
+            assert_eq!(blob.size(), (60, 48));
+            assert_eq!(blob.encode(), data);
+        }
+    }
+
+    #[test]
+    fn setters_change_only_their_byte() {
+        let mut blob = StuckRects::decode(&BOTTOM).unwrap();
+        blob.set_auto_hide(true);
+        assert_eq!(blob.encode(), capture(true, 3));
+        blob.set_edge(Edge::Top);
+        assert_eq!(blob.encode(), capture(true, 1));
+        blob.set_auto_hide(false);
+        blob.set_edge(Edge::Bottom);
+        assert_eq!(blob.encode(), BOTTOM);
+    }
+
+    #[test]
+    fn rejects_short_values() {
+        for len in 0..MIN_LEN {
+            assert!(matches!(
+                StuckRects::decode(&BOTTOM[..len]),
+                Err(reg::Error::MalformedData(_, n)) if n == len
+            ));
+        }
+        assert!(StuckRects::decode(&BOTTOM[..MIN_LEN]).is_ok());
+    }
+}
@@ -0,0 +1,162 @@
+use super::{Error, Result, Tweak, TweakInfo};
+use crate::watch::ValueChanged;
+use windows::Win32::UI::WindowsAndMessaging::{
+    SystemParametersInfoW, SPIF_SENDCHANGE, SPIF_UPDATEINIFILE, SYSTEM_PARAMETERS_INFO_ACTION,
+    SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS,
+};
+
+/// `SystemParametersInfo` で整数の設定を読み書きする
+pub trait SystemParameters: Send + Sync {
+    fn get(&self, action: u32) -> Result<u32>;
+    fn set(&self, action: u32, value: u32) -> Result<()>;
+}
+
+/// 読み取りは `pvParam` が指す整数、書き込みは `pvParam` そのものに値を渡す設定だけを扱う
+/// (SPI_SETCLIENTAREAANIMATION や SPI_SETMOUSESPEED など)
+pub struct WinSystemParameters;
+
+impl SystemParameters for WinSystemParameters {
+    fn get(&self, action: u32) -> Result<u32> {
+        let mut ret = 0u32;
+        unsafe {
+            SystemParametersInfoW(
+                SYSTEM_PARAMETERS_INFO_ACTION(action),
+                0,
+                Some(&mut ret as *mut u32 as *mut _),
+                SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS(0),
+            )?;
+        }
+        Ok(ret)
+    }
+
+    fn set(&self, action: u32, value: u32) -> Result<()> {
+        // 設定を保存し、変更を他のウィンドウに通知する
+        unsafe {
+            SystemParametersInfoW(
+                SYSTEM_PARAMETERS_INFO_ACTION(action),
+                0,
+                Some(value as usize as *mut _),
+                SPIF_UPDATEINIFILE | SPIF_SENDCHANGE,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// `SystemParametersInfo` の設定 (候補の値は整数)
+pub struct SystemParameterTweak<P = WinSystemParameters> {
+    info: TweakInfo,
+    get_action: u32,
+    set_action: u32,
+    parameters: P,
+}
+
+impl SystemParameterTweak {
+    pub fn new(info: TweakInfo, get_action: u32, set_action: u32) -> Self {
+        Self::with_parameters(info, get_action, set_action, WinSystemParameters)
+    }
+}
+
+impl<P: SystemParameters> SystemParameterTweak<P> {
+    pub fn with_parameters(
+        info: TweakInfo,
+        get_action: u32,
+        set_action: u32,
+        parameters: P,
+    ) -> Self {
+        Self {
+            info,
+            get_action,
+            set_action,
+            parameters,
+        }
+    }
+}
+
+impl<P: SystemParameters> Tweak for SystemParameterTweak<P> {
+    fn info(&self) -> &TweakInfo {
+        &self.info
+    }
+
+    fn read_state(&self) -> ValueChanged {
+        let state = self
+            .parameters
+            .get(self.get_action)
+            .map(|x| Some(x.to_string()));
+        self.info.status(state)
+    }
+
+    fn apply(&self, candidate: Option<&str>) -> Result<()> {
+        let candidate = self.info.check(candidate)?;
+        let value = candidate
+            .value
+            .parse::<u32>()
+            .map_err(|_| Error::UnknownCandidate(self.info.id, candidate.value.clone()))?;
+        self.parameters.set(self.set_action, value)
+    }
+
+    fn describe(&self) -> String {
+        format!("SystemParametersInfo: 0x{:04x}", self.set_action)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command::OptionCandidate;
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    const GET: u32 = 0x1042;
+    const SET: u32 = 0x1043;
+
+    /// 読み取りと書き込みで別の `action` を使う設定
+    struct FakeParameters(Mutex<HashMap<u32, u32>>);
+
+    impl SystemParameters for FakeParameters {
+        fn get(&self, action: u32) -> Result<u32> {
+            assert_eq!(action, GET);
+            Ok(*self.0.lock().unwrap().get(&GET).unwrap_or(&0))
+        }
+
+        fn set(&self, action: u32, value: u32) -> Result<()> {
+            assert_eq!(action, SET);
+            self.0.lock().unwrap().insert(GET, value);
+            Ok(())
+        }
+    }
+
+    fn tweak(values: Vec<OptionCandidate>) -> SystemParameterTweak<FakeParameters> {
+        SystemParameterTweak::with_parameters(
+            TweakInfo::new(1, "", "", values),
+            GET,
+            SET,
+            FakeParameters(Mutex::new(HashMap::from([(GET, 1)]))),
+        )
+    }
+
+    #[test]
+    fn reads_and_sets_value() {
+        let tweak = tweak(vec![
+            OptionCandidate::new("0", "オフ"),
+            OptionCandidate::new("1", "オン"),
+        ]);
+        assert_eq!(tweak.read_state().candidate.as_deref(), Some("1"));
+
+        tweak.apply(Some("0")).unwrap();
+        let state = tweak.read_state();
+        assert_eq!(state.value.as_deref(), Some("0"));
+        assert_eq!(state.description.as_deref(), Some("オフ"));
+        assert_eq!(tweak.describe(), "SystemParametersInfo: 0x1043");
+    }
+
+    #[test]
+    fn rejects_non_numeric_candidates() {
+        let tweak = tweak(vec![OptionCandidate::new("on", "オン")]);
+        assert!(matches!(
+            tweak.apply(Some("on")),
+            Err(Error::UnknownCandidate(1, _))
+        ));
+        assert_eq!(tweak.read_state().value.as_deref(), Some("1"));
+    }
+}
@@ -0,0 +1,5 @@
+mod source;
+mod watcher;
+
+pub use source::{ChangeSource, EventSink, Notification, ValueChanged};
+pub use watcher::{status, Watcher};
@@ -0,0 +1,118 @@
+use crate::policy::Layer;
+use crate::win::reg::{Environment, KeyNotifier, Value};
+use serde::Serialize;
+
+/// 変更通知の内容 (添字は `Watcher::keys` 上の位置)
+#[derive(Debug, Clone, PartialEq)]
+pub enum Notification {
+    Changed(usize),
+    /// 変更は受け取ったが、監視を再登録できなかった (キーが削除された場合など)
+    Lost(usize, String),
+}
+
+/// 監視対象キーの変更通知元
+pub trait ChangeSource {
+    /// 次の通知を待つ。`None` で監視を終了する
+    fn wait(&mut self) -> Option<Notification>;
+
+    /// 監視できなくなったキーを開き直す
+    /// 開けない場合はそのキーの監視だけをやめて `false` を返す
+    fn reopen(&mut self, index: usize) -> bool;
+}
+
+impl ChangeSource for KeyNotifier {
+    fn wait(&mut self) -> Option<Notification> {
+        let index = match KeyNotifier::wait(self) {
+            Ok(index) => index,
+            Err(e) => {
+                tracing::error!("Failed to wait for registry changes: {e}");
+                return None;
+            }
+        };
+        match self.arm(index) {
+            Ok(()) => Some(Notification::Changed(index)),
+            Err(e) => Some(Notification::Lost(index, e.to_string())),
+        }
+    }
+
+    fn reopen(&mut self, index: usize) -> bool {
+        match KeyNotifier::reopen(self, index) {
+            Ok(()) => true,
+            Err(e) => {
+                tracing::warn!("Failed to reopen watched key: {e}");
+                false
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ValueChanged {
+    pub id: u64,
+    /// 現在有効な値 (`None` は値が存在しない)
+    pub value: Option<String>,
+    /// 値を決めている層 (ポリシーで上書きされている場合は項目を変更しても反映されない)
+    pub layer: Option<Layer>,
+    /// 一致した候補の値と説明
+    pub candidate: Option<String>,
+    pub description: Option<String>,
+    /// REG_EXPAND_SZ の環境変数を展開した値 (`value` は展開前のまま)
+    pub expanded: Option<String>,
+    /// 展開したパス (`;` 区切りの場合は各項目) のうち存在しないもの
+    pub missing_paths: Vec<String>,
+    pub error: Option<String>,
+}
+
+impl ValueChanged {
+    /// 値も候補も無い状態 (値が存在しない場合)
+    pub fn new(id: u64) -> Self {
+        Self {
+            id,
+            value: None,
+            layer: None,
+            candidate: None,
+            description: None,
+            expanded: None,
+            missing_paths: Vec::new(),
+            error: None,
+        }
+    }
+
+    /// 状態を読み取れなかった理由
+    pub fn error(id: u64, e: impl std::fmt::Display) -> Self {
+        Self {
+            error: Some(e.to_string()),
+            ..Self::new(id)
+        }
+    }
+
+    /// 読み取った値が REG_EXPAND_SZ の場合に、展開した値とパスの有無を加える
+    pub fn with_expansion(mut self, value: Option<&Value>, env: &impl Environment) -> Self {
+        let Some(expanded) = value.and_then(|x| x.expanded(env)) else {
+            return self;
+        };
+        self.missing_paths = expanded
+            .split(';')
+            .map(str::trim)
+            .filter(|x| is_absolute_path(x) && !std::path::Path::new(x).exists())
+            .map(str::to_string)
+            .collect();
+        self.expanded = Some(expanded);
+        self
+    }
+}
+
+/// `C:\...` や `\\server\...` の形の文字列 (パス以外の値は存在を確かめない)
+fn is_absolute_path(text: &str) -> bool {
+    let bytes = text.as_bytes();
+    text.starts_with(r"\\")
+        || (bytes.len() >= 3
+            && bytes[0].is_ascii_alphabetic()
+            && bytes[1] == b':'
+            && matches!(bytes[2], b'\\' | b'/'))
+}
+
+/// 値の変化を受け取る側 (画面へのイベント送信など)
+pub trait EventSink {
+    fn value_changed(&self, change: &ValueChanged);
+}
@@ -0,0 +1,272 @@
+use super::{ChangeSource, EventSink, Notification, ValueChanged};
+use crate::command::RegistryEditOption;
+use crate::plan::CurrentState;
+use crate::policy;
+use crate::win::reg::{ProcessEnvironment, Root, Target, View};
+use std::collections::HashMap;
+
+/// 設定項目が参照しているキーを監視し、値が変わった項目を通知する
+pub struct Watcher<'a> {
+    options: &'a [RegistryEditOption],
+    keys: Vec<(Root, String, View)>,
+    /// キーの位置ごとの設定項目 (`options` の添字)
+    members: Vec<Vec<usize>>,
+    last: HashMap<u64, ValueChanged>,
+}
+
+impl<'a> Watcher<'a> {
+    pub fn new(options: &'a [RegistryEditOption]) -> Self {
+        let mut keys: Vec<(Root, String, View)> = Vec::new();
+        let mut members: Vec<Vec<usize>> = Vec::new();
+        // ポリシーのキーが変わった場合も上書きされる項目を読み直す
+        for (i, opt) in options.iter().enumerate() {
+            let defs = std::iter::once(&opt.def).chain(opt.policies.iter().map(|x| &x.def));
+            for def in defs {
+                // 現在のユーザーの値を監視するため、`Root::User` と HKCU は同じキーとして扱う
+                let (def_root, def_sub_key) = Target::CurrentUser.resolve(def.root, &def.sub_key);
+                let pos = keys.iter().position(|(root, sub_key, view)| {
+                    *root == def_root
+                        && sub_key.eq_ignore_ascii_case(&def_sub_key)
+                        && *view == def.view
+                });
+                match pos {
+                    Some(pos) if members[pos].contains(&i) => (),
+                    Some(pos) => members[pos].push(i),
+                    None => {
+                        keys.push((def_root, def_sub_key, def.view));
+                        members.push(vec![i]);
+                    }
+                }
+            }
+        }
+
+        Self {
+            options,
+            keys,
+            members,
+            last: HashMap::new(),
+        }
+    }
+
+    pub fn keys(&self) -> &[(Root, String, View)] {
+        &self.keys
+    }
+
+    /// 現在の値を通知せずに記録する
+    pub fn prime(&mut self, state: &impl CurrentState) {
+        for opt in self.options.iter() {
+            self.last.insert(opt.id, status(opt, state));
+        }
+    }
+
+    pub fn run(
+        &mut self,
+        source: &mut impl ChangeSource,
+        state: &impl CurrentState,
+        sink: &impl EventSink,
+    ) {
+        while let Some(notification) = source.wait() {
+            match notification {
+                Notification::Changed(index) => self.refresh(index, state, sink),
+                // 削除されたキーの項目も値が無くなったことを通知し、残りのキーの監視は続ける
+                Notification::Lost(index, e) => {
+                    let key = self.keys.get(index).map(|(_, k, _)| k.clone());
+                    tracing::warn!(key = ?key, "Lost registry watch: {e}");
+                    self.refresh(index, state, sink);
+                    if !source.reopen(index) {
+                        tracing::warn!(key = ?key, "Stopped watching key");
+                    }
+                }
+            }
+        }
+    }
+
+    /// `index` のキーに属する項目を読み直し、前回から変わったものだけ通知する
+    pub fn refresh(&mut self, index: usize, state: &impl CurrentState, sink: &impl EventSink) {
+        let Some(members) = self.members.get(index) else {
+            return;
+        };
+
+        for &i in members.iter() {
+            let opt = &self.options[i];
+            let change = status(opt, state);
+            if self.last.get(&opt.id) != Some(&change) {
+                sink.value_changed(&change);
+                self.last.insert(opt.id, change);
+            }
+        }
+    }
+}
+
+/// 項目の現在の状態 (ポリシーで上書きされている場合は有効な値)
+pub fn status(opt: &RegistryEditOption, state: &impl CurrentState) -> ValueChanged {
+    match policy::resolve(opt, state) {
+        Ok(r) => {
+            let v = r.effective;
+            let candidate = opt.detect(v.as_ref());
+            ValueChanged {
+                value: v.as_ref().map(|x| x.to_string()),
+                layer: Some(r.layer),
+                candidate: candidate.map(|x| x.value.clone()),
+                description: candidate.map(|x| x.description.clone()),
+                ..ValueChanged::new(opt.id)
+            }
+            .with_expansion(v.as_ref(), &ProcessEnvironment)
+        }
+        Err(e) => ValueChanged::error(opt.id, e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command::OptionCandidate;
+    use crate::win::reg::{DataType, RegDef, Result, Value};
+    use std::cell::RefCell;
+    use std::collections::VecDeque;
+
+    /// 値の名前ごとの値 (キーは区別しない)
+    #[derive(Default)]
+    struct FakeState(RefCell<HashMap<String, Value>>);
+
+    impl FakeState {
+        fn set(&self, name: &str, value: Option<u32>) {
+            let mut values = self.0.borrow_mut();
+            match value {
+                Some(v) => values.insert(name.into(), Value::DWord(v)),
+                None => values.remove(name),
+            };
+        }
+    }
+
+    impl CurrentState for FakeState {
+        fn key_exists(&self, _def: &RegDef) -> Result<bool> {
+            Ok(true)
+        }
+
+        fn value(&self, def: &RegDef) -> Result<Option<Value>> {
+            Ok(self.0.borrow().get(&def.value_name).cloned())
+        }
+    }
+
+    /// 通知を返す前に状態を書き換える
+    struct FakeSource<'a> {
+        state: &'a FakeState,
+        steps: VecDeque<(&'static str, Option<u32>, Notification)>,
+        reopened: Vec<usize>,
+    }
+
+    impl ChangeSource for FakeSource<'_> {
+        fn wait(&mut self) -> Option<Notification> {
+            let (name, value, notification) = self.steps.pop_front()?;
+            self.state.set(name, value);
+            Some(notification)
+        }
+
+        fn reopen(&mut self, index: usize) -> bool {
+            self.reopened.push(index);
+            false
+        }
+    }
+
+    #[derive(Default)]
+    struct Recorder(RefCell<Vec<ValueChanged>>);
+
+    impl EventSink for Recorder {
+        fn value_changed(&self, change: &ValueChanged) {
+            self.0.borrow_mut().push(change.clone());
+        }
+    }
+
+    fn option(id: u64, sub_key: &str, name: &str) -> RegistryEditOption {
+        RegistryEditOption {
+            id,
+            label: name.into(),
+            category: String::new(),
+            tags: Vec::new(),
+            def: RegDef::user(sub_key, name, DataType::DWord),
+            values: vec![
+                OptionCandidate::new("0", "オフ"),
+                OptionCandidate::new("1", "オン"),
+                OptionCandidate::absent("なし"),
+            ],
+            domain: Default::default(),
+            bits: None,
+            windows_default: None,
+            requires: Vec::new(),
+            conflicts_with: Vec::new(),
+            policies: Vec::new(),
+        }
+    }
+
+    fn run(
+        steps: Vec<(&'static str, Option<u32>, Notification)>,
+    ) -> (Vec<ValueChanged>, Vec<usize>) {
+        let options = vec![option(1, r"Software\A", "X"), option(2, r"Software\B", "Y")];
+        let state = FakeState::default();
+        state.set("X", Some(0));
+        state.set("Y", Some(0));
+
+        let mut watcher = Watcher::new(&options);
+        assert_eq!(watcher.keys().len(), 2);
+        watcher.prime(&state);
+
+        let mut source = FakeSource {
+            state: &state,
+            steps: steps.into(),
+            reopened: Vec::new(),
+        };
+        let sink = Recorder::default();
+        watcher.run(&mut source, &state, &sink);
+        (sink.0.into_inner(), source.reopened)
+    }
+
+    #[test]
+    fn notifies_only_changed_values() {
+        let (events, _) = run(vec![
+            ("X", Some(0), Notification::Changed(0)),
+            ("X", Some(1), Notification::Changed(0)),
+            ("Y", Some(1), Notification::Changed(0)),
+        ]);
+
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].id, 1);
+        assert_eq!(events[0].candidate.as_deref(), Some("1"));
+        assert_eq!(events[0].description.as_deref(), Some("オン"));
+    }
+
+    #[test]
+    fn watches_user_and_hkcu_as_one_key() {
+        let mut options = vec![
+            option(1, r"Software\A", "X"),
+            option(2, r"SOFTWARE\a", "Y"),
+            option(3, r"Software\A", "Z"),
+        ];
+        options[1].def = RegDef::hkcu(r"SOFTWARE\a", "Y", DataType::DWord);
+        options[2].def = options[2].def.clone().in_view(View::Bit32);
+
+        let watcher = Watcher::new(&options);
+        assert_eq!(
+            watcher.keys(),
+            [
+                (Root::CurrentUser, r"Software\A".to_string(), View::Native),
+                (Root::CurrentUser, r"Software\A".to_string(), View::Bit32),
+            ]
+        );
+        assert_eq!(watcher.members, [vec![0, 1], vec![2]]);
+    }
+
+    #[test]
+    fn keeps_watching_after_key_is_lost() {
+        let (events, reopened) = run(vec![
+            ("X", None, Notification::Lost(0, "deleted".into())),
+            ("Y", Some(1), Notification::Changed(1)),
+        ]);
+
+        assert_eq!(reopened, vec![0]);
+        assert_eq!(events.len(), 2);
+        assert_eq!((events[0].id, events[0].value.as_deref()), (1, None));
+        assert_eq!(events[0].description.as_deref(), Some("なし"));
+        assert_eq!((events[1].id, events[1].value.as_deref()), (2, Some("1")));
+    }
+}
@@ -1,6 +1,7 @@
 pub mod ps;
 pub mod reg;
 use windows::core::*;
+use windows::Win32::System::Console::{AttachConsole, ATTACH_PARENT_PROCESS};
 pub use windows::Win32::System::Registry::REG_DWORD;
 use windows::Win32::System::WindowsProgramming::GetUserNameW;
 use windows::Win32::UI::WindowsAndMessaging::*;
@@ -16,6 +17,33 @@ pub fn message_box(text: impl AsRef<str>, caption: impl AsRef<str>) {
     }
 }
 
+/// 起動元のコンソールに標準入出力をつなぐ
+/// リリースビルドは GUI サブシステムのため、呼ばないとコマンドラインの出力が表示されない
+pub fn attach_parent_console() {
+    // コンソールから起動されていない場合は失敗するが、そのままでよい
+    let _ = unsafe { AttachConsole(ATTACH_PARENT_PROCESS) };
+}
+
+/// 設定の変更を全ウィンドウに通知する (`area` は "Environment" など)
+/// 応答しないウィンドウは待たずに飛ばす
+pub fn broadcast_setting_change(area: &str) {
+    use windows::Win32::Foundation::{LPARAM, WPARAM};
+
+    let area = HSTRING::from(area);
+    let mut result = 0usize;
+    unsafe {
+        SendMessageTimeoutW(
+            HWND_BROADCAST,
+            WM_SETTINGCHANGE,
+            WPARAM(0),
+            LPARAM(area.as_ptr() as isize),
+            SMTO_ABORTIFHUNG,
+            5000,
+            Some(&mut result),
+        );
+    }
+}
+
 pub fn get_username() -> std::result::Result<String, windows::core::Error> {
     // バッファを用意 (Windowsのユーザー名は通常256文字以下)
     let mut buffer = vec![0u16; 256];
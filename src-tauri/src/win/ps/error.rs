@@ -5,6 +5,8 @@ pub type Result<T> = std::result::Result<T, Error>;
 #[derive(Debug)]
 pub enum Error {
     Io(io::Error),
+    /// スクリプトがエラーで終了した (標準エラー出力)
+    Failed(String),
 }
 
 impl std::error::Error for Error {}
@@ -13,6 +15,7 @@ impl std::fmt::Display for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Self::Io(e) => e.fmt(f),
+            Self::Failed(e) => write!(f, "PowerShell failed: {e}"),
         }
     }
 }
@@ -2,4 +2,4 @@ mod error;
 mod proc;
 
 pub use error::{Error, Result};
-pub use proc::get_sid;
+pub use proc::{get_sid, query, quote, run};
@@ -1,16 +1,87 @@
-use super::Result;
+use super::{Error, Result};
+use std::collections::HashMap;
+use std::os::windows::process::CommandExt;
 use std::process::Command;
+use std::sync::{LazyLock, Mutex};
+use std::time::{Duration, Instant};
+use windows::Win32::System::Threading::CREATE_NO_WINDOW;
+
+/// 読み取りの結果を使い回す時間 (画面の更新や変更の通知のたびに起動しないようにする)
+const QUERY_TTL: Duration = Duration::from_secs(10);
+
+/// スクリプトごとの読み取りの結果と取得した時刻
+static QUERIES: LazyLock<Mutex<HashMap<String, (Instant, String)>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// 画面から呼び出してもコンソール ウィンドウを表示しない
+fn powershell() -> Command {
+    let mut ret = Command::new("PowerShell");
+    ret.creation_flags(CREATE_NO_WINDOW.0);
+    ret
+}
 
 pub fn get_sid(user: &str) -> Result<String> {
     let cmd = format!(
         "Get-CimInstance -Filter \"name='{}'\" win32_useraccount | Select-Object -ExpandProperty SID",
         user
     );
-    let output = Command::new("PowerShell")
+    let output = powershell().arg("-Command").arg(&cmd).output()?;
+
+    let stdout: String = String::from_utf8_lossy(&output.stdout).into();
+    Ok(stdout.trim().into())
+}
+
+/// スクリプトを実行して標準出力を返す
+/// エラーが発生した場合は標準エラー出力の内容を `Error::Failed` で返す
+/// 状態を変えるスクリプトもあるため、`query` の結果は破棄する
+pub fn run(script: &str) -> Result<String> {
+    QUERIES.lock().unwrap().clear();
+    execute(script)
+}
+
+/// 状態を読み取るだけのスクリプトを実行する
+/// 同じスクリプトを `QUERY_TTL` 以内に実行した場合は、前回の結果を返す
+pub fn query(script: &str) -> Result<String> {
+    if let Some((at, stdout)) = QUERIES.lock().unwrap().get(script) {
+        if at.elapsed() < QUERY_TTL {
+            return Ok(stdout.clone());
+        }
+    }
+    let ret = execute(script)?;
+    QUERIES
+        .lock()
+        .unwrap()
+        .insert(script.to_string(), (Instant::now(), ret.clone()));
+    Ok(ret)
+}
+
+fn execute(script: &str) -> Result<String> {
+    let output = powershell()
+        .arg("-NoProfile")
+        .arg("-NonInteractive")
         .arg("-Command")
-        .arg(&cmd)
+        .arg(format!("$ErrorActionPreference = 'Stop'; {script}"))
         .output()?;
 
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(Error::Failed(stderr.trim().into()));
+    }
     let stdout: String = String::from_utf8_lossy(&output.stdout).into();
     Ok(stdout.trim().into())
 }
+
+/// 単一引用符の文字列にする (変数展開は行われない)
+/// PowerShell は全角の引用符も単一引用符として扱うため同様に重ねる
+pub fn quote(s: &str) -> String {
+    let mut ret = String::with_capacity(s.len() + 2);
+    ret.push('\'');
+    for c in s.chars() {
+        if matches!(c, '\'' | '\u{2018}' | '\u{2019}' | '\u{201A}' | '\u{201B}') {
+            ret.push(c);
+        }
+        ret.push(c);
+    }
+    ret.push('\'');
+    ret
+}
@@ -0,0 +1,172 @@
+use super::{DataType, Error, Result, Value};
+use serde::{Deserialize, Serialize};
+
+/// 1 つの値に詰め込まれたフラグの位置
+/// 数値はリトルエンディアンのバイト列として扱う
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum BitField {
+    /// 先頭からのビット番号
+    Bit(u32),
+    /// バイト位置とその中のマスク (マスクのビットをまとめて切り替える)
+    Byte { offset: usize, mask: u8 },
+}
+
+impl BitField {
+    /// 値が存在しない場合に書き換えの元にする値 (数値型は 0、それ以外は `None`)
+    pub fn initial(data_type: DataType) -> Option<Value> {
+        match data_type {
+            DataType::DWord => Some(Value::DWord(0)),
+            DataType::QWord => Some(Value::QWord(0)),
+            _ => None,
+        }
+    }
+
+    /// バイト位置とマスク
+    pub fn locate(&self) -> (usize, u8) {
+        match *self {
+            Self::Bit(n) => ((n / 8) as usize, 1u8 << (n % 8)),
+            Self::Byte { offset, mask } => (offset, mask),
+        }
+    }
+
+    /// マスクのビットがすべて立っているか
+    pub fn get(&self, value: &Value) -> Result<bool> {
+        let (offset, mask) = self.locate();
+        let bytes = bytes_of(value)?;
+        let b = bytes
+            .get(offset)
+            .ok_or(Error::BitFieldOutOfRange(offset, bytes.len()))?;
+        Ok(b & mask == mask)
+    }
+
+    /// マスク以外のビットはそのままにした新しい値を返す
+    pub fn set(&self, value: &Value, on: bool) -> Result<Value> {
+        let (offset, mask) = self.locate();
+        let mut bytes = bytes_of(value)?;
+        let len = bytes.len();
+        let b = bytes
+            .get_mut(offset)
+            .ok_or(Error::BitFieldOutOfRange(offset, len))?;
+        if on {
+            *b |= mask;
+        } else {
+            *b &= !mask;
+        }
+
+        let ret = match value {
+            Value::DWord(_) => {
+                Value::DWord(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+            }
+            Value::QWord(_) => {
+                let mut buf = [0u8; 8];
+                buf.copy_from_slice(&bytes);
+                Value::QWord(u64::from_le_bytes(buf))
+            }
+            _ => Value::Binary(bytes),
+        };
+        Ok(ret)
+    }
+}
+
+fn bytes_of(value: &Value) -> Result<Vec<u8>> {
+    match value {
+        Value::Binary(_) | Value::DWord(_) | Value::QWord(_) => Ok(value.to_bytes()),
+        v => Err(Error::UnexpectedDataType((
+            "REG_BINARY",
+            v.data_type().str(),
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// ShellState の fNoConfirmRecycle
+    const NO_CONFIRM_RECYCLE: BitField = BitField::Byte {
+        offset: 4,
+        mask: 0x04,
+    };
+
+    #[test]
+    fn locates_bits_and_bytes() {
+        assert_eq!(BitField::Bit(0).locate(), (0, 0x01));
+        assert_eq!(BitField::Bit(13).locate(), (1, 0x20));
+        assert_eq!(NO_CONFIRM_RECYCLE.locate(), (4, 0x04));
+    }
+
+    #[test]
+    fn sets_shell_state_byte_and_keeps_the_rest() {
+        let state = Value::Binary(vec![0x24, 0x00, 0x00, 0x00, 0x33, 0x28, 0x00, 0x00]);
+        assert!(!NO_CONFIRM_RECYCLE.get(&state).unwrap());
+
+        let on = NO_CONFIRM_RECYCLE.set(&state, true).unwrap();
+        assert_eq!(
+            on,
+            Value::Binary(vec![0x24, 0x00, 0x00, 0x00, 0x37, 0x28, 0x00, 0x00])
+        );
+        assert!(NO_CONFIRM_RECYCLE.get(&on).unwrap());
+        assert_eq!(NO_CONFIRM_RECYCLE.set(&on, false).unwrap(), state);
+        // 既に立っている場合は変わらない
+        assert_eq!(NO_CONFIRM_RECYCLE.set(&on, true).unwrap(), on);
+    }
+
+    #[test]
+    fn sets_dword_and_qword_bits() {
+        let field = BitField::Bit(9);
+        let value = Value::DWord(0xF0F0_00FF);
+        assert!(!field.get(&value).unwrap());
+        assert_eq!(field.set(&value, true).unwrap(), Value::DWord(0xF0F0_02FF));
+        assert_eq!(
+            field.set(&Value::DWord(0xFFFF_FFFF), false).unwrap(),
+            Value::DWord(0xFFFF_FDFF)
+        );
+
+        let field = BitField::Bit(40);
+        assert_eq!(
+            field
+                .set(&Value::QWord(0x8000_0000_0000_0001), true)
+                .unwrap(),
+            Value::QWord(0x8000_0100_0000_0001)
+        );
+        // マスクのビットがすべて立っている場合だけオン
+        let field = BitField::Byte {
+            offset: 0,
+            mask: 0x03,
+        };
+        assert!(!field.get(&Value::DWord(0x01)).unwrap());
+        assert!(field.get(&Value::DWord(0x03)).unwrap());
+    }
+
+    #[test]
+    fn missing_numbers_start_from_zero() {
+        let field = BitField::Bit(3);
+        let dword = BitField::initial(DataType::DWord).unwrap();
+        assert_eq!(field.set(&dword, true).unwrap(), Value::DWord(0x08));
+        let qword = BitField::initial(DataType::QWord).unwrap();
+        assert_eq!(field.set(&qword, true).unwrap(), Value::QWord(0x08));
+        // バイナリは長さが分からないため作らない
+        assert_eq!(BitField::initial(DataType::Binary), None);
+    }
+
+    #[test]
+    fn rejects_short_data_and_other_types() {
+        let short = Value::Binary(vec![0x24, 0x00, 0x00, 0x00]);
+        assert!(matches!(
+            NO_CONFIRM_RECYCLE.get(&short),
+            Err(Error::BitFieldOutOfRange(4, 4))
+        ));
+        assert!(matches!(
+            NO_CONFIRM_RECYCLE.set(&Value::Binary(Vec::new()), true),
+            Err(Error::BitFieldOutOfRange(4, 0))
+        ));
+        assert!(matches!(
+            BitField::Bit(32).set(&Value::DWord(0), true),
+            Err(Error::BitFieldOutOfRange(4, 4))
+        ));
+        assert!(matches!(
+            BitField::Bit(0).get(&Value::String("1".into())),
+            Err(Error::UnexpectedDataType(_))
+        ));
+    }
+}
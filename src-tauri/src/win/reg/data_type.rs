@@ -1,9 +1,10 @@
+use serde::{Deserialize, Serialize};
 use windows::Win32::System::Registry::REG_VALUE_TYPE;
 use windows::Win32::System::Registry::{
     REG_BINARY, REG_DWORD, REG_EXPAND_SZ, REG_MULTI_SZ, REG_QWORD, REG_SZ,
 };
 
-#[derive(Debug, PartialEq, Clone, Copy)]
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
 pub enum DataType {
     Binary,
     DWord,
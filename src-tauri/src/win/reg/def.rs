@@ -1,17 +1,41 @@
-use super::DataType;
-use windows::Win32::System::Registry::{HKEY, HKEY_CURRENT_USER, HKEY_USERS};
+use super::{view, DataType, Target, View};
+use serde::{Deserialize, Serialize};
+use windows::Win32::System::Registry::{HKEY, HKEY_CURRENT_USER, HKEY_LOCAL_MACHINE, HKEY_USERS};
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Root {
     CurrentUser,
     Users,
+    LocalMachine,
+    /// 対象のユーザー (`Target` で HKCU か HKU\<SID> に解決する。解決前は現在のユーザー)
+    User,
+}
+
+impl Root {
+    pub fn hkey(&self) -> HKEY {
+        match self {
+            Root::CurrentUser | Root::User => HKEY_CURRENT_USER,
+            Root::Users => HKEY_USERS,
+            Root::LocalMachine => HKEY_LOCAL_MACHINE,
+        }
+    }
+
+    /// `.reg` ファイルなどで使われる正式名
+    pub fn long_name(&self) -> &'static str {
+        match self {
+            Root::CurrentUser | Root::User => "HKEY_CURRENT_USER",
+            Root::Users => "HKEY_USERS",
+            Root::LocalMachine => "HKEY_LOCAL_MACHINE",
+        }
+    }
 }
 
 impl std::fmt::Display for Root {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let root = match self {
-            Root::CurrentUser => "HKCU",
+            Root::CurrentUser | Root::User => "HKCU",
             Root::Users => "HKU",
+            Root::LocalMachine => "HKLM",
         };
 
         root.fmt(f)
@@ -23,17 +47,20 @@ impl From<HKEY> for Root {
         match value {
             HKEY_CURRENT_USER => Self::CurrentUser,
             HKEY_USERS => Self::Users,
+            HKEY_LOCAL_MACHINE => Self::LocalMachine,
             _ => panic!(),
         }
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RegDef {
     pub root: Root,
     pub sub_key: String,
     pub value_name: String,
     pub data_type: DataType,
+    #[serde(default)]
+    pub view: View,
 }
 
 impl RegDef {
@@ -48,9 +75,35 @@ impl RegDef {
             sub_key: sub_key.into(),
             value_name: value_name.into(),
             data_type,
+            view: View::Native,
         }
     }
 
+    /// ユーザーごとのキーを対象のユーザーのキーに置き換える
+    pub fn for_target(&self, target: &Target) -> Self {
+        let (root, sub_key) = target.resolve(self.root, &self.sub_key);
+        Self {
+            root,
+            sub_key,
+            ..self.clone()
+        }
+    }
+
+    /// 同じ値を指しているか (キーのパスは大文字小文字を区別しない)
+    pub fn same_target(&self, other: &RegDef) -> bool {
+        self.root == other.root
+            && self.sub_key.eq_ignore_ascii_case(&other.sub_key)
+            && self.value_name == other.value_name
+            && self.data_type == other.data_type
+            && self.view == other.view
+    }
+
+    /// WOW64 のビューを指定する
+    pub fn in_view(mut self, view: View) -> Self {
+        self.view = view;
+        self
+    }
+
     pub fn hkcu(
         sub_key: impl Into<String>,
         value_name: impl Into<String>,
@@ -67,11 +120,35 @@ impl RegDef {
         Self::new(Root::Users, sub_key, value_name, data_type)
     }
 
+    /// ユーザーごとのキー (`sub_key` はユーザーのハイブからの相対パス)
+    pub fn user(
+        sub_key: impl Into<String>,
+        value_name: impl Into<String>,
+        data_type: DataType,
+    ) -> Self {
+        Self::new(Root::User, sub_key, value_name, data_type)
+    }
+
+    pub fn hklm(
+        sub_key: impl Into<String>,
+        value_name: impl Into<String>,
+        data_type: DataType,
+    ) -> Self {
+        Self::new(Root::LocalMachine, sub_key, value_name, data_type)
+    }
+
     pub fn root(&self) -> HKEY {
-        match self.root {
-            Root::CurrentUser => HKEY_CURRENT_USER,
-            Root::Users => HKEY_USERS,
-        }
+        self.root.hkey()
+    }
+
+    /// 64 ビットのビューから見た、値を読み取るキーのパス
+    pub fn physical_sub_key(&self) -> String {
+        view::physical_sub_key(
+            self.root,
+            &self.sub_key,
+            self.view,
+            cfg!(target_pointer_width = "64"),
+        )
     }
 }
 
@@ -0,0 +1,106 @@
+use std::collections::HashMap;
+
+/// REG_EXPAND_SZ の `%NAME%` を展開するときに参照する環境変数
+pub trait Environment {
+    /// 名前は大文字小文字を区別しない
+    fn var(&self, name: &str) -> Option<String>;
+}
+
+/// このプロセスの環境変数
+pub struct ProcessEnvironment;
+
+impl Environment for ProcessEnvironment {
+    fn var(&self, name: &str) -> Option<String> {
+        std::env::var_os(name).map(|x| x.to_string_lossy().into_owned())
+    }
+}
+
+/// 固定の環境変数 (別のユーザーの値や確認用)
+impl Environment for HashMap<String, String> {
+    fn var(&self, name: &str) -> Option<String> {
+        self.iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.clone())
+    }
+}
+
+/// `%NAME%` を環境変数の値に置き換える
+/// ExpandEnvironmentStrings と同じく、設定されていない変数は `%NAME%` のまま残す
+pub fn expand(text: &str, env: &impl Environment) -> String {
+    expand_checked(text, env).0
+}
+
+/// `expand` と同じく置き換え、設定されていない変数の名前も返す
+pub fn expand_checked(text: &str, env: &impl Environment) -> (String, Vec<String>) {
+    let mut ret = String::with_capacity(text.len());
+    let mut unset = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find('%') {
+        ret.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let Some(end) = after.find('%') else {
+            rest = &rest[start..];
+            break;
+        };
+        let name = &after[..end];
+        match env.var(name).filter(|_| !name.is_empty()) {
+            Some(v) => ret.push_str(&v),
+            None => {
+                if !name.is_empty() {
+                    unset.push(name.to_string());
+                }
+                ret.push_str(&rest[start..start + end + 2]);
+            }
+        }
+        rest = &after[end + 1..];
+    }
+    ret.push_str(rest);
+    (ret, unset)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn env() -> HashMap<String, String> {
+        HashMap::from([
+            ("USERPROFILE".to_string(), r"C:\Users\test".to_string()),
+            ("Empty".to_string(), String::new()),
+        ])
+    }
+
+    #[test]
+    fn replaces_set_variables() {
+        assert_eq!(expand(r"%USERPROFILE%\bin", &env()), r"C:\Users\test\bin");
+        assert_eq!(expand("a%EMPTY%b", &env()), "ab");
+        assert_eq!(expand("no variables", &env()), "no variables");
+    }
+
+    #[test]
+    fn names_are_case_insensitive() {
+        assert_eq!(
+            expand(r"%userprofile%;%UserProfile%", &env()),
+            r"C:\Users\test;C:\Users\test"
+        );
+    }
+
+    #[test]
+    fn keeps_unset_variables() {
+        let (text, unset) = expand_checked(r"%MISSING%\%USERPROFILE%", &env());
+        assert_eq!(text, r"%MISSING%\C:\Users\test");
+        assert_eq!(unset, vec!["MISSING".to_string()]);
+    }
+
+    #[test]
+    fn keeps_double_percent() {
+        let (text, unset) = expand_checked("100%%", &env());
+        assert_eq!(text, "100%%");
+        assert!(unset.is_empty());
+    }
+
+    #[test]
+    fn keeps_unmatched_trailing_percent() {
+        assert_eq!(expand("50%", &env()), "50%");
+        assert_eq!(expand(r"%USERPROFILE%\100%", &env()), r"C:\Users\test\100%");
+    }
+}
@@ -1,4 +1,4 @@
-use std::num::ParseIntError;
+use std::num::{ParseIntError, TryFromIntError};
 use windows::Win32::Foundation::WIN32_ERROR;
 use windows::Win32::System::Registry::REG_VALUE_TYPE;
 
@@ -13,6 +13,12 @@ pub enum Error {
     UnknownDataType(REG_VALUE_TYPE),
     UnexpectedDataType((&'static str, &'static str)), // expected, actual
     ParseIntError(ParseIntError),
+    OutOfRange(TryFromIntError),
+    TooManyWatches(usize),
+    NothingToWatch,
+    InvalidBinary(String),
+    BitFieldOutOfRange(usize, usize),   // offset, len
+    MalformedData(&'static str, usize), // data type, len
 }
 
 impl std::error::Error for Error {}
@@ -31,6 +37,14 @@ impl std::fmt::Display for Error {
                 )
             }
             Self::ParseIntError(e) => e.fmt(f),
+            Self::OutOfRange(e) => e.fmt(f),
+            Self::TooManyWatches(n) => write!(f, "Too many keys to watch ({n})"),
+            Self::NothingToWatch => write!(f, "No keys to watch"),
+            Self::InvalidBinary(v) => write!(f, "Invalid binary value ({v})"),
+            Self::MalformedData(t, len) => write!(f, "Malformed {t} data ({len} bytes)"),
+            Self::BitFieldOutOfRange(offset, len) => {
+                write!(f, "Bit field out of range (offset: {offset}, len: {len})")
+            }
         }
     }
 }
@@ -41,6 +55,18 @@ impl From<ParseIntError> for Error {
     }
 }
 
+impl From<TryFromIntError> for Error {
+    fn from(value: TryFromIntError) -> Self {
+        Error::OutOfRange(value)
+    }
+}
+
+impl From<windows::core::Error> for Error {
+    fn from(value: windows::core::Error) -> Self {
+        Error::Win32Error(value)
+    }
+}
+
 impl From<WIN32_ERROR> for Error {
     fn from(value: WIN32_ERROR) -> Self {
         Error::Win32Error(windows::core::Error::from(value))
@@ -1,12 +1,18 @@
-use super::{DataType, Error, Result, Root};
-use windows::core::HSTRING;
-use windows::Win32::Foundation::{ERROR_SUCCESS, WIN32_ERROR};
+use super::{DataType, Error, Result, Root, Value};
+use windows::core::{HSTRING, PWSTR};
+use windows::Win32::Foundation::{
+    ERROR_MORE_DATA, ERROR_NO_MORE_ITEMS, ERROR_SUCCESS, WIN32_ERROR,
+};
 use windows::Win32::System::Registry::REG_SAM_FLAGS;
 use windows::Win32::System::Registry::{
-    RegCloseKey, RegCreateKeyExW, RegOpenKeyExW, RegQueryValueExW, RegSetValueExW,
+    RegCloseKey, RegCreateKeyExW, RegDeleteValueW, RegEnumKeyExW, RegEnumValueW, RegOpenKeyExW,
+    RegQueryValueExW, RegSetValueExW,
 };
 use windows::Win32::System::Registry::{HKEY, REG_OPTION_NON_VOLATILE, REG_VALUE_TYPE};
 
+/// 値が書き換わり続けている場合に読み直す上限
+const MAX_QUERY_ATTEMPTS: usize = 4;
+
 pub(super) struct KeyHandler {
     key: HKEY,
 }
@@ -33,6 +39,10 @@ impl KeyHandler {
         Ok(Self { key: k })
     }
 
+    pub fn raw(&self) -> HKEY {
+        self.key
+    }
+
     pub fn create_or_open(root: HKEY, sub_key: &str, samdesired: REG_SAM_FLAGS) -> Result<Self> {
         let mut k: HKEY = HKEY::default();
         unsafe {
@@ -82,95 +92,158 @@ impl KeyHandler {
         Ok(Some(ret))
     }
 
-    pub fn get_dword(&self, value_name: &str) -> Result<u32> {
-        let vi = self.get_value_info(value_name)?;
-        if let Some(vi) = vi {
-            if vi.data_type() != DataType::DWord {
-                return Err(Error::UnexpectedDataType((
-                    "REG_DWORD",
-                    vi.data_type().str(),
-                )));
+    /// サブキーの名前を列挙する
+    pub fn sub_keys(&self) -> Result<Vec<String>> {
+        let mut ret = Vec::new();
+        // キーの名前は 255 文字まで
+        let mut buf = [0u16; 256];
+        for index in 0.. {
+            let mut len = buf.len() as u32;
+            let r = unsafe {
+                RegEnumKeyExW(
+                    self.key,
+                    index,
+                    PWSTR(buf.as_mut_ptr()),
+                    &mut len,
+                    None,
+                    PWSTR::null(),
+                    None,
+                    None,
+                )
+            };
+            match r {
+                ERROR_SUCCESS => ret.push(String::from_utf16_lossy(&buf[..len as usize])),
+                ERROR_NO_MORE_ITEMS => break,
+                e => return Err(Error::from(e)),
             }
+        }
+        Ok(ret)
+    }
 
-            let buf = self.get_value(value_name, &vi)?;
-            let ret = u32::from_le_bytes([buf[0], buf[1], buf[2], buf[3]]);
-            Ok(ret)
-        } else {
-            Err(Error::ValueNameNotFound(value_name.into()))
+    /// 値の名前を列挙する
+    pub fn value_names(&self) -> Result<Vec<String>> {
+        let mut ret = Vec::new();
+        // 値の名前は 16383 文字まで
+        let mut buf = vec![0u16; 16384];
+        for index in 0.. {
+            let mut len = buf.len() as u32;
+            let r = unsafe {
+                RegEnumValueW(
+                    self.key,
+                    index,
+                    PWSTR(buf.as_mut_ptr()),
+                    &mut len,
+                    None,
+                    None,
+                    None,
+                    None,
+                )
+            };
+            match r {
+                ERROR_SUCCESS => ret.push(String::from_utf16_lossy(&buf[..len as usize])),
+                ERROR_NO_MORE_ITEMS => break,
+                e => return Err(Error::from(e)),
+            }
+        }
+        Ok(ret)
+    }
+
+    /// 値を読み取る (値が存在しない場合は `None`)
+    pub fn get(&self, value_name: &str) -> Result<Option<Value>> {
+        match self.query(value_name)? {
+            Some((data_type, buf)) => Ok(Some(Value::from_bytes(data_type, &buf)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// 型を確認して値を読み取る
+    pub fn get_typed(&self, value_name: &str, data_type: DataType) -> Result<Value> {
+        match self.query(value_name)? {
+            Some((t, buf)) if t == data_type => Value::from_bytes(t, &buf),
+            Some((t, _)) => Err(Error::UnexpectedDataType((data_type.str(), t.str()))),
+            None => Err(Error::ValueNameNotFound(value_name.into())),
+        }
+    }
+
+    pub fn get_dword(&self, value_name: &str) -> Result<u32> {
+        match self.get_typed(value_name, DataType::DWord)? {
+            Value::DWord(v) => Ok(v),
+            v => Err(Error::UnexpectedDataType((
+                "REG_DWORD",
+                v.data_type().str(),
+            ))),
         }
     }
 
     pub fn get_string(&self, value_name: &str) -> Result<String> {
-        let vi = self.get_value_info(value_name)?;
-        if let Some(vi) = vi {
-            if vi.data_type() != DataType::String {
-                return Err(Error::UnexpectedDataType(("REG_SZ", vi.data_type().str())));
-            }
+        match self.get_typed(value_name, DataType::String)? {
+            Value::String(v) => Ok(v),
+            v => Err(Error::UnexpectedDataType(("REG_SZ", v.data_type().str()))),
+        }
+    }
 
-            let buf = self.get_value(value_name, &vi)?;
-            let utf16_data: Vec<u16> = buf
-                .chunks_exact(2)
-                .map(|chunk| u16::from_le_bytes([chunk[0], chunk[1]]))
-                .collect();
-            let ret = String::from_utf16_lossy(&utf16_data)
-                .trim_end_matches('\0')
-                .to_string();
-            Ok(ret)
-        } else {
-            Err(Error::ValueNameNotFound(value_name.into()))
+    pub fn get_binary(&self, value_name: &str) -> Result<Vec<u8>> {
+        match self.get_typed(value_name, DataType::Binary)? {
+            Value::Binary(v) => Ok(v),
+            v => Err(Error::UnexpectedDataType((
+                "REG_BINARY",
+                v.data_type().str(),
+            ))),
         }
     }
 
-    fn get_value(&self, value_name: &str, vi: &ValueInfo) -> Result<Vec<u8>> {
-        let mut len = vi.len();
-        let mut buf = vec![0u8; len as usize];
-        let mut data_type = vi.data_type().value();
+    /// 値の型と生のデータ (値が存在しない場合は `None`)
+    /// サイズを問い合わせてから読み取るまでに値が大きくなった場合は読み直す
+    fn query(&self, value_name: &str) -> Result<Option<(DataType, Vec<u8>)>> {
+        let Some(vi) = self.get_value_info(value_name)? else {
+            return Ok(None);
+        };
 
-        unsafe {
-            let ret = RegQueryValueExW(
-                self.key,
-                &HSTRING::from(value_name),
-                None,
-                Some(&mut data_type),
-                Some(buf.as_mut_ptr()),
-                Some(&mut len),
-            );
+        let name = HSTRING::from(value_name);
+        let mut capacity = vi.len();
+        for _ in 0..MAX_QUERY_ATTEMPTS {
+            let mut buf = vec![0u8; capacity as usize];
+            let mut data_type = REG_VALUE_TYPE(0);
+            let mut len = capacity;
+            let ret = unsafe {
+                RegQueryValueExW(
+                    self.key,
+                    &name,
+                    None,
+                    Some(&mut data_type),
+                    Some(buf.as_mut_ptr()),
+                    Some(&mut len),
+                )
+            };
 
-            if ret != ERROR_SUCCESS {
+            if ret == ERROR_SUCCESS {
+                buf.truncate(len as usize);
+                return Ok(Some((DataType::try_from(data_type)?, buf)));
+            } else if ret == ERROR_MORE_DATA {
+                capacity = len.max(capacity.saturating_mul(2));
+            } else if check_no_key_error(ret) {
+                return Ok(None);
+            } else {
                 return Err(Error::from(ret));
             }
         }
 
-        Ok(buf)
+        Err(Error::from(ERROR_MORE_DATA))
     }
 
-    pub fn set_dword(&self, value_name: &str, value: u32) -> Result<()> {
+    pub fn set(&self, value_name: &str, value: &Value) -> Result<()> {
+        let data_type = value.data_type();
         let vi = self.get_value_info(value_name)?;
         if let Some(vi) = vi {
-            if vi.data_type() != DataType::DWord {
+            if vi.data_type() != data_type {
                 return Err(Error::UnexpectedDataType((
-                    "REG_DWORD",
+                    data_type.str(),
                     vi.data_type().str(),
                 )));
             }
         }
 
-        let buf = value.to_ne_bytes();
-        self.set_value(value_name, DataType::DWord, &buf)?;
-        Ok(())
-    }
-
-    pub fn set_string(&self, value_name: &str, value: &str) -> Result<()> {
-        let vi = self.get_value_info(value_name)?;
-        if let Some(vi) = vi {
-            if vi.data_type() != DataType::String {
-                return Err(Error::UnexpectedDataType(("REG_SZ", vi.data_type().str())));
-            }
-        }
-
-        let data_utf16: Vec<u16> = value.encode_utf16().chain(std::iter::once(0)).collect();
-        let buf: Vec<u8> = data_utf16.iter().flat_map(|&u| u.to_le_bytes()).collect();
-        self.set_value(value_name, DataType::String, &buf)
+        self.set_value(value_name, data_type, &value.to_bytes())
     }
 
     fn set_value(&self, value_name: &str, data_type: DataType, buffer: &[u8]) -> Result<()> {
@@ -193,6 +266,21 @@ impl KeyHandler {
         }
         Ok(())
     }
+
+    pub fn delete_value(&self, value_name: &str) -> Result<()> {
+        unsafe {
+            let ret = RegDeleteValueW(self.key, &HSTRING::from(value_name));
+
+            if ret != ERROR_SUCCESS {
+                if check_no_key_error(ret) {
+                    return Err(Error::ValueNameNotFound(value_name.into()));
+                } else {
+                    return Err(Error::from(ret));
+                }
+            }
+        }
+        Ok(())
+    }
 }
 
 impl Drop for KeyHandler {
@@ -1,12 +1,26 @@
+mod bit_field;
 mod data_type;
 mod def;
+mod env;
 mod error;
 mod key_handler;
+mod notify;
+mod open_key;
 mod registry;
+mod target;
 mod value;
+mod verify;
+mod view;
 
+pub use bit_field::BitField;
 pub use data_type::DataType;
 pub use def::*;
+pub use env::{expand, expand_checked, Environment, ProcessEnvironment};
 pub use error::*;
+pub use notify::KeyNotifier;
+pub use open_key::OpenKey;
 pub use registry::*;
+pub use target::Target;
 pub use value::Value;
+pub use verify::{virtual_store_key, WriteOutcome};
+pub use view::{physical_sub_key, redirect, View};
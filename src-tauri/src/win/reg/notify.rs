@@ -0,0 +1,134 @@
+use super::key_handler::KeyHandler;
+use super::{Error, Result, View};
+use windows::Win32::Foundation::{CloseHandle, ERROR_SUCCESS, HANDLE, WAIT_OBJECT_0};
+use windows::Win32::System::Registry::{
+    RegNotifyChangeKeyValue, HKEY, KEY_NOTIFY, REG_NOTIFY_CHANGE_LAST_SET, REG_NOTIFY_CHANGE_NAME,
+};
+use windows::Win32::System::Threading::{CreateEventW, WaitForMultipleObjects, INFINITE};
+
+/// WaitForMultipleObjects で待機できるハンドル数の上限
+const MAX_WATCHES: usize = 64;
+
+struct Watch {
+    handler: KeyHandler,
+    event: HANDLE,
+    subtree: bool,
+}
+
+impl Watch {
+    fn new(root: HKEY, sub_key: &str, view: View) -> Result<Self> {
+        // まだ存在しないキーは、存在する一番近い親キーのサブツリーを監視する
+        let mut key = sub_key;
+        let mut subtree = false;
+        let handler = loop {
+            match KeyHandler::open(root, key, KEY_NOTIFY | view.sam()) {
+                Ok(h) => break h,
+                Err(Error::SubkeyNotFound(_)) if !key.is_empty() => {
+                    key = key.rsplit_once('\\').map(|x| x.0).unwrap_or("");
+                    subtree = true;
+                }
+                Err(e) => return Err(e),
+            }
+        };
+
+        let event = unsafe { CreateEventW(None, false, false, None)? };
+        let ret = Self {
+            handler,
+            event,
+            subtree,
+        };
+        ret.arm()?;
+        Ok(ret)
+    }
+
+    /// 通知は 1 回で解除されるため、受け取るたびに再登録する
+    fn arm(&self) -> Result<()> {
+        unsafe {
+            let ret = RegNotifyChangeKeyValue(
+                self.handler.raw(),
+                self.subtree,
+                REG_NOTIFY_CHANGE_NAME | REG_NOTIFY_CHANGE_LAST_SET,
+                self.event,
+                true,
+            );
+
+            if ret != ERROR_SUCCESS {
+                return Err(Error::from(ret));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Drop for Watch {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = CloseHandle(self.event);
+        }
+    }
+}
+
+/// RegNotifyChangeKeyValue で複数のキーの変更を待ち受ける
+pub struct KeyNotifier {
+    targets: Vec<(HKEY, String, View)>,
+    /// 監視をやめたキーは `None`
+    watches: Vec<Option<Watch>>,
+}
+
+impl KeyNotifier {
+    /// 両方のビューを指定したキーは読み取りに使うビューを監視する
+    pub fn new(targets: &[(HKEY, String, View)]) -> Result<Self> {
+        if targets.len() > MAX_WATCHES {
+            return Err(Error::TooManyWatches(targets.len()));
+        }
+
+        let watches = targets
+            .iter()
+            .map(|(root, sub_key, view)| Watch::new(*root, sub_key, view.targets()[0]).map(Some))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self {
+            targets: targets.to_vec(),
+            watches,
+        })
+    }
+
+    /// いずれかのキーが変更されるまで待機し、そのキーの `targets` 上の位置を返す
+    /// 通知は 1 回で解除されるため、続けて待機する前に `arm` で再登録する
+    pub fn wait(&self) -> Result<usize> {
+        let (indices, events): (Vec<usize>, Vec<HANDLE>) = self
+            .watches
+            .iter()
+            .enumerate()
+            .filter_map(|(i, x)| x.as_ref().map(|x| (i, x.event)))
+            .unzip();
+        if events.is_empty() {
+            return Err(Error::NothingToWatch);
+        }
+
+        let ret = unsafe { WaitForMultipleObjects(&events, false, INFINITE) };
+        let pos = ret.0.wrapping_sub(WAIT_OBJECT_0.0) as usize;
+        match indices.get(pos) {
+            Some(index) => Ok(*index),
+            None => Err(Error::from(windows::core::Error::from_win32())),
+        }
+    }
+
+    pub fn arm(&self, index: usize) -> Result<()> {
+        match self.watches.get(index) {
+            Some(Some(watch)) => watch.arm(),
+            _ => Err(Error::NothingToWatch),
+        }
+    }
+
+    /// キーを開き直して監視を続ける (削除されたキーは存在する親キーを監視する)
+    /// 開けない場合はそのキーの監視をやめる
+    pub fn reopen(&mut self, index: usize) -> Result<()> {
+        let Some((root, sub_key, view)) = self.targets.get(index) else {
+            return Err(Error::NothingToWatch);
+        };
+        // 古いハンドルを先に閉じる
+        self.watches[index] = None;
+        self.watches[index] = Some(Watch::new(*root, sub_key, view.targets()[0])?);
+        Ok(())
+    }
+}
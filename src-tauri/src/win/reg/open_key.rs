@@ -0,0 +1,161 @@
+use super::key_handler::KeyHandler;
+use super::{DataType, Error, Result, Root, Value, View};
+use windows::Win32::System::Registry::HKEY;
+use windows::Win32::System::Registry::{KEY_READ, KEY_WRITE};
+
+/// 開いたままのキー
+/// 同じキーにある複数の値を、キーを開き直さずに読み書きする
+/// 両方のビューを指定した場合は、読み取りは先頭のビューだけ、書き込みはすべてのビューに行う
+pub struct OpenKey {
+    root: HKEY,
+    sub_key: String,
+    view: View,
+    handlers: Vec<KeyHandler>,
+}
+
+impl OpenKey {
+    /// 読み取り用に開く
+    pub fn open(root: HKEY, sub_key: impl Into<String>, view: View) -> Result<Self> {
+        let sub_key = sub_key.into();
+        let handler = KeyHandler::open(root, &sub_key, KEY_READ | view.targets()[0].sam())?;
+        Ok(Self {
+            root,
+            sub_key,
+            view,
+            handlers: vec![handler],
+        })
+    }
+
+    /// 読み書き用に開く (キーが無い場合は作成する)
+    pub fn create(root: HKEY, sub_key: impl Into<String>, view: View) -> Result<Self> {
+        let sub_key = sub_key.into();
+        let handlers = view
+            .targets()
+            .iter()
+            .map(|x| KeyHandler::create_or_open(root, &sub_key, KEY_WRITE | KEY_READ | x.sam()))
+            .collect::<Result<_>>()?;
+        Ok(Self {
+            root,
+            sub_key,
+            view,
+            handlers,
+        })
+    }
+
+    /// 値を削除するために開く (キーが無い場合は作成しない)
+    /// 先頭以外のビューにキーが無い場合は、そのビューを飛ばす
+    pub fn open_writable(root: HKEY, sub_key: impl Into<String>, view: View) -> Result<Self> {
+        let sub_key = sub_key.into();
+        let mut handlers = Vec::with_capacity(view.targets().len());
+        for (i, x) in view.targets().iter().enumerate() {
+            match KeyHandler::open(root, &sub_key, KEY_WRITE | KEY_READ | x.sam()) {
+                Ok(h) => handlers.push(h),
+                Err(Error::SubkeyNotFound(_)) if i > 0 => (),
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(Self {
+            root,
+            sub_key,
+            view,
+            handlers,
+        })
+    }
+
+    /// ログに出力するキーのパス
+    pub fn path(&self) -> String {
+        if self.view.is_native() {
+            format!("{}\\{}", Root::from(self.root), self.sub_key)
+        } else {
+            format!(
+                "{}\\{} ({})",
+                Root::from(self.root),
+                self.sub_key,
+                self.view
+            )
+        }
+    }
+
+    pub fn get_value(&self, value_name: &str, data_type: DataType) -> Result<Value> {
+        let ret = self.handlers[0].get_typed(value_name, data_type);
+        match &ret {
+            Ok(v) => {
+                tracing::debug!(key = %self.path(), name = value_name, value = %v, "read value")
+            }
+            Err(e) => {
+                tracing::debug!(key = %self.path(), name = value_name, error = %e, "read value failed")
+            }
+        }
+
+        ret
+    }
+
+    /// 値が存在しない場合は `None` を返す
+    pub fn find_value(&self, value_name: &str, data_type: DataType) -> Result<Option<Value>> {
+        match self.get_value(value_name, data_type) {
+            Ok(v) => Ok(Some(v)),
+            Err(Error::ValueNameNotFound(_)) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn write_value(&self, value_name: &str, value: &Value) -> Result<()> {
+        let old = self.old_value(value_name);
+        let ret = self
+            .handlers
+            .iter()
+            .try_for_each(|x| x.set(value_name, value));
+        match &ret {
+            Ok(_) => tracing::info!(
+                key = %self.path(),
+                name = value_name,
+                old = ?old,
+                new = %value,
+                "write value"
+            ),
+            Err(e) => tracing::warn!(
+                key = %self.path(),
+                name = value_name,
+                old = ?old,
+                new = %value,
+                error = %e,
+                "write value failed"
+            ),
+        }
+        ret
+    }
+
+    pub fn delete_value(&self, value_name: &str) -> Result<()> {
+        let old = self.old_value(value_name);
+        // 先頭以外のビューに値が無いのはエラーにしない
+        let ret = self.handlers[0].delete_value(value_name).and_then(|_| {
+            self.handlers[1..]
+                .iter()
+                .try_for_each(|x| match x.delete_value(value_name) {
+                    Err(Error::ValueNameNotFound(_)) => Ok(()),
+                    ret => ret,
+                })
+        });
+        match &ret {
+            Ok(_) => {
+                tracing::info!(key = %self.path(), name = value_name, old = ?old, "delete value")
+            }
+            Err(e) => tracing::warn!(
+                key = %self.path(),
+                name = value_name,
+                old = ?old,
+                error = %e,
+                "delete value failed"
+            ),
+        }
+        ret
+    }
+
+    /// ログに残す変更前の値 (読み取れない場合は `None`)
+    fn old_value(&self, value_name: &str) -> Option<String> {
+        self.handlers[0]
+            .get(value_name)
+            .ok()?
+            .map(|x| x.to_string())
+    }
+}
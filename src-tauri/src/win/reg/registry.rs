@@ -1,12 +1,13 @@
 use super::key_handler::KeyHandler;
-use super::{DataType, Result, Value};
-use windows::Win32::System::Registry::HKEY;
-use windows::Win32::System::Registry::{KEY_READ, KEY_WRITE};
+use super::{physical_sub_key, virtual_store_key, WriteOutcome};
+use super::{BitField, DataType, Error, OpenKey, RegDef, Result, Root, Value, View};
+use windows::Win32::System::Registry::{HKEY, HKEY_CURRENT_USER, KEY_READ, REG_SAM_FLAGS};
 
 pub struct Registry {
     root: HKEY,
     sub_key: String,
     value_name: String,
+    view: View,
 }
 
 impl Registry {
@@ -15,51 +16,196 @@ impl Registry {
             root,
             sub_key: sub_key.into(),
             value_name: value_name.into(),
+            view: View::Native,
         }
     }
 
+    pub fn from_def(def: &RegDef) -> Self {
+        Self::new(def.root(), &def.sub_key, &def.value_name).in_view(def.view)
+    }
+
+    /// WOW64 のビューを指定する
+    pub fn in_view(mut self, view: View) -> Self {
+        self.view = view;
+        self
+    }
+
+    /// 読み取りに使うビューのアクセス権
+    fn sam(&self) -> REG_SAM_FLAGS {
+        self.view.targets()[0].sam()
+    }
+
+    pub fn key_exists(&self) -> Result<bool> {
+        match KeyHandler::open(self.root, &self.sub_key, KEY_READ | self.sam()) {
+            Ok(_) => Ok(true),
+            Err(Error::SubkeyNotFound(_)) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn create_key(&self) -> Result<()> {
+        let ret = OpenKey::create(self.root, &self.sub_key, self.view);
+        match &ret {
+            Ok(key) => tracing::info!(key = %key.path(), "create key"),
+            Err(e) => tracing::warn!(
+                key = %Root::from(self.root),
+                sub_key = %self.sub_key,
+                view = %self.view,
+                error = %e,
+                "create key failed"
+            ),
+        }
+        ret.map(|_| ())
+    }
+
     pub fn get_value(&self, data_type: DataType) -> Result<Value> {
-        let ret = match data_type {
-            DataType::DWord => Value::DWord(self.get_dword()?),
-            DataType::String => Value::String(self.get_string()?),
-            _ => unimplemented!(),
-        };
+        OpenKey::open(self.root, &self.sub_key, self.view)?.get_value(&self.value_name, data_type)
+    }
 
-        Ok(ret)
+    /// キーや値が存在しない場合は `None` を返す
+    pub fn find_value(&self, data_type: DataType) -> Result<Option<Value>> {
+        match self.get_value(data_type) {
+            Ok(v) => Ok(Some(v)),
+            Err(Error::SubkeyNotFound(_)) | Err(Error::ValueNameNotFound(_)) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// 型を問わずに値を読み取る (キーや値が存在しない場合は `None`)
+    pub fn find_any_value(&self) -> Result<Option<Value>> {
+        match KeyHandler::open(self.root, &self.sub_key, KEY_READ | self.sam()) {
+            Ok(handler) => handler.get(&self.value_name),
+            Err(Error::SubkeyNotFound(_)) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// サブキーの名前 (キーが存在しない場合は空)
+    pub fn sub_keys(&self) -> Result<Vec<String>> {
+        match KeyHandler::open(self.root, &self.sub_key, KEY_READ | self.sam()) {
+            Ok(handler) => handler.sub_keys(),
+            Err(Error::SubkeyNotFound(_)) => Ok(Vec::new()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// キーにある値の名前 (キーが存在しない場合は空。`value_name` は使わない)
+    pub fn value_names(&self) -> Result<Vec<String>> {
+        match KeyHandler::open(self.root, &self.sub_key, KEY_READ | self.sam()) {
+            Ok(handler) => handler.value_names(),
+            Err(Error::SubkeyNotFound(_)) => Ok(Vec::new()),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn get_binary(&self) -> Result<Vec<u8>> {
+        let handler = KeyHandler::open(self.root, &self.sub_key, KEY_READ | self.sam())?;
+        handler.get_binary(&self.value_name)
+    }
+
+    pub fn get_bits(&self, data_type: DataType, field: BitField) -> Result<bool> {
+        field.get(&self.get_value(data_type)?)
+    }
+
+    /// 値を読み取ってからフラグだけを書き換える (値が無い数値型は 0 から始める)
+    pub fn set_bits(&self, data_type: DataType, field: BitField, on: bool) -> Result<()> {
+        self.write_value(&self.bits_value(data_type, field, on)?)
+    }
+
+    /// `set_bits` の後に値を読み直して確認する
+    pub fn set_bits_verified(
+        &self,
+        data_type: DataType,
+        field: BitField,
+        on: bool,
+    ) -> Result<WriteOutcome> {
+        self.write_value_verified(&self.bits_value(data_type, field, on)?)
+    }
+
+    fn bits_value(&self, data_type: DataType, field: BitField, on: bool) -> Result<Value> {
+        let current = self
+            .find_value(data_type)?
+            .or_else(|| BitField::initial(data_type))
+            .ok_or_else(|| Error::ValueNameNotFound(self.value_name.clone()))?;
+        field.set(&current, on)
     }
 
     pub fn get_dword(&self) -> Result<u32> {
-        let handler = KeyHandler::open(self.root, &self.sub_key, KEY_READ)?;
+        let handler = KeyHandler::open(self.root, &self.sub_key, KEY_READ | self.sam())?;
         handler.get_dword(&self.value_name)
     }
 
     pub fn get_string(&self) -> Result<String> {
-        let handler = KeyHandler::open(self.root, &self.sub_key, KEY_READ)?;
+        let handler = KeyHandler::open(self.root, &self.sub_key, KEY_READ | self.sam())?;
         handler.get_string(&self.value_name)
     }
 
     pub fn set_value(&self, data_type: DataType, value: &str) -> Result<()> {
-        match data_type {
-            DataType::DWord => {
-                let value = value.parse::<u32>()?;
-                self.set_dword(value)?;
-            }
-            DataType::String => {
-                self.set_string(value)?;
+        self.write_value(&Value::parse(data_type, value)?)
+    }
+
+    pub fn write_value(&self, value: &Value) -> Result<()> {
+        OpenKey::create(self.root, &self.sub_key, self.view)?.write_value(&self.value_name, value)
+    }
+
+    /// 書き込んだ後に値を読み直し、反映されたかを確認する
+    pub fn write_value_verified(&self, value: &Value) -> Result<WriteOutcome> {
+        let mirror = self.virtual_store_value(value.data_type())?;
+        self.write_value(value)?;
+        self.verify(value, mirror.as_ref())
+    }
+
+    /// UAC の仮想化で書き込みが振り替えられる HKCU の VirtualStore の値 (対象外のキーは `None`)
+    /// `verify` に渡すため書き込む前に読み取っておく
+    pub fn virtual_store_value(&self, data_type: DataType) -> Result<Option<Value>> {
+        let root = Root::from(self.root);
+        let physical = physical_sub_key(
+            root,
+            &self.sub_key,
+            self.view.targets()[0],
+            cfg!(target_pointer_width = "64"),
+        );
+        match virtual_store_key(root, &physical) {
+            Some(key) => {
+                Registry::new(HKEY_CURRENT_USER, key, &self.value_name).find_value(data_type)
             }
-            _ => unimplemented!(),
+            None => Ok(None),
         }
+    }
+
+    /// 書き込んだ値が反映されているかを確認する
+    /// 反映されていない場合は、HKLM への書き込みが HKCU の VirtualStore に振り替えられていないかも確認する
+    /// (`mirror` は書き込む前の `virtual_store_value`)
+    pub fn verify(&self, written: &Value, mirror: Option<&Value>) -> Result<WriteOutcome> {
+        let read_back = self.find_value(written.data_type())?;
+        let root = Root::from(self.root);
+        let virtualized = self.virtual_store_value(written.data_type())?;
 
-        Ok(())
+        let ret = WriteOutcome::classify(written, read_back.as_ref(), mirror, virtualized.as_ref());
+        if !ret.is_applied() {
+            let read_back = read_back.as_ref().map(Value::to_string);
+            tracing::warn!(
+                key = %root,
+                sub_key = %self.sub_key,
+                name = %self.value_name,
+                written = %written,
+                read_back = ?read_back,
+                outcome = %ret,
+                "write not applied"
+            );
+        }
+        Ok(ret)
     }
 
     pub fn set_dword(&self, value: u32) -> Result<()> {
-        let handler = KeyHandler::create_or_open(self.root, &self.sub_key, KEY_WRITE | KEY_READ)?;
-        handler.set_dword(&self.value_name, value)
+        self.write_value(&Value::DWord(value))
     }
 
     pub fn set_string(&self, value: &str) -> Result<()> {
-        let handler = KeyHandler::create_or_open(self.root, &self.sub_key, KEY_WRITE | KEY_READ)?;
-        handler.set_string(&self.value_name, value)
+        self.write_value(&Value::String(value.into()))
+    }
+
+    pub fn delete_value(&self) -> Result<()> {
+        OpenKey::open_writable(self.root, &self.sub_key, self.view)?.delete_value(&self.value_name)
     }
 }
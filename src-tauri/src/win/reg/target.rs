@@ -0,0 +1,34 @@
+use super::Root;
+use serde::{Deserialize, Serialize};
+
+/// ユーザーごとのキー (`Root::User`) を読み書きする対象
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "sid", rename_all = "kebab-case")]
+pub enum Target {
+    /// 現在のユーザー (HKCU)
+    #[default]
+    CurrentUser,
+    /// ログオン中の別のユーザーや、HKU に読み込んだハイブ (HKU\<SID>)
+    Sid(String),
+}
+
+impl Target {
+    /// `Root::User` のキーを実際のルートとパスに置き換える (それ以外はそのまま)
+    pub fn resolve(&self, root: Root, sub_key: &str) -> (Root, String) {
+        match (root, self) {
+            (Root::User, Self::CurrentUser) => (Root::CurrentUser, sub_key.to_string()),
+            (Root::User, Self::Sid(sid)) if sub_key.is_empty() => (Root::Users, sid.clone()),
+            (Root::User, Self::Sid(sid)) => (Root::Users, format!("{sid}\\{sub_key}")),
+            _ => (root, sub_key.to_string()),
+        }
+    }
+}
+
+impl std::fmt::Display for Target {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::CurrentUser => "current user".fmt(f),
+            Self::Sid(sid) => sid.fmt(f),
+        }
+    }
+}
@@ -1,14 +1,284 @@
-#[derive(Debug, Clone)]
+use super::{DataType, Environment, Error, Result};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum Value {
+    Binary(Vec<u8>),
     DWord(u32),
+    QWord(u64),
     String(String),
+    /// 画面やファイル上では 1 行 1 要素で表す
+    MultiString(Vec<String>),
+    ExpandString(String),
+}
+
+impl Value {
+    /// 画面や設定ファイルから渡された文字列を `data_type` の値として解釈する
+    /// 数値は 10 進か `0x` 付きの 16 進、REG_BINARY は `01,02,ff` のような 16 進表記、
+    /// REG_MULTI_SZ は改行区切り
+    pub fn parse(data_type: DataType, value: &str) -> Result<Self> {
+        let ret = match data_type {
+            DataType::Binary => Self::Binary(parse_hex_bytes(value)?),
+            DataType::DWord => Self::DWord(u32::try_from(parse_number(value)?)?),
+            DataType::QWord => Self::QWord(parse_number(value)?),
+            DataType::String => Self::String(value.into()),
+            DataType::MultiString => {
+                Self::MultiString(value.lines().map(|x| x.to_string()).collect())
+            }
+            DataType::ExpandString => Self::ExpandString(value.into()),
+        };
+
+        Ok(ret)
+    }
+
+    pub fn data_type(&self) -> DataType {
+        match self {
+            Self::Binary(_) => DataType::Binary,
+            Self::DWord(_) => DataType::DWord,
+            Self::QWord(_) => DataType::QWord,
+            Self::String(_) => DataType::String,
+            Self::MultiString(_) => DataType::MultiString,
+            Self::ExpandString(_) => DataType::ExpandString,
+        }
+    }
+
+    /// REG_EXPAND_SZ の環境変数を展開した文字列 (それ以外の型は `None`)
+    pub fn expanded(&self, env: &impl Environment) -> Option<String> {
+        match self {
+            Self::ExpandString(v) => Some(super::expand(v, env)),
+            _ => None,
+        }
+    }
+
+    /// RegSetValueExW に渡すバイト列
+    pub fn to_bytes(&self) -> Vec<u8> {
+        match self {
+            Self::Binary(v) => v.clone(),
+            Self::DWord(v) => v.to_le_bytes().to_vec(),
+            Self::QWord(v) => v.to_le_bytes().to_vec(),
+            Self::String(v) | Self::ExpandString(v) => encode_utf16z(v),
+            Self::MultiString(v) => {
+                let mut ret = v.iter().flat_map(|x| encode_utf16z(x)).collect::<Vec<u8>>();
+                ret.extend_from_slice(&[0, 0]);
+                ret
+            }
+        }
+    }
+}
+
+impl Value {
+    /// レジストリから読み取ったデータを変換する
+    /// 固定長の型で長さが合わない場合は `Error::MalformedData` を返す
+    /// 文字列は終端の NUL が無くてもよく、最初の NUL までを値とする
+    pub fn from_bytes(data_type: DataType, buf: &[u8]) -> Result<Self> {
+        let malformed = || Error::MalformedData(data_type.str(), buf.len());
+        let ret = match data_type {
+            DataType::Binary => Self::Binary(buf.to_vec()),
+            DataType::DWord => {
+                Self::DWord(u32::from_le_bytes(buf.try_into().map_err(|_| malformed())?))
+            }
+            DataType::QWord => {
+                Self::QWord(u64::from_le_bytes(buf.try_into().map_err(|_| malformed())?))
+            }
+            DataType::String => Self::String(decode_utf16z(buf).ok_or_else(malformed)?),
+            DataType::ExpandString => Self::ExpandString(decode_utf16z(buf).ok_or_else(malformed)?),
+            DataType::MultiString => {
+                let units = utf16_units(buf).ok_or_else(malformed)?;
+                // 空の要素 (NUL の連続) で終わる。終端が無い場合はデータの最後まで
+                let items = units
+                    .split(|x| *x == 0)
+                    .take_while(|x| !x.is_empty())
+                    .map(String::from_utf16_lossy)
+                    .collect();
+                Self::MultiString(items)
+            }
+        };
+
+        Ok(ret)
+    }
 }
 
 impl std::fmt::Display for Value {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
+            Self::Binary(v) => {
+                let hex = v.iter().map(|x| format!("{x:02x}")).collect::<Vec<_>>();
+                hex.join(",").fmt(f)
+            }
             Self::DWord(v) => v.fmt(f),
-            Self::String(v) => v.fmt(f),
+            Self::QWord(v) => v.fmt(f),
+            Self::String(v) | Self::ExpandString(v) => v.fmt(f),
+            Self::MultiString(v) => v.join("\n").fmt(f),
+        }
+    }
+}
+
+fn parse_number(value: &str) -> Result<u64> {
+    let value = value.trim();
+    let ret = match value
+        .strip_prefix("0x")
+        .or_else(|| value.strip_prefix("0X"))
+    {
+        Some(hex) => u64::from_str_radix(hex, 16)?,
+        None => value.parse::<u64>()?,
+    };
+
+    Ok(ret)
+}
+
+fn parse_hex_bytes(value: &str) -> Result<Vec<u8>> {
+    let digits = value
+        .chars()
+        .filter(|x| !matches!(x, ',' | ' '))
+        .collect::<String>();
+    if !digits.is_ascii() || digits.len() % 2 != 0 {
+        return Err(Error::InvalidBinary(value.into()));
+    }
+
+    (0..digits.len())
+        .step_by(2)
+        .map(|i| {
+            u8::from_str_radix(&digits[i..i + 2], 16)
+                .map_err(|_| Error::InvalidBinary(value.into()))
+        })
+        .collect()
+}
+
+/// UTF-16LE のコード単位に分ける
+/// 奇数長は末尾の 1 バイトが 0 の場合だけ (NUL を 1 バイトで数えた書き込み) 受け付ける
+fn utf16_units(buf: &[u8]) -> Option<Vec<u16>> {
+    let buf = match buf.len() % 2 {
+        0 => buf,
+        _ if buf.last() == Some(&0) => &buf[..buf.len() - 1],
+        _ => return None,
+    };
+    Some(
+        buf.chunks_exact(2)
+            .map(|x| u16::from_le_bytes([x[0], x[1]]))
+            .collect(),
+    )
+}
+
+fn decode_utf16z(buf: &[u8]) -> Option<String> {
+    let units = utf16_units(buf)?;
+    let end = units.iter().position(|x| *x == 0).unwrap_or(units.len());
+    Some(String::from_utf16_lossy(&units[..end]))
+}
+
+fn encode_utf16z(value: &str) -> Vec<u8> {
+    value
+        .encode_utf16()
+        .chain(std::iter::once(0))
+        .flat_map(|x| x.to_le_bytes())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utf16(text: &str) -> Vec<u8> {
+        text.encode_utf16().flat_map(|x| x.to_le_bytes()).collect()
+    }
+
+    fn is_malformed(data_type: DataType, buf: &[u8]) -> bool {
+        matches!(
+            Value::from_bytes(data_type, buf),
+            Err(Error::MalformedData(_, n)) if n == buf.len()
+        )
+    }
+
+    #[test]
+    fn dword_requires_four_bytes() {
+        for len in [0, 2, 5, 8] {
+            assert!(is_malformed(DataType::DWord, &vec![1; len]), "{len} bytes");
+        }
+        assert_eq!(
+            Value::from_bytes(DataType::DWord, &[0x78, 0x56, 0x34, 0x12]).unwrap(),
+            Value::DWord(0x1234_5678)
+        );
+    }
+
+    #[test]
+    fn qword_requires_eight_bytes() {
+        for len in [0, 4, 7, 9] {
+            assert!(is_malformed(DataType::QWord, &vec![1; len]), "{len} bytes");
+        }
+        // リトルエンディアンで読む (上位の 4 バイトを落とさない)
+        let buf = [1, 0, 0, 0, 2, 0, 0, 0];
+        assert_eq!(
+            Value::from_bytes(DataType::QWord, &buf).unwrap(),
+            Value::QWord(0x2_0000_0001)
+        );
+    }
+
+    #[test]
+    fn string_without_terminator() {
+        let buf = utf16("abc");
+        assert_eq!(
+            Value::from_bytes(DataType::String, &buf).unwrap(),
+            Value::String("abc".into())
+        );
+        assert_eq!(
+            Value::from_bytes(DataType::ExpandString, &buf).unwrap(),
+            Value::ExpandString("abc".into())
+        );
+    }
+
+    #[test]
+    fn string_with_odd_length() {
+        // NUL を 1 バイトで数えた書き込みは受け付ける
+        let mut buf = utf16("abc");
+        buf.push(0);
+        assert_eq!(
+            Value::from_bytes(DataType::String, &buf).unwrap(),
+            Value::String("abc".into())
+        );
+
+        let mut buf = utf16("abc");
+        buf.push(b'd');
+        assert!(is_malformed(DataType::String, &buf));
+        assert!(is_malformed(DataType::MultiString, &buf));
+    }
+
+    #[test]
+    fn string_stops_at_embedded_nul() {
+        let buf = utf16("abc\0def\0");
+        assert_eq!(
+            Value::from_bytes(DataType::String, &buf).unwrap(),
+            Value::String("abc".into())
+        );
+    }
+
+    #[test]
+    fn multi_string_without_double_nul() {
+        let expected = Value::MultiString(vec!["a".into(), "bc".into()]);
+        for text in ["a\0bc\0\0", "a\0bc\0", "a\0bc"] {
+            assert_eq!(
+                Value::from_bytes(DataType::MultiString, &utf16(text)).unwrap(),
+                expected,
+                "{text:?}"
+            );
+        }
+        assert_eq!(
+            Value::from_bytes(DataType::MultiString, &[]).unwrap(),
+            Value::MultiString(Vec::new())
+        );
+    }
+
+    #[test]
+    fn round_trip() {
+        let values = [
+            Value::Binary(vec![0, 1, 0xff]),
+            Value::DWord(u32::MAX),
+            Value::QWord(u64::MAX),
+            Value::String("日本語".into()),
+            Value::ExpandString(r"%SystemRoot%\System32".into()),
+            Value::MultiString(vec!["a".into(), "b".into()]),
+        ];
+        for value in values {
+            let buf = value.to_bytes();
+            assert_eq!(Value::from_bytes(value.data_type(), &buf).unwrap(), value);
         }
     }
 }
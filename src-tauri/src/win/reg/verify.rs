@@ -0,0 +1,133 @@
+use super::{Root, Value};
+use serde::Serialize;
+
+/// 書き込んだ値を読み直した結果
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum WriteOutcome {
+    Applied,
+    /// 管理者権限の無い書き込みが HKCU の VirtualStore に振り替えられた
+    AppliedButVirtualized,
+    /// 読み直した値が書き込んだ値と異なる (Explorer などが書き戻した)
+    Reverted,
+}
+
+impl WriteOutcome {
+    /// 読み直した値と、書き込みの前後の VirtualStore の値から結果を決める
+    /// VirtualStore に以前の書き込みの値が残っていることがあるため、書き込みで変わった場合だけ仮想化とみなす
+    pub fn classify(
+        written: &Value,
+        read_back: Option<&Value>,
+        mirror_before: Option<&Value>,
+        mirror_after: Option<&Value>,
+    ) -> Self {
+        if read_back == Some(written) {
+            Self::Applied
+        } else if mirror_after == Some(written) && mirror_before != mirror_after {
+            Self::AppliedButVirtualized
+        } else {
+            Self::Reverted
+        }
+    }
+
+    pub fn is_applied(&self) -> bool {
+        *self == Self::Applied
+    }
+}
+
+impl std::fmt::Display for WriteOutcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let outcome = match self {
+            Self::Applied => "applied",
+            Self::AppliedButVirtualized => "applied but virtualized",
+            Self::Reverted => "reverted",
+        };
+
+        outcome.fmt(f)
+    }
+}
+
+/// UAC の仮想化で書き込みが振り替えられる HKCU のキー
+/// 仮想化されるのは `HKLM\SOFTWARE` 以下だけ (`sub_key` は 64 ビットのビューから見たパス)
+pub fn virtual_store_key(root: Root, sub_key: &str) -> Option<String> {
+    let (first, _) = sub_key.split_once('\\')?;
+    if root != Root::LocalMachine || !first.eq_ignore_ascii_case("SOFTWARE") {
+        return None;
+    }
+
+    Some(format!(r"Software\Classes\VirtualStore\MACHINE\{sub_key}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ON: Value = Value::DWord(1);
+    const OFF: Value = Value::DWord(0);
+
+    #[test]
+    fn applied_when_read_back_matches() {
+        assert_eq!(
+            WriteOutcome::classify(&ON, Some(&ON), None, None),
+            WriteOutcome::Applied
+        );
+        // 以前の書き込みで VirtualStore に残っている値は無視する
+        assert_eq!(
+            WriteOutcome::classify(&ON, Some(&ON), Some(&ON), Some(&ON)),
+            WriteOutcome::Applied
+        );
+        assert_eq!(
+            WriteOutcome::classify(&ON, Some(&ON), None, Some(&ON)),
+            WriteOutcome::Applied
+        );
+    }
+
+    #[test]
+    fn virtualized_when_mirror_changed_to_written_value() {
+        assert_eq!(
+            WriteOutcome::classify(&ON, Some(&OFF), None, Some(&ON)),
+            WriteOutcome::AppliedButVirtualized
+        );
+        assert_eq!(
+            WriteOutcome::classify(&ON, None, Some(&OFF), Some(&ON)),
+            WriteOutcome::AppliedButVirtualized
+        );
+    }
+
+    #[test]
+    fn reverted_otherwise() {
+        assert_eq!(
+            WriteOutcome::classify(&ON, Some(&OFF), None, None),
+            WriteOutcome::Reverted
+        );
+        assert_eq!(
+            WriteOutcome::classify(&ON, None, None, None),
+            WriteOutcome::Reverted
+        );
+        // VirtualStore の値が書き込みの前から同じなら、今回の書き込みは振り替えられていない
+        assert_eq!(
+            WriteOutcome::classify(&ON, Some(&OFF), Some(&ON), Some(&ON)),
+            WriteOutcome::Reverted
+        );
+        assert_eq!(
+            WriteOutcome::classify(&ON, Some(&OFF), Some(&ON), Some(&OFF)),
+            WriteOutcome::Reverted
+        );
+    }
+
+    #[test]
+    fn only_hklm_software_is_virtualized() {
+        assert_eq!(
+            virtual_store_key(Root::LocalMachine, r"SOFTWARE\Vendor").as_deref(),
+            Some(r"Software\Classes\VirtualStore\MACHINE\SOFTWARE\Vendor")
+        );
+        assert_eq!(
+            virtual_store_key(Root::LocalMachine, r"SYSTEM\CurrentControlSet"),
+            None
+        );
+        assert_eq!(
+            virtual_store_key(Root::CurrentUser, r"Software\Vendor"),
+            None
+        );
+        assert_eq!(virtual_store_key(Root::LocalMachine, "SOFTWARE"), None);
+    }
+}
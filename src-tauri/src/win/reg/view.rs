@@ -0,0 +1,317 @@
+use super::Root;
+use serde::{Deserialize, Serialize};
+use windows::Win32::System::Registry::{KEY_WOW64_32KEY, KEY_WOW64_64KEY, REG_SAM_FLAGS};
+
+/// 32 ビットのビューで `Wow6432Node` に振り替えられるクラス登録のキー
+const REDIRECTED_CLASSES: [&str; 5] = [
+    "CLSID",
+    "DirectShow",
+    "Interface",
+    "Media Type",
+    "MediaFoundation",
+];
+
+/// `HKLM` 以下で 32 ビットと 64 ビットのビューが共有するキー (サブキーも含む)
+/// "Registry Keys Affected by WOW64" の一覧のうち、Windows 7 以降も共有されているもの
+const SHARED_KEYS: [&str; 26] = [
+    r"SOFTWARE\Classes\HCP",
+    r"SOFTWARE\Microsoft\COM3",
+    r"SOFTWARE\Microsoft\DFS",
+    r"SOFTWARE\Microsoft\EventSystem",
+    r"SOFTWARE\Microsoft\OLE",
+    r"SOFTWARE\Microsoft\RPC",
+    r"SOFTWARE\Microsoft\SystemCertificates",
+    r"SOFTWARE\Microsoft\Windows\CurrentVersion\App Paths",
+    r"SOFTWARE\Microsoft\Windows\CurrentVersion\Explorer\AutoplayHandlers",
+    r"SOFTWARE\Microsoft\Windows\CurrentVersion\Explorer\DriveIcons",
+    r"SOFTWARE\Microsoft\Windows\CurrentVersion\Explorer\KindMap",
+    r"SOFTWARE\Microsoft\Windows\CurrentVersion\Group Policy",
+    r"SOFTWARE\Microsoft\Windows\CurrentVersion\Policies",
+    r"SOFTWARE\Microsoft\Windows\CurrentVersion\PreviewHandlers",
+    r"SOFTWARE\Microsoft\Windows\CurrentVersion\Setup",
+    r"SOFTWARE\Microsoft\Windows NT\CurrentVersion\Console",
+    r"SOFTWARE\Microsoft\Windows NT\CurrentVersion\FontLink",
+    r"SOFTWARE\Microsoft\Windows NT\CurrentVersion\Fonts",
+    r"SOFTWARE\Microsoft\Windows NT\CurrentVersion\FontSubstitutes",
+    r"SOFTWARE\Microsoft\Windows NT\CurrentVersion\NetworkCards",
+    r"SOFTWARE\Microsoft\Windows NT\CurrentVersion\Perflib",
+    r"SOFTWARE\Microsoft\Windows NT\CurrentVersion\Print",
+    r"SOFTWARE\Microsoft\Windows NT\CurrentVersion\ProfileList",
+    r"SOFTWARE\Microsoft\Windows NT\CurrentVersion\Time Zones",
+    r"SOFTWARE\Policies",
+    r"SOFTWARE\RegisteredApplications",
+];
+
+const WOW64_NODE: &str = "WOW6432Node";
+
+/// WOW64 のレジストリビュー
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum View {
+    /// プロセスのビット数に合わせる
+    #[default]
+    Native,
+    Bit64,
+    Bit32,
+    /// 両方のビューに書き込む (読み取りは 64 ビットのビュー)
+    Both,
+}
+
+impl View {
+    pub fn is_native(&self) -> bool {
+        *self == Self::Native
+    }
+
+    /// 書き込むビュー (先頭が読み取りに使うビュー)
+    pub fn targets(&self) -> &'static [View] {
+        match self {
+            Self::Native => &[Self::Native],
+            Self::Bit64 => &[Self::Bit64],
+            Self::Bit32 => &[Self::Bit32],
+            Self::Both => &[Self::Bit64, Self::Bit32],
+        }
+    }
+
+    /// キーを開くときに追加するアクセス権
+    pub(crate) fn sam(&self) -> REG_SAM_FLAGS {
+        match self {
+            Self::Native => REG_SAM_FLAGS(0),
+            Self::Bit64 | Self::Both => KEY_WOW64_64KEY,
+            Self::Bit32 => KEY_WOW64_32KEY,
+        }
+    }
+
+    /// 読み取りで実際に参照するのが 32 ビットのビューか
+    /// (Windows 11 は 64 ビット版だけなので、`Native` はプロセスのビット数で決まる)
+    pub fn is_32bit(&self, is_64bit_process: bool) -> bool {
+        match self {
+            Self::Native => !is_64bit_process,
+            Self::Bit32 => true,
+            Self::Bit64 | Self::Both => false,
+        }
+    }
+}
+
+impl std::fmt::Display for View {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let view = match self {
+            Self::Native => "native",
+            Self::Bit64 => "64-bit",
+            Self::Bit32 => "32-bit",
+            Self::Both => "64-bit and 32-bit",
+        };
+
+        view.fmt(f)
+    }
+}
+
+/// 32 ビットのビューでのキーのパスを 64 ビットのビューから見たパスに変換する
+/// リダイレクトされないキーはそのまま返す
+///
+/// - `HKLM\SOFTWARE` 以下は `HKLM\SOFTWARE\WOW6432Node` 以下 (`SOFTWARE\Policies` などの共有キーを除く)
+/// - `SOFTWARE\Classes` のうち CLSID などは `SOFTWARE\Classes\WOW6432Node` 以下
+/// - `HKCU` と `HKU` はクラス登録だけ
+pub fn redirect(root: Root, sub_key: &str) -> String {
+    let parts: Vec<&str> = sub_key.split('\\').collect();
+    let pos = match root {
+        Root::LocalMachine => match parts.as_slice() {
+            [software, classes, ..]
+                if software.eq_ignore_ascii_case("SOFTWARE")
+                    && classes.eq_ignore_ascii_case("Classes") =>
+            {
+                classes_pos(&parts, 0)
+            }
+            [software, _, ..]
+                if software.eq_ignore_ascii_case("SOFTWARE") && !is_shared(&parts) =>
+            {
+                Some(1)
+            }
+            _ => None,
+        },
+        Root::CurrentUser | Root::User => classes_pos(&parts, 0),
+        Root::Users => match parts.as_slice() {
+            [user, name, ..]
+                if user.to_ascii_lowercase().ends_with("_classes") && is_redirected_class(name) =>
+            {
+                Some(1)
+            }
+            _ => classes_pos(&parts, 1),
+        },
+    };
+
+    match pos {
+        // 既に WOW6432Node を含むパスは振り替えない
+        Some(pos) if !parts[pos].eq_ignore_ascii_case(WOW64_NODE) => {
+            let mut ret = parts[..pos].to_vec();
+            ret.push(WOW64_NODE);
+            ret.extend_from_slice(&parts[pos..]);
+            ret.join("\\")
+        }
+        _ => sub_key.to_string(),
+    }
+}
+
+/// 64 ビットのビューから見た、実際に読み書きされるキーのパス
+pub fn physical_sub_key(root: Root, sub_key: &str, view: View, is_64bit_process: bool) -> String {
+    if view.is_32bit(is_64bit_process) {
+        redirect(root, sub_key)
+    } else {
+        sub_key.to_string()
+    }
+}
+
+/// `Software\Classes\<name>` の `<name>` の位置 (`skip` は `Software` の位置)
+fn classes_pos(parts: &[&str], skip: usize) -> Option<usize> {
+    match parts.get(skip..)? {
+        [software, classes, name, ..]
+            if software.eq_ignore_ascii_case("Software")
+                && classes.eq_ignore_ascii_case("Classes")
+                && is_redirected_class(name) =>
+        {
+            Some(skip + 2)
+        }
+        _ => None,
+    }
+}
+
+/// `HKLM` 以下の共有キーか、そのサブキーか
+fn is_shared(parts: &[&str]) -> bool {
+    SHARED_KEYS.iter().any(|key| {
+        let key = key.split('\\').collect::<Vec<_>>();
+        parts.len() >= key.len()
+            && key
+                .iter()
+                .zip(parts)
+                .all(|(a, b)| a.eq_ignore_ascii_case(b))
+    })
+}
+
+fn is_redirected_class(name: &str) -> bool {
+    name.eq_ignore_ascii_case(WOW64_NODE)
+        || REDIRECTED_CLASSES
+            .iter()
+            .any(|x| x.eq_ignore_ascii_case(name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn machine_software() {
+        assert_eq!(
+            redirect(
+                Root::LocalMachine,
+                r"SOFTWARE\Microsoft\Windows\CurrentVersion\Run"
+            ),
+            r"SOFTWARE\WOW6432Node\Microsoft\Windows\CurrentVersion\Run"
+        );
+        assert_eq!(
+            redirect(Root::LocalMachine, r"software\Vendor"),
+            r"software\WOW6432Node\Vendor"
+        );
+        // SOFTWARE 直下の値や SOFTWARE 以外は振り替えない
+        assert_eq!(redirect(Root::LocalMachine, "SOFTWARE"), "SOFTWARE");
+        assert_eq!(
+            redirect(Root::LocalMachine, r"SYSTEM\CurrentControlSet"),
+            r"SYSTEM\CurrentControlSet"
+        );
+    }
+
+    #[test]
+    fn machine_shared_keys() {
+        for key in [
+            r"SOFTWARE\Policies\Microsoft\Windows\Explorer",
+            r"Software\policies",
+            r"SOFTWARE\Microsoft\Windows\CurrentVersion\Policies\Explorer",
+            r"SOFTWARE\Microsoft\Windows NT\CurrentVersion\ProfileList",
+        ] {
+            assert_eq!(redirect(Root::LocalMachine, key), key);
+        }
+        // 名前が前方一致するだけのキーは共有ではない
+        assert_eq!(
+            redirect(Root::LocalMachine, r"SOFTWARE\PoliciesExtra"),
+            r"SOFTWARE\WOW6432Node\PoliciesExtra"
+        );
+    }
+
+    #[test]
+    fn machine_classes() {
+        assert_eq!(
+            redirect(
+                Root::LocalMachine,
+                r"SOFTWARE\Classes\CLSID\{0}\InprocServer32"
+            ),
+            r"SOFTWARE\Classes\WOW6432Node\CLSID\{0}\InprocServer32"
+        );
+        // CLSID などのクラス登録以外は共有
+        assert_eq!(
+            redirect(Root::LocalMachine, r"SOFTWARE\Classes\*\shell"),
+            r"SOFTWARE\Classes\*\shell"
+        );
+    }
+
+    #[test]
+    fn user_classes() {
+        assert_eq!(
+            redirect(Root::CurrentUser, r"Software\Classes\CLSID\{0}"),
+            r"Software\Classes\WOW6432Node\CLSID\{0}"
+        );
+        assert_eq!(
+            redirect(Root::CurrentUser, r"Software\Microsoft\Notepad"),
+            r"Software\Microsoft\Notepad"
+        );
+        assert_eq!(
+            redirect(Root::Users, r"S-1-5-21-1\Software\Classes\Interface\{0}"),
+            r"S-1-5-21-1\Software\Classes\WOW6432Node\Interface\{0}"
+        );
+        assert_eq!(
+            redirect(Root::Users, r"S-1-5-21-1_Classes\CLSID\{0}"),
+            r"S-1-5-21-1_Classes\WOW6432Node\CLSID\{0}"
+        );
+        assert_eq!(
+            redirect(Root::Users, r"S-1-5-21-1_Classes\*\shell"),
+            r"S-1-5-21-1_Classes\*\shell"
+        );
+    }
+
+    #[test]
+    fn already_redirected() {
+        for (root, key) in [
+            (Root::LocalMachine, r"SOFTWARE\WOW6432Node\Vendor"),
+            (
+                Root::LocalMachine,
+                r"SOFTWARE\Classes\Wow6432Node\CLSID\{0}",
+            ),
+            (Root::CurrentUser, r"Software\Classes\WOW6432Node\CLSID\{0}"),
+            (Root::Users, r"S-1-5-21-1_Classes\WOW6432Node\CLSID\{0}"),
+        ] {
+            assert_eq!(redirect(root, key), key);
+        }
+    }
+
+    #[test]
+    fn physical_key_follows_view() {
+        let key = r"SOFTWARE\Vendor";
+        let redirected = r"SOFTWARE\WOW6432Node\Vendor";
+        assert_eq!(
+            physical_sub_key(Root::LocalMachine, key, View::Bit64, false),
+            key
+        );
+        assert_eq!(
+            physical_sub_key(Root::LocalMachine, key, View::Bit32, true),
+            redirected
+        );
+        assert_eq!(
+            physical_sub_key(Root::LocalMachine, key, View::Native, true),
+            key
+        );
+        assert_eq!(
+            physical_sub_key(Root::LocalMachine, key, View::Native, false),
+            redirected
+        );
+        assert_eq!(
+            physical_sub_key(Root::LocalMachine, key, View::Both, false),
+            key
+        );
+    }
+}
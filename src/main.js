@@ -5,18 +5,378 @@ function corelog(text) {
     invoke("log", { "text": text });
 }
 
+// 分類ごとの枠を返す (無ければ作成する)
+function category_section(name) {
+    const container = document.getElementById("container");
+    const found = [...container.querySelectorAll(".category")].find(x => x.dataset.category === name);
+    if (found) {
+        return found;
+    }
+
+    const section = document.createElement("section");
+    section.className = "category";
+    section.dataset.category = name;
+    const header = document.createElement("div");
+    header.className = "category-header";
+    const title = document.createElement("span");
+    title.textContent = name;
+    const reset = document.createElement("button");
+    reset.className = "button";
+    reset.textContent = "この分類を初期状態に戻す";
+    reset.addEventListener("click", () => request_reset({ "kind": "category", "value": name }));
+    header.append(title, reset);
+    section.appendChild(header);
+    container.appendChild(section);
+    return section;
+}
+
 function add_component(html) {
-    const groupContainer = document.getElementById("container");
     const parser = new DOMParser();
     const doc = parser.parseFromString(html, "text/html");
     const group = doc.body.firstChild;
 
     if (group) {
-        groupContainer.appendChild(group);
+        category_section(group.dataset.category).appendChild(group);
+    }
+}
+
+// 検索結果に含まれない項目と、表示する項目が無い分類を隠す
+function filter_components(ids) {
+    const visible = new Set(ids);
+    document.querySelectorAll(".group").forEach((group) => {
+        group.hidden = !visible.has(Number(group.dataset.cmdid));
+    });
+    document.querySelectorAll(".category").forEach((section) => {
+        section.hidden = !section.querySelector(".group:not([hidden])");
+    });
+}
+
+const LAYER_NAMES = { "machine-policy": "コンピューターのポリシー", "user-policy": "ユーザーのポリシー" };
+const ROOT_NAMES = { "CurrentUser": "HKCU", "Users": "HKU", "LocalMachine": "HKLM", "User": "HKCU" };
+
+function format_value(v) {
+    if (v === null || v === undefined) {
+        return "(なし)";
+    }
+    return String(Object.values(v)[0]);
+}
+
+function format_target(target) {
+    const key = `${ROOT_NAMES[target.root] ?? target.root}\\${target.sub_key}`;
+    return target.value_name === "" ? key : `${key}\\${target.value_name}`;
+}
+
+function format_plan_entry(entry) {
+    const action = entry.action;
+    switch (action.kind) {
+        case "create-key":
+            return `キーを作成: ${format_target({ ...entry.target, value_name: "" })}`;
+        case "set-value":
+            return `値を設定: ${format_target(entry.target)} (${format_value(action.old)} → ${format_value(action.new)})`;
+        case "delete-value":
+            return `値を削除: ${format_target(entry.target)} (${format_value(action.old)})`;
+        // レジストリ以外の項目は値が文字列のまま渡される
+        case "apply-tweak":
+            return `設定を変更: ${action.subject} (${action.old ?? "(なし)"} → ${action.new ?? "(なし)"})`;
+        default:
+            return entry.target ? `変更なし: ${format_target(entry.target)}` : "変更なし";
+    }
+}
+
+// 値を削除する候補は null、自由入力欄は入力された文字列
+// チェックボックスは操作されるまで、候補の一覧は「変更しない」の間は未選択として扱う
+function selected_value(combobox) {
+    if (combobox.type === "checkbox") {
+        if (!combobox.dataset.dirty) {
+            return undefined;
+        }
+        return combobox.checked ? "1" : "0";
+    }
+    if (combobox.tagName === "SELECT") {
+        const option = combobox.selectedOptions[0];
+        if (!option || option.dataset.keep) {
+            return undefined;
+        }
+        return option.dataset.absent ? null : option.value;
+    }
+    return combobox.value === "" ? undefined : combobox.value;
+}
+
+// レジストリ以外の項目も同じ計画に含める (計画の作成時に項目の種類で振り分ける)
+function collect_selections() {
+    const selections = {};
+    document.querySelectorAll(".group").forEach((group) => {
+        const combobox = group.querySelector(".combobox");
+        const value = combobox ? selected_value(combobox) : undefined;
+        if (value !== undefined) {
+            selections[group.dataset.cmdid] = value;
+        }
+    });
+    return selections;
+}
+
+function show_plan(plan) {
+    const dialog = document.getElementById("plan-dialog");
+    const list = document.getElementById("plan-list");
+    list.replaceChildren();
+    plan.entries.forEach((entry) => {
+        const item = document.createElement("li");
+        item.textContent = `${entry.label} - ${format_plan_entry(entry)}`;
+        if (entry.action.kind === "no-op") {
+            item.classList.add("plan-noop");
+        }
+        list.appendChild(item);
+    });
+    (plan.warnings ?? []).forEach((warning) => {
+        const item = document.createElement("li");
+        item.textContent = `警告: ${warning.message}`;
+        item.classList.add("plan-warning");
+        list.appendChild(item);
+    });
+
+    const apply = document.getElementById("button-plan-apply");
+    apply.disabled = !plan.entries.some(x => x.action.kind !== "no-op");
+    apply.onclick = () => {
+        const task_id = Date.now();
+        set_task_running("plan", task_id);
+        invoke("apply_plan", { "taskId": task_id, "plan": plan })
+            .then(() => dialog.close())
+            .catch(e => {
+                corelog(`Failed to apply plan: ${e}`);
+                alert(e);
+            })
+            .finally(() => set_task_running("plan", null));
+    };
+    set_task_running("plan", null);
+    dialog.showModal();
+}
+
+// 処理中は進み具合と中断ボタンを表示し、他のボタンを隠す
+// `name` はダイアログ内の要素の ID の接頭辞 ("plan", "menu", "startup")
+const TASK_BUTTONS = {
+    "plan": ["button-plan-apply", "button-plan-cancel"],
+    "menu": ["button-menu-revert", "button-menu-close"],
+    "startup": ["button-startup-close"],
+};
+let running_task = null;
+
+function set_task_running(name, task_id) {
+    running_task = task_id === null ? null : { "id": task_id, "name": name };
+    const running = task_id !== null;
+    document.getElementById(`${name}-progress`).hidden = !running;
+    document.getElementById(`button-${name}-abort`).hidden = !running;
+    TASK_BUTTONS[name].forEach(x => document.getElementById(x).hidden = running);
+    if (running) {
+        on_task_progress({ "task_id": task_id, "step": 0, "total": 1, "key": "" });
+    }
+}
+
+function on_task_progress(progress) {
+    if (progress.task_id !== running_task?.id) {
+        return;
+    }
+    const name = running_task.name;
+    const bar = document.getElementById(`${name}-progress-bar`);
+    bar.max = progress.total;
+    bar.value = progress.step;
+    document.getElementById(`${name}-progress-key`).textContent =
+        progress.key === "" ? "" : `${progress.step} / ${progress.total}: ${progress.key}`;
+}
+
+// 一覧を読み取る間もダイアログを開いて進み具合を表示する
+function load_list(name, command, show) {
+    const dialog = document.getElementById(`${name}-dialog`);
+    document.getElementById(`${name}-list`).replaceChildren();
+    const task_id = Date.now();
+    set_task_running(name, task_id);
+    dialog.showModal();
+    invoke(command, { "taskId": task_id })
+        .then(show)
+        .catch(e => {
+            corelog(`Failed to invoke ${command}: ${e}`);
+            dialog.close();
+            alert(e);
+        })
+        .finally(() => set_task_running(name, null));
+}
+
+function on_value_changed(change) {
+    const group = document.querySelector(`.group[data-cmdid="${change.id}"]`);
+    const textbox = group?.querySelector(".textbox");
+    if (!textbox) {
+        return;
+    }
+
+    if (change.error) {
+        textbox.value = `エラー: ${change.error}`;
+    } else if (change.value === null) {
+        textbox.value = "現在の値: (なし)";
+    } else if (change.description !== null) {
+        textbox.value = `現在の値: ${change.value}: ${change.description}`;
+    } else {
+        textbox.value = `現在の値: ${change.value}`;
+    }
+    // REG_EXPAND_SZ は展開前と展開後の両方を表示する
+    if (!change.error && change.expanded !== null && change.expanded !== change.value) {
+        textbox.value += ` (展開後: ${change.expanded})`;
     }
+    if (change.missing_paths.length > 0) {
+        textbox.value += ` [存在しないパス: ${change.missing_paths.join(", ")}]`;
+    }
+    if (change.layer && change.layer !== "user-preference") {
+        textbox.value += ` (${LAYER_NAMES[change.layer]}により固定)`;
+    }
+
+    const toggle = group.querySelector(".combobox[type=checkbox]");
+    if (toggle && !toggle.dataset.dirty && !change.error) {
+        toggle.checked = change.value === "1";
+    }
+}
+
+// すべての項目の現在の状態を 1 回の呼び出しで取得する
+function refresh_values() {
+    const ids = [...document.querySelectorAll(".group")].map(x => Number(x.dataset.cmdid));
+    return invoke("get_values", { "ids": ids })
+        .then((changes) => changes.forEach(on_value_changed));
+}
+
+const LOCATION_NAMES = { "all-files": "すべてのファイル", "directory": "フォルダー", "background": "フォルダーの背景" };
+
+// 右クリックメニューの項目を一覧に表示する (チェックを外すと無効にする)
+function show_context_menu(entries) {
+    const list = document.getElementById("menu-list");
+    list.replaceChildren();
+    entries.forEach((entry) => {
+        const item = document.createElement("li");
+        const label = document.createElement("label");
+        const check = document.createElement("input");
+        check.type = "checkbox";
+        check.checked = entry.disabled_by.length === 0;
+        check.addEventListener("change", () => {
+            check.disabled = true;
+            invoke("set_context_menu_entry", { "id": entry.id, "enabled": check.checked })
+                .then(show_context_menu)
+                .catch(e => {
+                    corelog(`Failed to update context menu: ${e}`);
+                    alert(e);
+                    check.checked = !check.checked;
+                    check.disabled = false;
+                });
+        });
+        const scope = entry.scope === "user" ? " (ユーザー)" : "";
+        label.append(check, `${LOCATION_NAMES[entry.location]}: ${entry.text ?? entry.name}${scope}`);
+        const detail = document.createElement("div");
+        detail.className = "menu-detail";
+        detail.textContent = [entry.friendly_name, entry.clsid, entry.dll].filter(x => x).join(" / ");
+        item.append(label, detail);
+        list.appendChild(item);
+    });
+}
+
+const STARTUP_SOURCE_NAMES = {
+    "user-run": "Run (ユーザー)",
+    "user-run-once": "RunOnce (ユーザー)",
+    "machine-run": "Run",
+    "machine-run32": "Run (32 ビット)",
+    "machine-run-once": "RunOnce",
+    "user-folder": "スタートアップ フォルダー (ユーザー)",
+    "common-folder": "スタートアップ フォルダー",
+};
+
+// スタートアップ アプリを一覧に表示する (RunOnce は切り替えられない)
+function show_startup_apps(entries) {
+    const list = document.getElementById("startup-list");
+    list.replaceChildren();
+    entries.forEach((entry) => {
+        const item = document.createElement("li");
+        const label = document.createElement("label");
+        const check = document.createElement("input");
+        check.type = "checkbox";
+        check.checked = entry.enabled;
+        check.disabled = !entry.toggleable;
+        check.addEventListener("change", () => {
+            check.disabled = true;
+            invoke("set_startup_app", { "id": entry.id, "enabled": check.checked })
+                .then(show_startup_apps)
+                .catch(e => {
+                    corelog(`Failed to update startup app: ${e}`);
+                    alert(e);
+                    check.checked = !check.checked;
+                    check.disabled = false;
+                });
+        });
+        label.append(check, `${entry.name} (${STARTUP_SOURCE_NAMES[entry.source]})`);
+        const detail = document.createElement("div");
+        detail.className = "menu-detail";
+        detail.textContent = entry.disabled_at === null
+            ? entry.command
+            : `${entry.command} / 無効にした日時: ${new Date(entry.disabled_at * 1000).toLocaleString()}`;
+        item.append(label, detail);
+        list.appendChild(item);
+    });
+}
+
+// 初期状態に戻す計画を作成して確認ダイアログを表示する
+function request_reset(scope) {
+    invoke("plan_reset", { "scope": scope })
+        .then(show_plan)
+        .catch(e => {
+            corelog(`Failed to create reset plan: ${e}`);
+            alert(e);
+        });
 }
 
 document.addEventListener("DOMContentLoaded", () => {
+    listen("registry-value-changed", (event) => on_value_changed(event.payload));
+    listen("task-progress", (event) => on_task_progress(event.payload));
+
+    document.getElementById("button-plan").addEventListener("click", () => {
+        invoke("plan_registry_values", { "selections": collect_selections() })
+            .then(show_plan)
+            .catch(e => {
+                corelog(`Failed to create plan: ${e}`);
+                alert(e);
+            });
+    });
+    document.getElementById("search").addEventListener("input", (event) => {
+        invoke("search_options", { "query": event.target.value })
+            .then(filter_components)
+            .catch(e => corelog(`Failed to search: ${e}`));
+    });
+    document.getElementById("button-reset-all").addEventListener("click", () => {
+        request_reset({ "kind": "all" });
+    });
+    ["plan", "menu", "startup"].forEach((name) => {
+        document.getElementById(`button-${name}-abort`).addEventListener("click", () => {
+            if (running_task !== null) {
+                invoke("cancel_task", { "taskId": running_task.id });
+            }
+        });
+    });
+    document.getElementById("button-context-menu").addEventListener("click", () => {
+        load_list("menu", "get_context_menu", show_context_menu);
+    });
+    document.getElementById("button-menu-revert").addEventListener("click", () => {
+        invoke("revert_context_menu", { "id": null })
+            .then(show_context_menu)
+            .catch(e => {
+                corelog(`Failed to revert context menu: ${e}`);
+                alert(e);
+            });
+    });
+    document.getElementById("button-startup").addEventListener("click", () => {
+        load_list("startup", "get_startup_apps", show_startup_apps);
+    });
+    document.getElementById("button-startup-close").addEventListener("click", () => {
+        document.getElementById("startup-dialog").close();
+    });
+    document.getElementById("button-menu-close").addEventListener("click", () => {
+        document.getElementById("menu-dialog").close();
+    });
+    document.getElementById("button-plan-cancel").addEventListener("click", () => {
+        document.getElementById("plan-dialog").close();
+    });
+
     invoke("get_default_components")
         .then((data) => {
             data.forEach(x => add_component(x));
@@ -34,18 +394,35 @@ document.addEventListener("DOMContentLoaded", () => {
                     return;
                 }
 
+                if (combobox?.type === "checkbox") {
+                    combobox.addEventListener("change", () => {
+                        combobox.dataset.dirty = "1";
+                    });
+                }
+
                 if (button1) {
                     button1.addEventListener("click", () => {
                         invoke("get_registry_value", { "cmdId": cmd_id });
                     });
                 }
 
+                const button3 = group.querySelector(".button-reset");
+                if (button3) {
+                    button3.addEventListener("click", () => {
+                        request_reset({ "kind": "option", "value": cmd_id });
+                    });
+                }
+
                 if (button2) {
                     button2.addEventListener("click", () => {
-                        invoke("set_registry_value", { "cmdId": cmd_id, "value": combobox.value });
+                        const value = selected_value(combobox);
+                        if (value !== undefined) {
+                            invoke("set_registry_value", { "cmdId": cmd_id, "value": value });
+                        }
                     });
                 }
             });
         })
+        .then(refresh_values)
         .catch(e => console.log(JSON.stringify(e)))
 });